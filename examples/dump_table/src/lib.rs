@@ -127,21 +127,31 @@ impl<'h> FileAndPathHelper<'h> for Helper {
         // Also consider .so.dbg files in the symbol directory.
         if debug_name.ends_with(".so") {
             let debug_debug_name = format!("{}.dbg", debug_name);
-            paths.push(CandidatePathInfo::SingleFile(FileLocation::Path(
-                self.symbol_directory.join(debug_debug_name),
-            )));
+            let debug_file = FileLocation::Path(self.symbol_directory.join(debug_debug_name));
+            // Prefer combining the debug file with the binary, so that the symbols
+            // of both files are used.
+            paths.push(CandidatePathInfo::BinaryWithDebugFile {
+                binary: FileLocation::Path(self.symbol_directory.join(debug_name)),
+                debug_file: debug_file.clone(),
+            });
+            paths.push(CandidatePathInfo::SingleFile(debug_file));
         }
 
         // And dSYM packages.
         if !debug_name.ends_with(".pdb") {
-            paths.push(CandidatePathInfo::SingleFile(FileLocation::Path(
+            let debug_file = FileLocation::Path(
                 self.symbol_directory
                     .join(&format!("{}.dSYM", debug_name))
                     .join("Contents")
                     .join("Resources")
                     .join("DWARF")
                     .join(debug_name),
-            )));
+            );
+            paths.push(CandidatePathInfo::BinaryWithDebugFile {
+                binary: FileLocation::Path(self.symbol_directory.join(debug_name)),
+                debug_file: debug_file.clone(),
+            });
+            paths.push(CandidatePathInfo::SingleFile(debug_file));
         }
 
         // Finally, the file itself.
@@ -258,7 +268,7 @@ mod test {
         ));
         assert!(result.is_ok());
         let result = result.unwrap();
-        assert_eq!(result.addr.len(), 947);
+        assert_eq!(result.addr.len(), 1011);
 
         // Test an export symbol.
         assert_eq!(result.addr[430], 0x34670);
@@ -277,6 +287,15 @@ mod test {
             ),
             Ok("fun_56420")
         );
+
+        // Test an import thunk.
+        assert_eq!(result.addr[930], 0x7ff70);
+        assert_eq!(
+            std::str::from_utf8(
+                &result.buffer[result.index[930] as usize..result.index[931] as usize]
+            ),
+            Ok("[thunk] NtQueryVirtualMemory")
+        );
     }
 
     #[test]
@@ -368,7 +387,7 @@ mod test {
         ));
         assert!(result.is_ok());
         let result = result.unwrap();
-        assert_eq!(result.addr.len(), 13);
+        assert_eq!(result.addr.len(), 61);
         assert_eq!(result.addr[9], 0x2730);
         assert_eq!(
             std::str::from_utf8(
@@ -376,6 +395,13 @@ mod test {
             ),
             Ok("__ZN7mozilla20ProfileChunkedBuffer17ResetChunkManagerEv")
         );
+        assert_eq!(result.addr[15], 0x2998);
+        assert_eq!(
+            std::str::from_utf8(
+                &result.buffer[result.index[15] as usize..result.index[16] as usize]
+            ),
+            Ok("stub for _CFRelease")
+        );
     }
 
     #[test]
@@ -387,7 +413,7 @@ mod test {
         ));
         assert!(result.is_ok());
         let result = result.unwrap();
-        assert_eq!(result.addr.len(), 13);
+        assert_eq!(result.addr.len(), 61);
         assert_eq!(result.addr[9], 0x759c);
         assert_eq!(
             std::str::from_utf8(
@@ -6,6 +6,7 @@ use profiler_get_symbols::{
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::time::UNIX_EPOCH;
 
 pub async fn query_api(request_url: &str, request_json: &str, symbol_directory: PathBuf) -> String {
     let helper = Helper { symbol_directory };
@@ -29,21 +30,31 @@ impl<'h> FileAndPathHelper<'h> for Helper {
         // Also consider .so.dbg files in the symbol directory.
         if debug_name.ends_with(".so") {
             let debug_debug_name = format!("{}.dbg", debug_name);
-            paths.push(CandidatePathInfo::SingleFile(FileLocation::Path(
-                self.symbol_directory.join(debug_debug_name),
-            )));
+            let debug_file = FileLocation::Path(self.symbol_directory.join(debug_debug_name));
+            // Prefer combining the debug file with the binary, so that the symbols
+            // of both files are used.
+            paths.push(CandidatePathInfo::BinaryWithDebugFile {
+                binary: FileLocation::Path(self.symbol_directory.join(debug_name)),
+                debug_file: debug_file.clone(),
+            });
+            paths.push(CandidatePathInfo::SingleFile(debug_file));
         }
 
         // And dSYM packages.
         if !debug_name.ends_with(".pdb") {
-            paths.push(CandidatePathInfo::SingleFile(FileLocation::Path(
+            let debug_file = FileLocation::Path(
                 self.symbol_directory
                     .join(&format!("{}.dSYM", debug_name))
                     .join("Contents")
                     .join("Resources")
                     .join("DWARF")
                     .join(debug_name),
-            )));
+            );
+            paths.push(CandidatePathInfo::BinaryWithDebugFile {
+                binary: FileLocation::Path(self.symbol_directory.join(debug_name)),
+                debug_file: debug_file.clone(),
+            });
+            paths.push(CandidatePathInfo::SingleFile(debug_file));
         }
 
         // Finally, the file itself.
@@ -87,26 +98,40 @@ impl<'h> FileAndPathHelper<'h> for Helper {
             Ok(unsafe { memmap2::MmapOptions::new().map(&file)? })
         }
 
-        let mut path = match location {
+        let path = match location {
             FileLocation::Path(path) => path.clone(),
             FileLocation::Custom(_) => panic!("Unexpected FileLocation::Custom"),
         };
-        if !path.starts_with(&self.symbol_directory) {
-            // See if this file exists in self.symbol_directory.
-            // For example, when looking up object files referenced by mach-O binaries,
-            // we want to take the object files from the symbol directory if they exist,
-            // rather than from the original path.
-            if let Some(filename) = path.file_name() {
-                let redirected_path = self.symbol_directory.join(filename);
-                if std::fs::metadata(&redirected_path).is_ok() {
-                    // redirected_path exists!
-                    eprintln!("Redirecting {:?} to {:?}", &path, &redirected_path);
-                    path = redirected_path;
-                }
+        Box::pin(read_file_impl(path))
+    }
+
+    fn get_candidate_paths_for_object_file(
+        &self,
+        object_path: &Path,
+        _archive_member_name: Option<&str>,
+    ) -> FileAndPathHelperResult<Vec<FileLocation>> {
+        let mut paths = vec![];
+
+        // When looking up object files referenced by mach-O binaries, we want to
+        // take the object files from the symbol directory if they exist, rather
+        // than from the original path.
+        if !object_path.starts_with(&self.symbol_directory) {
+            if let Some(filename) = object_path.file_name() {
+                paths.push(FileLocation::Path(self.symbol_directory.join(filename)));
             }
         }
+        paths.push(FileLocation::Path(object_path.to_path_buf()));
 
-        Box::pin(read_file_impl(path))
+        Ok(paths)
+    }
+
+    fn get_file_modification_time(&self, location: &FileLocation) -> Option<u64> {
+        let path = match location {
+            FileLocation::Path(path) => path,
+            FileLocation::Custom(_) => return None,
+        };
+        let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+        Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
     }
 }
 
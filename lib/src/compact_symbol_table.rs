@@ -1,4 +1,5 @@
-use super::shared::{AddressDebugInfo, SymbolicationResult};
+use super::shared::{AddressDebugInfo, AddressSymbolInfo, SymbolicationResult};
+use super::symbolicate::demangle::DemangleOptions;
 use std::ops::Deref;
 
 /// A "compact" representation of a symbol table.
@@ -20,6 +21,36 @@ pub struct CompactSymbolTable {
     /// in the order of the addresses they correspond to, in utf-8 encoded
     /// form, all concatenated together.
     pub buffer: Vec<u8>,
+    /// Whether the names came from a mach-O binary and have an extra leading
+    /// underscore, which is removed when the names are formatted.
+    has_leading_underscores: bool,
+}
+
+impl CompactSymbolTable {
+    /// Returns the symbol table with every name passed through
+    /// `DemangleOptions::format_name`, or `DemangleOptions::format_macho_symbol_name`
+    /// for mach-O binaries.
+    pub(crate) fn with_formatted_names(self, options: &DemangleOptions) -> Self {
+        let mut index = Vec::with_capacity(self.index.len());
+        let mut buffer = Vec::with_capacity(self.buffer.len());
+        for range in self.index.windows(2) {
+            let name = String::from_utf8_lossy(&self.buffer[range[0] as usize..range[1] as usize]);
+            index.push(buffer.len() as u32);
+            let name = if self.has_leading_underscores {
+                options.format_macho_symbol_name(&name)
+            } else {
+                options.format_name(&name)
+            };
+            buffer.extend_from_slice(name.as_bytes());
+        }
+        index.push(buffer.len() as u32);
+        Self {
+            addr: self.addr,
+            index,
+            buffer,
+            has_leading_underscores: self.has_leading_underscores,
+        }
+    }
 }
 
 impl SymbolicationResult for CompactSymbolTable {
@@ -41,6 +72,7 @@ impl SymbolicationResult for CompactSymbolTable {
             addr,
             index,
             buffer,
+            has_leading_underscores: false,
         }
     }
 
@@ -48,13 +80,11 @@ impl SymbolicationResult for CompactSymbolTable {
         panic!("Should not be called")
     }
 
-    fn add_address_symbol(
-        &mut self,
-        _address: u32,
-        _symbol_address: u32,
-        _symbol_name: &str,
-        _function_size: Option<u32>,
-    ) {
+    fn add_address_symbol(&mut self, _address: u32, _symbol: AddressSymbolInfo) {
+        panic!("Should not be called")
+    }
+
+    fn add_address_symbol_aliases(&mut self, _address: u32, _aliases: &[&str]) {
         panic!("Should not be called")
     }
 
@@ -63,4 +93,8 @@ impl SymbolicationResult for CompactSymbolTable {
     }
 
     fn set_total_symbol_count(&mut self, _total_symbol_count: u32) {}
+
+    fn set_symbol_names_have_leading_underscore(&mut self) {
+        self.has_leading_underscores = true;
+    }
 }
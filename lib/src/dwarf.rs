@@ -1,8 +1,9 @@
 use crate::path_mapper::PathMapper;
 use crate::shared::{
-    relative_address_base, AddressDebugInfo, InlineStackFrame, RangeReadRef, SymbolicationResult,
+    elf_tls_template_address, relative_address_base, AdditionalSymbol, AddressDebugInfo,
+    DataSymbol, InlineStackFrame, RangeReadRef, SourceFileChecksum, SymbolSource,
+    SymbolicationResult,
 };
-use crate::symbolicate::demangle;
 use addr2line::{
     fallible_iterator,
     gimli::{self, EndianSlice, Reader, ReaderOffsetId, RunTimeEndian},
@@ -11,6 +12,8 @@ use fallible_iterator::FallibleIterator;
 use gimli::SectionId;
 use object::read::ReadRef;
 use object::CompressionFormat;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::{borrow::Cow, cmp::min, marker::PhantomData, str};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -61,14 +64,60 @@ pub fn collect_dwarf_address_debug_data<'data: 'file, 'file, O, R>(
 
     let section_data = SectionDataNoCopy::from_object(data, object);
     if let Ok(context) = section_data.make_addr2line_context() {
+        // The file checksums and embedded sources of each unit, keyed by the unit's offset.
+        let mut unit_file_checksums = HashMap::new();
+        let mut unit_embedded_sources = HashMap::new();
+        let wants_embedded_source = R::wants_embedded_source();
         for AddressPair {
             original_relative_address,
             vmaddr_in_this_object,
         } in addresses
         {
             if let Ok(frame_iter) = context.find_frames(*vmaddr_in_this_object as u64) {
+                let unit = context.find_dwarf_unit(*vmaddr_in_this_object);
+                let file_checksums = unit.map(|unit| {
+                    &*unit_file_checksums
+                        .entry(unit.header.offset())
+                        .or_insert_with(|| {
+                            dwarf_file_checksums(context.dwarf(), unit).unwrap_or_default()
+                        })
+                });
+                let embedded_sources = unit.filter(|_| wants_embedded_source).map(|unit| {
+                    &*unit_embedded_sources
+                        .entry(unit.header.offset())
+                        .or_insert_with(|| {
+                            dwarf_embedded_sources(context.dwarf(), unit).unwrap_or_default()
+                        })
+                });
                 let frames: std::result::Result<Vec<_>, _> = frame_iter
-                    .map(|f| Ok(convert_stack_frame(f, path_mapper)))
+                    .map(|f| {
+                        let dw_die_offset = f.dw_die_offset;
+                        let raw_file = f.location.as_ref().and_then(|location| location.file);
+                        let mut frame = convert_stack_frame(f, path_mapper);
+                        if let (Some(file_checksums), Some(raw_file)) = (file_checksums, raw_file) {
+                            frame.file_checksum = file_checksums.get(raw_file).cloned();
+                        }
+                        if let (Some(embedded_sources), Some(raw_file), Some(file_path)) =
+                            (embedded_sources, raw_file, &frame.file_path)
+                        {
+                            if let Some(source) = embedded_sources.get(raw_file) {
+                                symbolication_result
+                                    .add_embedded_source(file_path.clone(), source.clone());
+                            }
+                        }
+                        if let (Some(unit), Some(offset)) = (unit, dw_die_offset) {
+                            let _ = add_function_details(
+                                &mut frame,
+                                context.dwarf(),
+                                unit,
+                                offset,
+                                *original_relative_address,
+                                *vmaddr_in_this_object,
+                                path_mapper,
+                            );
+                        }
+                        Ok(frame)
+                    })
                     .collect();
                 if let Ok(frames) = frames {
                     if !frames.is_empty() {
@@ -83,18 +132,81 @@ pub fn collect_dwarf_address_debug_data<'data: 'file, 'file, O, R>(
     }
 }
 
+/// Returns whether `object` has DWARF debug info, in a plain or compressed .debug_info
+/// section.
+pub fn has_dwarf_debug_info<'data: 'file, 'file, O>(object: &'file O) -> bool
+where
+    O: object::Object<'data, 'file>,
+{
+    object.section_by_name(".debug_info").is_some()
+        || object.section_by_name(".zdebug_info").is_some()
+}
+
+/// Collect function symbols from the DW_TAG_subprogram entries in the DWARF debug
+/// info of `object`, for symbolicating binaries whose symbol table is stripped.
+pub fn collect_dwarf_function_symbols<'data: 'file, 'file, O>(
+    data: RangeReadRef<'data, impl ReadRef<'data>>,
+    object: &'file O,
+) -> Vec<AdditionalSymbol>
+where
+    O: object::Object<'data, 'file>,
+{
+    let base_address = relative_address_base(object);
+    let section_data = SectionDataNoCopy::from_object(data, object);
+    let dwarf = section_data.make_dwarf();
+    collect_dwarf_functions(&dwarf)
+        .into_iter()
+        .filter_map(|function| {
+            Some(AdditionalSymbol {
+                address: u32::try_from(function.start_address.checked_sub(base_address)?).ok()?,
+                size: Some(u32::try_from(function.end_address - function.start_address).ok()?),
+                name: function.name,
+                source: SymbolSource::Dwarf,
+            })
+        })
+        .collect()
+}
+
+/// Collect data symbols for the variables in the DWARF debug info of `object`,
+/// for symbolicating data addresses in binaries which don't have a symbol table.
+pub fn collect_dwarf_data_symbols<'data: 'file, 'file, O>(
+    data: RangeReadRef<'data, impl ReadRef<'data>>,
+    object: &'file O,
+) -> Vec<DataSymbol>
+where
+    O: object::Object<'data, 'file>,
+{
+    let base_address = relative_address_base(object);
+    let tls_template_address = elf_tls_template_address(object);
+    let section_data = SectionDataNoCopy::from_object(data, object);
+    let dwarf = section_data.make_dwarf();
+    collect_dwarf_variables(&dwarf)
+        .into_iter()
+        .filter_map(|variable| {
+            let address = if variable.is_thread_local {
+                tls_template_address?.checked_add(variable.address)?
+            } else {
+                variable.address
+            };
+            Some(DataSymbol {
+                address: u32::try_from(address.checked_sub(base_address)?).ok()?,
+                size: match variable.size {
+                    Some(size) => Some(u32::try_from(size).ok()?),
+                    None => None,
+                },
+                name: variable.name,
+                source: SymbolSource::Dwarf,
+            })
+        })
+        .collect()
+}
+
 fn convert_stack_frame<R: gimli::Reader>(
     frame: addr2line::Frame<R>,
     path_mapper: &mut PathMapper<()>,
 ) -> InlineStackFrame {
     let function = match frame.function {
-        Some(function_name) => {
-            if let Ok(name) = function_name.raw_name() {
-                Some(demangle::demangle_any(&name))
-            } else {
-                None
-            }
-        }
+        Some(function_name) => function_name.raw_name().ok().map(|name| name.into_owned()),
         None => None,
     };
     let file_path = match &frame.location {
@@ -105,7 +217,525 @@ fn convert_stack_frame<R: gimli::Reader>(
     InlineStackFrame {
         function,
         file_path,
-        line_number: frame.location.and_then(|l| l.line).map(|l| l as u32),
+        file_checksum: None,
+        line_number: frame.location.as_ref().and_then(|l| l.line),
+        column_number: frame.location.as_ref().and_then(|l| l.column),
+        function_decl_file_path: None,
+        function_decl_line_number: None,
+        inline_range_start: None,
+    }
+}
+
+/// Fills in where the frame's function is declared, from the DW_AT_decl_file and
+/// DW_AT_decl_line attributes of its entry, and for inlined frames, where the range
+/// of the inlined code which contains the address starts.
+fn add_function_details<R: gimli::Reader>(
+    frame: &mut InlineStackFrame,
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    original_relative_address: u32,
+    vmaddr_in_this_object: u64,
+    path_mapper: &mut PathMapper<()>,
+) -> gimli::Result<()> {
+    let entry = unit.entry(offset)?;
+    if entry.tag() == gimli::DW_TAG_inlined_subroutine {
+        let mut ranges = dwarf.die_ranges(unit, &entry)?;
+        while let Some(range) = ranges.next()? {
+            if range.begin <= vmaddr_in_this_object && vmaddr_in_this_object < range.end {
+                let offset_in_range = vmaddr_in_this_object - range.begin;
+                frame.inline_range_start = u64::from(original_relative_address)
+                    .checked_sub(offset_in_range)
+                    .and_then(|start| u32::try_from(start).ok());
+                break;
+            }
+        }
+    }
+
+    // Inlined subroutines and out-of-line instances of inline functions have their
+    // declaration on the abstract instance, and member functions can have it on the
+    // declaration inside the class.
+    let mut entry = entry;
+    for _ in 0..4 {
+        if let Some(line) = entry
+            .attr_value(gimli::DW_AT_decl_line)?
+            .and_then(|value| value.udata_value())
+        {
+            frame.function_decl_line_number = u32::try_from(line).ok();
+            frame.function_decl_file_path = match entry.attr_value(gimli::DW_AT_decl_file)? {
+                Some(gimli::AttributeValue::FileIndex(index)) => {
+                    dwarf_file_path(dwarf, unit, index)?.map(|path| path_mapper.map_path(&path))
+                }
+                _ => None,
+            };
+            return Ok(());
+        }
+        let reference = match entry.attr_value(gimli::DW_AT_abstract_origin)? {
+            Some(reference) => reference,
+            None => match entry.attr_value(gimli::DW_AT_specification)? {
+                Some(reference) => reference,
+                None => return Ok(()),
+            },
+        };
+        entry = match reference {
+            gimli::AttributeValue::UnitRef(offset) => unit.entry(offset)?,
+            _ => return Ok(()),
+        };
+    }
+    Ok(())
+}
+
+/// Returns the path of the file with the given index in the unit's line program
+/// header, joined with its directory and with the unit's compilation directory.
+fn dwarf_file_path<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    index: u64,
+) -> gimli::Result<Option<String>> {
+    let header = match &unit.line_program {
+        Some(line_program) => line_program.header(),
+        None => return Ok(None),
+    };
+    let file = match header.file(index) {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let mut path = match &unit.comp_dir {
+        Some(comp_dir) => comp_dir.to_string_lossy()?.into_owned(),
+        None => String::new(),
+    };
+    if let Some(directory) = file.directory(header) {
+        let directory = dwarf.attr_string(unit, directory)?;
+        push_path(&mut path, &directory.to_string_lossy()?);
+    }
+    let file_name = dwarf.attr_string(unit, file.path_name())?;
+    push_path(&mut path, &file_name.to_string_lossy()?);
+    Ok(Some(path))
+}
+
+/// Returns the MD5 checksums from the unit's line program header, keyed by the
+/// file path as `dwarf_file_path` returns it. Only DWARF 5 line tables can
+/// have checksums, and only if they have a DW_LNCT_MD5 entry.
+fn dwarf_file_checksums<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+) -> gimli::Result<HashMap<String, SourceFileChecksum>> {
+    let mut checksums = HashMap::new();
+    let header = match &unit.line_program {
+        Some(line_program) => line_program.header(),
+        None => return Ok(checksums),
+    };
+    if !header.file_has_md5() {
+        return Ok(checksums);
+    }
+    // File indexes start at 0 in DWARF 5 and at 1 in earlier versions.
+    let first_index = if header.version() >= 5 { 0 } else { 1 };
+    for (index, file) in (first_index..).zip(header.file_names()) {
+        if let Some(path) = dwarf_file_path(dwarf, unit, index)? {
+            checksums.insert(path, SourceFileChecksum::Md5(*file.md5()));
+        }
+    }
+    Ok(checksums)
+}
+
+/// The LLVM extension for source code which is embedded in the line table,
+/// emitted by clang's `-gembed-source`.
+const DW_LNCT_LLVM_SOURCE: gimli::DwLnct = gimli::DwLnct(0x2001);
+
+/// Returns the source code which is embedded in the unit's line program header,
+/// keyed by the file path as `dwarf_file_path` returns it.
+///
+/// gimli skips DW_LNCT_LLVM_source entries when it parses the file table, so this
+/// parses the header again, up to the end of the file table.
+fn dwarf_embedded_sources<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+) -> gimli::Result<HashMap<String, String>> {
+    let mut sources = HashMap::new();
+    let header = match &unit.line_program {
+        Some(line_program) => line_program.header(),
+        None => return Ok(sources),
+    };
+    let has_embedded_source = header
+        .file_name_entry_format()
+        .iter()
+        .any(|format| format.content_type == DW_LNCT_LLVM_SOURCE);
+    if header.version() < 5 || !has_embedded_source {
+        return Ok(sources);
+    }
+
+    use gimli::{ReaderOffset, Section};
+    let mut input = dwarf.debug_line.reader().clone();
+    input.skip(header.offset().0)?;
+    let (_unit_length, format) = input.read_initial_length()?;
+    // version, address_size, segment_selector_size
+    input.skip(R::Offset::from_u8(4))?;
+    let _header_length = input.read_offset(format)?;
+    // minimum_instruction_length, maximum_operations_per_instruction, default_is_stmt,
+    // line_base, line_range
+    input.skip(R::Offset::from_u8(5))?;
+    let opcode_base = input.read_u8()?;
+    input.skip(R::Offset::from_u8(opcode_base.saturating_sub(1)))?;
+
+    let directory_formats = read_line_entry_formats(&mut input)?;
+    let directory_count = input.read_uleb128()?;
+    for _ in 0..directory_count {
+        for (_, form) in &directory_formats {
+            read_line_entry_value(&mut input, format, *form)?;
+        }
+    }
+
+    let file_formats = read_line_entry_formats(&mut input)?;
+    let file_count = input.read_uleb128()?;
+    for index in 0..file_count {
+        let mut source = None;
+        for (content_type, form) in &file_formats {
+            let value = read_line_entry_value(&mut input, format, *form)?;
+            if *content_type == DW_LNCT_LLVM_SOURCE {
+                source = Some(value);
+            }
+        }
+        let source = match source {
+            Some(value) => dwarf
+                .attr_string(unit, value)?
+                .to_string_lossy()?
+                .into_owned(),
+            None => continue,
+        };
+        // Files without embedded source have an empty string.
+        if source.is_empty() {
+            continue;
+        }
+        if let Some(path) = dwarf_file_path(dwarf, unit, index)? {
+            sources.insert(path, source);
+        }
+    }
+    Ok(sources)
+}
+
+/// Reads the content type and form pairs which describe the directory or file
+/// entries of a DWARF 5 line program header.
+fn read_line_entry_formats<R: gimli::Reader>(
+    input: &mut R,
+) -> gimli::Result<Vec<(gimli::DwLnct, gimli::DwForm)>> {
+    let count = input.read_u8()?;
+    let mut formats = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let content_type = input.read_uleb128_u16()?;
+        let form = input.read_uleb128_u16()?;
+        formats.push((gimli::DwLnct(content_type), gimli::DwForm(form)));
+    }
+    Ok(formats)
+}
+
+/// Reads a value of a directory or file entry in a DWARF 5 line program header.
+/// Only the forms which the DWARF 5 spec allows in the line table are supported.
+fn read_line_entry_value<R: gimli::Reader>(
+    input: &mut R,
+    format: gimli::Format,
+    form: gimli::DwForm,
+) -> gimli::Result<gimli::AttributeValue<R>> {
+    use gimli::{AttributeValue, ReaderOffset};
+    let index = |index: u64| {
+        R::Offset::from_u64(index)
+            .map(|index| AttributeValue::DebugStrOffsetsIndex(gimli::DebugStrOffsetsIndex(index)))
+    };
+    Ok(match form {
+        gimli::DW_FORM_string => AttributeValue::String(input.read_null_terminated_slice()?),
+        gimli::DW_FORM_line_strp => {
+            AttributeValue::DebugLineStrRef(gimli::DebugLineStrOffset(input.read_offset(format)?))
+        }
+        gimli::DW_FORM_strp => {
+            AttributeValue::DebugStrRef(gimli::DebugStrOffset(input.read_offset(format)?))
+        }
+        gimli::DW_FORM_strx => index(input.read_uleb128()?)?,
+        gimli::DW_FORM_strx1 => index(u64::from(input.read_u8()?))?,
+        gimli::DW_FORM_strx2 => index(u64::from(input.read_u16()?))?,
+        gimli::DW_FORM_strx3 => index(input.read_uint(3)?)?,
+        gimli::DW_FORM_strx4 => index(u64::from(input.read_u32()?))?,
+        gimli::DW_FORM_udata => AttributeValue::Udata(input.read_uleb128()?),
+        gimli::DW_FORM_data1 => AttributeValue::Data1(input.read_u8()?),
+        gimli::DW_FORM_data2 => AttributeValue::Data2(input.read_u16()?),
+        gimli::DW_FORM_data4 => AttributeValue::Data4(input.read_u32()?),
+        gimli::DW_FORM_data8 => AttributeValue::Data8(input.read_u64()?),
+        gimli::DW_FORM_data16 => AttributeValue::Block(input.split(R::Offset::from_u8(16))?),
+        gimli::DW_FORM_block => {
+            let length = input.read_uleb128().and_then(R::Offset::from_u64)?;
+            AttributeValue::Block(input.split(length)?)
+        }
+        _ => return Err(gimli::Error::UnknownForm),
+    })
+}
+
+/// Appends `component` to `path`, or replaces `path` if `component` is absolute.
+/// This matches what addr2line does for the file paths of frames, so that the
+/// paths can be compared.
+fn push_path(path: &mut String, component: &str) {
+    let is_windows_path = |p: &str| p.starts_with('\\') || p.get(1..3) == Some(":\\");
+    if component.starts_with('/') || is_windows_path(component) {
+        *path = component.to_string();
+        return;
+    }
+    let separator = if is_windows_path(path) { '\\' } else { '/' };
+    if !path.ends_with(separator) {
+        path.push(separator);
+    }
+    path.push_str(component);
+}
+
+/// A function with a code address range, found in the DWARF debug info.
+pub struct DwarfFunction {
+    pub start_address: u64,
+    pub end_address: u64,
+    /// The linkage name of the function if present, otherwise its plain name.
+    pub name: String,
+}
+
+/// Collect the address ranges of all functions, from the DW_TAG_subprogram entries
+/// which have code. Functions with multiple ranges, for example because the compiler
+/// has split off a cold part, produce one `DwarfFunction` per range.
+pub fn collect_dwarf_functions<R: gimli::Reader>(dwarf: &gimli::Dwarf<R>) -> Vec<DwarfFunction> {
+    let mut functions = Vec::new();
+    let mut units = dwarf.units();
+    while let Ok(Some(header)) = units.next() {
+        let unit = match dwarf.unit(header) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+        let mut entries = unit.entries();
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let _ = add_dwarf_function_ranges(dwarf, &unit, entry, &mut functions);
+        }
+    }
+    functions
+}
+
+fn add_dwarf_function_ranges<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+    functions: &mut Vec<DwarfFunction>,
+) -> gimli::Result<()> {
+    let mut ranges = dwarf.die_ranges(unit, entry)?;
+    let mut name = None;
+    while let Some(range) = ranges.next()? {
+        // Functions which were removed by the linker have their address set to
+        // zero or to a tombstone value like -1 or -2.
+        if range.begin == 0 || range.begin >= range.end || range.end >= u64::MAX - 1 {
+            continue;
+        }
+        if name.is_none() {
+            name = dwarf_entry_name(dwarf, unit, entry)?;
+        }
+        let name = match &name {
+            Some(name) => name.clone(),
+            None => return Ok(()),
+        };
+        functions.push(DwarfFunction {
+            start_address: range.begin,
+            end_address: range.end,
+            name,
+        });
+    }
+    Ok(())
+}
+
+/// A global or static variable with a fixed address, found in the DWARF debug info.
+pub struct DwarfVariable {
+    /// The address of the variable. For thread-local variables, this is the offset
+    /// in the thread-local storage block of the module.
+    pub address: u64,
+    pub is_thread_local: bool,
+    /// The size of the variable's type in bytes, if known.
+    pub size: Option<u64>,
+    /// The linkage name of the variable if present, otherwise its plain name.
+    pub name: String,
+}
+
+/// Collect all variables with a fixed address, i.e. all DW_TAG_variable entries
+/// whose location is a plain DW_OP_addr expression, or a TLS offset. This is used
+/// to symbolicate data addresses in binaries without a symbol table.
+pub fn collect_dwarf_variables<R: gimli::Reader>(dwarf: &gimli::Dwarf<R>) -> Vec<DwarfVariable> {
+    let mut variables = Vec::new();
+    let mut units = dwarf.units();
+    while let Ok(Some(header)) = units.next() {
+        let unit = match dwarf.unit(header) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+        let mut entries = unit.entries();
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag() != gimli::DW_TAG_variable {
+                continue;
+            }
+            if let Ok(Some(variable)) = dwarf_variable(dwarf, &unit, entry) {
+                variables.push(variable);
+            }
+        }
+    }
+    variables
+}
+
+fn dwarf_variable<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> gimli::Result<Option<DwarfVariable>> {
+    let expression = match entry.attr_value(gimli::DW_AT_location)? {
+        Some(gimli::AttributeValue::Exprloc(expression)) => expression,
+        _ => return Ok(None),
+    };
+    let mut operations = expression.operations(unit.encoding());
+    let (address, is_thread_local) = match (operations.next()?, operations.next()?) {
+        (Some(gimli::Operation::Address { address }), None) => (address, false),
+        (Some(gimli::Operation::AddressIndex { index }), None) => {
+            (dwarf.address(unit, index)?, false)
+        }
+        (Some(gimli::Operation::UnsignedConstant { value }), Some(gimli::Operation::TLS)) => {
+            (value, true)
+        }
+        _ => return Ok(None),
+    };
+    if operations.next()?.is_some() {
+        return Ok(None);
+    }
+
+    // The type can be on the declaration that this entry refers to, for example
+    // for static class members.
+    let declaration = match entry.attr_value(gimli::DW_AT_specification)? {
+        Some(gimli::AttributeValue::UnitRef(offset)) => Some(unit.entry(offset)?),
+        _ => None,
+    };
+    let name = match dwarf_entry_name(dwarf, unit, entry)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let type_entry = match entry.attr_value(gimli::DW_AT_type)? {
+        Some(value) => Some(value),
+        None => match &declaration {
+            Some(declaration) => declaration.attr_value(gimli::DW_AT_type)?,
+            None => None,
+        },
+    };
+    let size = match type_entry {
+        Some(gimli::AttributeValue::UnitRef(offset)) => dwarf_type_size(unit, offset, 0)?,
+        _ => None,
+    };
+    Ok(Some(DwarfVariable {
+        address,
+        is_thread_local,
+        size,
+        name,
+    }))
+}
+
+/// Returns the linkage name of the entry, or its plain name if it has no linkage
+/// name. If the entry has neither, the declaration or the abstract instance that it
+/// refers to is checked.
+fn dwarf_entry_name<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> gimli::Result<Option<String>> {
+    let mut entry = entry.clone();
+    for _ in 0..4 {
+        for attr in [
+            gimli::DW_AT_linkage_name,
+            gimli::DW_AT_MIPS_linkage_name,
+            gimli::DW_AT_name,
+        ] {
+            if let Some(value) = entry.attr_value(attr)? {
+                let name = dwarf.attr_string(unit, value)?;
+                return Ok(Some(name.to_string_lossy()?.into_owned()));
+            }
+        }
+        let reference = match entry.attr_value(gimli::DW_AT_specification)? {
+            Some(reference) => reference,
+            None => match entry.attr_value(gimli::DW_AT_abstract_origin)? {
+                Some(reference) => reference,
+                None => return Ok(None),
+            },
+        };
+        entry = match reference {
+            gimli::AttributeValue::UnitRef(offset) => unit.entry(offset)?,
+            _ => return Ok(None),
+        };
+    }
+    Ok(None)
+}
+
+/// Returns the size in bytes of the type at `offset`, following typedefs and
+/// qualifiers, and multiplying out array dimensions.
+fn dwarf_type_size<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    depth: u32,
+) -> gimli::Result<Option<u64>> {
+    if depth > 16 {
+        return Ok(None);
+    }
+    let entry = unit.entry(offset)?;
+    if let Some(byte_size) = entry
+        .attr_value(gimli::DW_AT_byte_size)?
+        .and_then(|value| value.udata_value())
+    {
+        return Ok(Some(byte_size));
+    }
+    let underlying_type = match entry.attr_value(gimli::DW_AT_type)? {
+        Some(gimli::AttributeValue::UnitRef(offset)) => offset,
+        _ => return Ok(None),
+    };
+    match entry.tag() {
+        gimli::DW_TAG_typedef
+        | gimli::DW_TAG_const_type
+        | gimli::DW_TAG_volatile_type
+        | gimli::DW_TAG_restrict_type
+        | gimli::DW_TAG_atomic_type => dwarf_type_size(unit, underlying_type, depth + 1),
+        gimli::DW_TAG_pointer_type
+        | gimli::DW_TAG_reference_type
+        | gimli::DW_TAG_rvalue_reference_type => Ok(Some(unit.encoding().address_size.into())),
+        gimli::DW_TAG_array_type => {
+            let element_size = match dwarf_type_size(unit, underlying_type, depth + 1)? {
+                Some(element_size) => element_size,
+                None => return Ok(None),
+            };
+            let mut size = element_size;
+            let mut tree = unit.entries_tree(Some(offset))?;
+            let mut children = tree.root()?.children();
+            while let Some(child) = children.next()? {
+                let subrange = child.entry();
+                if subrange.tag() != gimli::DW_TAG_subrange_type {
+                    continue;
+                }
+                let count = match subrange
+                    .attr_value(gimli::DW_AT_count)?
+                    .and_then(|value| value.udata_value())
+                {
+                    Some(count) => count,
+                    None => {
+                        let upper_bound = match subrange
+                            .attr_value(gimli::DW_AT_upper_bound)?
+                            .and_then(|value| value.udata_value())
+                        {
+                            Some(upper_bound) => upper_bound,
+                            // Arrays of unknown size, e.g. `extern int foo[];`.
+                            None => return Ok(None),
+                        };
+                        let lower_bound = subrange
+                            .attr_value(gimli::DW_AT_lower_bound)?
+                            .and_then(|value| value.udata_value())
+                            .unwrap_or(0);
+                        (upper_bound + 1).saturating_sub(lower_bound)
+                    }
+                };
+                size = size.saturating_mul(count);
+            }
+            Ok(Some(size))
+        }
+        _ => Ok(None),
     }
 }
 
@@ -265,6 +895,15 @@ impl<'data, T: ReadRef<'data>> SectionDataNoCopy<'data, T> {
     pub fn make_addr2line_context<'a>(
         &'a self,
     ) -> std::result::Result<addr2line::Context<EndianSlice<'a, RunTimeEndian>>, gimli::read::Error>
+    where
+        'data: 'a,
+    {
+        addr2line::Context::from_dwarf(self.make_dwarf())
+    }
+
+    /// Create a gimli::Dwarf around fully-read section data buffers, for consumers
+    /// which need to walk the debug info entries themselves.
+    pub fn make_dwarf<'a>(&'a self) -> gimli::Dwarf<EndianSlice<'a, RunTimeEndian>>
     where
         'data: 'a,
     {
@@ -280,19 +919,25 @@ impl<'data, T: ReadRef<'data>> SectionDataNoCopy<'data, T> {
             EndianSlice::new(buffer, endian)
         }
 
-        addr2line::Context::from_sections(
-            get(&self.debug_abbrev_data, endian).into(),
-            get(&self.debug_addr_data, endian).into(),
-            get(&self.debug_aranges_data, endian).into(),
-            get(&self.debug_info_data, endian).into(),
-            get(&self.debug_line_data, endian).into(),
-            get(&self.debug_line_str_data, endian).into(),
-            get(&self.debug_ranges_data, endian).into(),
-            get(&self.debug_rnglists_data, endian).into(),
-            get(&self.debug_str_data, endian).into(),
-            get(&self.debug_str_offsets_data, endian).into(),
-            get(&self.default_section_data, endian),
-        )
+        let default_section = get(&self.default_section_data, endian);
+        gimli::Dwarf {
+            debug_abbrev: get(&self.debug_abbrev_data, endian).into(),
+            debug_addr: get(&self.debug_addr_data, endian).into(),
+            debug_aranges: get(&self.debug_aranges_data, endian).into(),
+            debug_info: get(&self.debug_info_data, endian).into(),
+            debug_line: get(&self.debug_line_data, endian).into(),
+            debug_line_str: get(&self.debug_line_str_data, endian).into(),
+            debug_str: get(&self.debug_str_data, endian).into(),
+            debug_str_offsets: get(&self.debug_str_offsets_data, endian).into(),
+            debug_types: default_section.into(),
+            locations: gimli::LocationLists::new(default_section.into(), default_section.into()),
+            ranges: gimli::RangeLists::new(
+                get(&self.debug_ranges_data, endian).into(),
+                get(&self.debug_rnglists_data, endian).into(),
+            ),
+            file_type: gimli::DwarfFileType::Main,
+            sup: None,
+        }
     }
 
     /// Create an addr2line::Context where the section data is read lazily, by
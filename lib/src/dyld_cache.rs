@@ -0,0 +1,265 @@
+use crate::debugid_util::debug_id_for_object;
+use crate::error::{GetSymbolsError, Result};
+use crate::macho;
+use crate::shared::{
+    relative_address_base, FileAndPathHelper, FileContents, FileContentsWrapper, FileLocation,
+    SymbolicationQuery, SymbolicationResult,
+};
+use debugid::DebugId;
+use object::read::macho::DyldCache;
+use object::{Endianness, Object, ObjectSegment};
+use std::convert::TryFrom;
+use std::path::Path;
+
+/// Opens the dyld shared cache at `dyld_cache_path` together with its subcaches,
+/// i.e. `<dyld_cache_path>.1`, `<dyld_cache_path>.2` etc. and `<dyld_cache_path>.symbols`.
+/// Returns the contents of the main cache file and of the subcaches, in the order
+/// which `DyldCache::parse` expects.
+pub(crate) async fn open_dyld_cache_files<'h, H>(
+    dyld_cache_path: &Path,
+    helper: &'h H,
+) -> Result<(FileContentsWrapper<H::F>, Vec<FileContentsWrapper<H::F>>)>
+where
+    H: FileAndPathHelper<'h>,
+{
+    let root_contents = helper
+        .open_file(&FileLocation::Path(dyld_cache_path.to_path_buf()))
+        .await
+        .map_err(|e| {
+            GetSymbolsError::HelperErrorDuringOpenFile(
+                dyld_cache_path.to_string_lossy().to_string(),
+                e,
+            )
+        })?;
+    let root_contents = FileContentsWrapper::new(root_contents);
+
+    let dyld_cache_path = dyld_cache_path.to_string_lossy();
+
+    let mut subcache_contents = Vec::new();
+    for subcache_index in 1.. {
+        let subcache_path = format!("{}.{}", dyld_cache_path, subcache_index);
+        match helper
+            .open_file(&FileLocation::Path(subcache_path.into()))
+            .await
+        {
+            Ok(subcache) => subcache_contents.push(FileContentsWrapper::new(subcache)),
+            Err(_) => break,
+        };
+    }
+    let symbols_subcache_path = format!("{}.symbols", dyld_cache_path);
+    if let Ok(subcache) = helper
+        .open_file(&FileLocation::Path(symbols_subcache_path.into()))
+        .await
+    {
+        subcache_contents.push(FileContentsWrapper::new(subcache));
+    };
+
+    Ok((root_contents, subcache_contents))
+}
+
+/// A dyld shared cache, opened together with its subcaches.
+///
+/// This lets you find the images (dylibs) in the cache by their debug ID, without
+/// knowing their paths, and translate addresses in the cache into addresses that
+/// are relative to an image.
+pub struct DyldSharedCache<T: FileContents> {
+    root_contents: FileContentsWrapper<T>,
+    subcache_contents: Vec<FileContentsWrapper<T>>,
+    base_address: u64,
+    images: Vec<DyldSharedCacheImage>,
+}
+
+/// An image (dylib) in the dyld shared cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DyldSharedCacheImage {
+    /// The install path of the dylib, e.g. "/usr/lib/libSystem.B.dylib".
+    pub path: String,
+    /// The Mach-O UUID of the dylib.
+    pub uuid: [u8; 16],
+    /// The debug ID, which is derived from the UUID.
+    pub debug_id: DebugId,
+    /// The address of the __TEXT segment. Addresses which are relative to this
+    /// image are relative to this address.
+    pub base_address: u64,
+    /// The segments of the dylib, with their addresses in the cache.
+    pub segments: Vec<DyldSharedCacheSegment>,
+}
+
+/// The address range of a segment of an image in the dyld shared cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DyldSharedCacheSegment {
+    /// The segment name, e.g. "__TEXT".
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+/// An address in the dyld shared cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DyldCacheAddress {
+    /// An address as it is stored in the cache, i.e. before the cache is slid.
+    Absolute(u64),
+    /// An offset from the start of the cache, i.e. from the address of its first
+    /// mapping. This does not depend on the slide of the cache.
+    CacheRelative(u64),
+}
+
+impl<T: FileContents> DyldSharedCache<T> {
+    /// Opens the dyld shared cache at `dyld_cache_path`, and its subcaches, and
+    /// reads the list of images.
+    pub async fn open<'h, H>(dyld_cache_path: &Path, helper: &'h H) -> Result<Self>
+    where
+        H: FileAndPathHelper<'h, F = T>,
+    {
+        let (root_contents, subcache_contents) =
+            open_dyld_cache_files(dyld_cache_path, helper).await?;
+        let (base_address, images) = {
+            let subcache_contents_refs: Vec<&FileContentsWrapper<T>> =
+                subcache_contents.iter().collect();
+            let cache = DyldCache::<Endianness, _>::parse(&root_contents, &subcache_contents_refs)
+                .map_err(GetSymbolsError::DyldCacheParseError)?;
+            let base_address = object::macho::DyldCacheHeader::<Endianness>::parse(&root_contents)
+                .and_then(|header| header.mappings(cache.endianness(), &root_contents))
+                .ok()
+                .and_then(|mappings| mappings.first())
+                .map_or(0, |mapping| mapping.address.get(cache.endianness()));
+            let images = cache
+                .images()
+                .filter_map(|image| {
+                    let path = image.path().ok()?.to_string();
+                    let object = image.parse_object().ok()?;
+                    let debug_id = debug_id_for_object(&object)?;
+                    let segments = object
+                        .segments()
+                        .map(|segment| DyldSharedCacheSegment {
+                            name: segment
+                                .name()
+                                .ok()
+                                .flatten()
+                                .unwrap_or_default()
+                                .to_string(),
+                            address: segment.address(),
+                            size: segment.size(),
+                        })
+                        .collect();
+                    Some(DyldSharedCacheImage {
+                        path,
+                        uuid: *debug_id.uuid().as_bytes(),
+                        debug_id,
+                        base_address: relative_address_base(&object),
+                        segments,
+                    })
+                })
+                .collect();
+            (base_address, images)
+        };
+        Ok(Self {
+            root_contents,
+            subcache_contents,
+            base_address,
+            images,
+        })
+    }
+
+    /// The address of the first mapping of the cache. `DyldCacheAddress::CacheRelative`
+    /// addresses are relative to this address.
+    pub fn base_address(&self) -> u64 {
+        self.base_address
+    }
+
+    /// All images in the cache.
+    pub fn images(&self) -> &[DyldSharedCacheImage] {
+        &self.images
+    }
+
+    /// Finds the image with the given debug ID.
+    pub fn image_for_debug_id(&self, debug_id: DebugId) -> Option<&DyldSharedCacheImage> {
+        self.images.iter().find(|image| image.debug_id == debug_id)
+    }
+
+    /// Finds the image with a segment which contains `address`, and returns it
+    /// together with the address relative to the image's base address.
+    pub fn lookup_address(
+        &self,
+        address: DyldCacheAddress,
+    ) -> Option<(&DyldSharedCacheImage, u32)> {
+        let address = match address {
+            DyldCacheAddress::Absolute(address) => address,
+            DyldCacheAddress::CacheRelative(offset) => self.base_address.checked_add(offset)?,
+        };
+        find_image_for_address(&self.images, address)
+    }
+
+    /// Obtains symbol data for the image in the cache which matches `query.debug_id`.
+    /// `query.debug_name` is not used to find the image.
+    pub fn get_symbolication_result<R>(&self, query: SymbolicationQuery) -> Result<R>
+    where
+        R: SymbolicationResult,
+    {
+        let image = self.image_for_debug_id(query.debug_id).ok_or(
+            GetSymbolsError::NoMatchingDyldCacheImageDebugId(query.debug_id),
+        )?;
+        macho::get_symbolication_result_from_dyld_cache(
+            query,
+            &self.root_contents,
+            &self.subcache_contents,
+            Some(&image.path),
+        )
+    }
+}
+
+fn find_image_for_address(
+    images: &[DyldSharedCacheImage],
+    address: u64,
+) -> Option<(&DyldSharedCacheImage, u32)> {
+    let image = images.iter().find(|image| {
+        image
+            .segments
+            .iter()
+            .any(|segment| address >= segment.address && address - segment.address < segment.size)
+    })?;
+    let relative_address = u32::try_from(address.checked_sub(image.base_address)?).ok()?;
+    Some((image, relative_address))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn image_for_address() {
+        let image = |path: &str, address: u64| DyldSharedCacheImage {
+            path: path.to_string(),
+            uuid: [0; 16],
+            debug_id: DebugId::nil(),
+            base_address: address,
+            segments: vec![
+                DyldSharedCacheSegment {
+                    name: "__TEXT".to_string(),
+                    address,
+                    size: 0x1000,
+                },
+                DyldSharedCacheSegment {
+                    name: "__DATA".to_string(),
+                    address: address + 0x1000_0000,
+                    size: 0x100,
+                },
+            ],
+        };
+        let images = vec![
+            image("/usr/lib/libA.dylib", 0x1_8000_0000),
+            image("/usr/lib/libB.dylib", 0x1_8000_1000),
+        ];
+        let found = |address| {
+            find_image_for_address(&images, address)
+                .map(|(image, relative_address)| (image.path.as_str(), relative_address))
+        };
+        assert_eq!(found(0x1_8000_0010), Some(("/usr/lib/libA.dylib", 0x10)));
+        assert_eq!(found(0x1_8000_1ffc), Some(("/usr/lib/libB.dylib", 0xffc)));
+        assert_eq!(
+            found(0x1_9000_1008),
+            Some(("/usr/lib/libB.dylib", 0x1000_0008))
+        );
+        assert_eq!(found(0x1_8000_2000), None);
+    }
+}
@@ -1,13 +1,24 @@
 use crate::debugid_util::debug_id_for_object;
-use crate::dwarf::{collect_dwarf_address_debug_data, make_address_pairs_for_root_object};
+use crate::dwarf::{
+    collect_dwarf_address_debug_data, collect_dwarf_data_symbols, collect_dwarf_function_symbols,
+    has_dwarf_debug_info, make_address_pairs_for_root_object,
+};
 use crate::error::{GetSymbolsError, Result};
+use crate::gopclntab::{
+    collect_gopclntab_address_debug_data, collect_gopclntab_function_symbols, GoPcLnTab,
+};
 use crate::path_mapper::PathMapper;
 use crate::shared::{
-    get_symbolication_result_for_addresses_from_object, object_to_map, BasePath, FileContents,
-    FileContentsWrapper, SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
+    function_address_mask, get_data_symbolication_result_from_object,
+    get_symbolication_result_for_addresses_from_object, has_poor_symbol_coverage,
+    object_data_symbols, object_function_symbols, object_to_map, relative_address_base, BasePath,
+    FileContents, FileContentsWrapper, QuerySymbolKind, RangeReadRef, StubSymbol,
+    SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
 };
-use gimli::{CieOrFde, EhFrame, UnwindSection};
-use object::{File, FileKind, Object, ObjectSection, ReadRef};
+use debugid::DebugId;
+use gimli::{CieOrFde, DebugFrame, EhFrame, UnwindOffset, UnwindSection};
+use object::{File, FileKind, Object, ObjectSection, ObjectSymbol, ObjectSymbolTable, ReadRef};
+use std::convert::{TryFrom, TryInto};
 use std::io::Cursor;
 
 pub fn get_symbolication_result<R>(
@@ -21,50 +32,174 @@ where
 {
     let elf_file =
         File::parse(&file_contents).map_err(|e| GetSymbolsError::ObjectParseError(file_kind, e))?;
+    check_debug_id(&elf_file, query.debug_id)?;
+
+    // If this file has a .gnu_debugdata section ("MiniDebugInfo"), it contains an
+    // xz-compressed ELF file whose .symtab has the symbols which were stripped from
+    // the outer file. Use those symbols in addition to the outer file's symbols.
+    if let Some(objdata) = decompress_gnu_debugdata(&elf_file) {
+        if let Ok(inner_file) = File::parse(&objdata[..]) {
+            if inner_file.section_by_name(".debug_info").is_some() {
+                let inner_contents = FileContentsWrapper::new(&objdata[..]);
+                return get_symbolication_result_impl(
+                    &elf_file,
+                    Some(&inner_file),
+                    &inner_file,
+                    inner_contents.full_range(),
+                    base_path,
+                    query,
+                );
+            }
+            return get_symbolication_result_impl(
+                &elf_file,
+                Some(&inner_file),
+                &elf_file,
+                file_contents.full_range(),
+                base_path,
+                query,
+            );
+        }
+    }
 
-    let elf_debug_id = debug_id_for_object(&elf_file).ok_or(GetSymbolsError::InvalidInputError(
+    get_symbolication_result_impl(
+        &elf_file,
+        None::<&File>,
+        &elf_file,
+        file_contents.full_range(),
+        base_path,
+        query,
+    )
+}
+
+/// Symbolicate using a binary and its separate debug file, for example a file from
+/// /usr/lib/debug/.build-id/ or one that was created with `objcopy --only-keep-debug`.
+///
+/// The symbols from both files are merged: the binary provides the dynamic symbols,
+/// the PLT and the function bounds from the unwind info, and the debug file provides
+/// the full symbol table and the DWARF debug info. Both files must match the
+/// requested debug ID. `base_path` is the base path of the debug file.
+pub fn get_symbolication_result_with_debug_file<R>(
+    base_path: &BasePath,
+    binary_file_kind: FileKind,
+    binary_contents: FileContentsWrapper<impl FileContents>,
+    debug_file_kind: FileKind,
+    debug_contents: FileContentsWrapper<impl FileContents>,
+    query: SymbolicationQuery,
+) -> Result<R>
+where
+    R: SymbolicationResult,
+{
+    let elf_file = File::parse(&binary_contents)
+        .map_err(|e| GetSymbolsError::ObjectParseError(binary_file_kind, e))?;
+    check_debug_id(&elf_file, query.debug_id)?;
+    let debug_file = File::parse(&debug_contents)
+        .map_err(|e| GetSymbolsError::ObjectParseError(debug_file_kind, e))?;
+    check_debug_id(&debug_file, query.debug_id)?;
+
+    get_symbolication_result_impl(
+        &elf_file,
+        Some(&debug_file),
+        &debug_file,
+        debug_contents.full_range(),
+        base_path,
+        query,
+    )
+}
+
+fn check_debug_id<'data>(
+    elf_file: &File<'data, impl ReadRef<'data>>,
+    debug_id: DebugId,
+) -> Result<()> {
+    let elf_debug_id = debug_id_for_object(elf_file).ok_or(GetSymbolsError::InvalidInputError(
         "debug ID cannot be read",
     ))?;
-    let SymbolicationQuery { debug_id, .. } = query;
     if elf_debug_id != debug_id {
         return Err(GetSymbolsError::UnmatchedDebugId(elf_debug_id, debug_id));
     }
+    Ok(())
+}
 
-    // If this file has a .gnu_debugdata section, use the uncompressed object from that section instead.
-    if let Some(debugdata) = elf_file.section_by_name(".gnu_debugdata") {
-        if let Ok(data) = debugdata.data() {
-            let mut cursor = Cursor::new(data);
-            let mut objdata = Vec::new();
-            if let Ok(()) = lzma_rs::xz_decompress(&mut cursor, &mut objdata) {
-                if let Ok(elf_file) = File::parse(&objdata[..]) {
-                    let file_contents = FileContentsWrapper::new(&objdata[..]);
-                    return get_symbolication_result_impl(
-                        elf_file,
-                        base_path,
-                        &file_contents,
-                        query,
-                    );
-                }
-            }
-        }
-    }
-
-    get_symbolication_result_impl(elf_file, base_path, &file_contents, query)
+/// Returns the uncompressed contents of the .gnu_debugdata section, if present.
+fn decompress_gnu_debugdata<'data>(elf_file: &File<'data, impl ReadRef<'data>>) -> Option<Vec<u8>> {
+    let data = elf_file.section_by_name(".gnu_debugdata")?.data().ok()?;
+    let mut cursor = Cursor::new(data);
+    let mut objdata = Vec::new();
+    lzma_rs::xz_decompress(&mut cursor, &mut objdata).ok()?;
+    Some(objdata)
 }
 
-pub fn get_symbolication_result_impl<'data, R>(
-    elf_file: File<'data, impl ReadRef<'data>>,
+/// Symbolicate `elf_file`. The symbols from `symbol_file`, if present, are used in
+/// addition to the symbols of `elf_file`, and the debug info is read from `dwarf_file`,
+/// whose contents are `dwarf_data`. `symbol_file` and `dwarf_file` can be the same file.
+fn get_symbolication_result_impl<'data, 'sdata, 'ddata, R>(
+    elf_file: &File<'data, impl ReadRef<'data>>,
+    symbol_file: Option<&File<'sdata, impl ReadRef<'sdata>>>,
+    dwarf_file: &File<'ddata, impl ReadRef<'ddata>>,
+    dwarf_data: RangeReadRef<'ddata, impl ReadRef<'ddata>>,
     base_path: &BasePath,
-    file_contents: &'data FileContentsWrapper<impl FileContents>,
     query: SymbolicationQuery,
 ) -> Result<R>
 where
     R: SymbolicationResult,
 {
-    let (function_starts, function_ends) = function_start_and_end_addresses(&elf_file);
+    let has_symbol_table = elf_file.symbols().next().is_some()
+        || matches!(symbol_file, Some(symbol_file) if symbol_file.symbols().next().is_some());
+
+    if query.symbol_kind == QuerySymbolKind::Data {
+        let mut extra_symbols = match symbol_file {
+            Some(symbol_file) => object_data_symbols(symbol_file),
+            None => Vec::new(),
+        };
+        // Stripped binaries only have data symbols for exported variables in their
+        // dynamic symbol table. If there is no regular symbol table, also look at
+        // the variables in the DWARF debug info, if present.
+        if !has_symbol_table {
+            extra_symbols.extend(collect_dwarf_data_symbols(dwarf_data, dwarf_file));
+        }
+        return Ok(get_data_symbolication_result_from_object(
+            elf_file,
+            query.result_kind,
+            extra_symbols,
+        ));
+    }
+
+    let (function_starts, function_ends) = function_start_and_end_addresses(elf_file);
+    let plt_symbols = plt_stub_symbols(elf_file);
+
+    let mut additional_symbols = match symbol_file {
+        Some(symbol_file) => object_function_symbols(symbol_file, relative_address_base(elf_file)),
+        None => Vec::new(),
+    };
+
+    // Go binaries have a function table with the names of all functions, which is
+    // kept even if the binary is stripped.
+    let gopclntab = GoPcLnTab::from_object(elf_file);
+    if let Some(gopclntab) = &gopclntab {
+        additional_symbols.extend(collect_gopclntab_function_symbols(
+            gopclntab,
+            relative_address_base(elf_file),
+        ));
+    }
+
+    // If the symbol table was stripped, get function symbols from the DWARF
+    // debug info, if present.
+    let mut symbol_addresses = text_symbol_addresses(elf_file);
+    symbol_addresses.extend(additional_symbols.iter().map(|symbol| symbol.address));
+    symbol_addresses.sort_unstable();
+    symbol_addresses.dedup();
+    if has_poor_symbol_coverage(&function_starts, &symbol_addresses, has_symbol_table) {
+        additional_symbols.extend(collect_dwarf_function_symbols(dwarf_data, dwarf_file));
+    }
+
     let (addresses, mut symbolication_result) = match query.result_kind {
         SymbolicationResultKind::AllSymbols => {
-            let map = object_to_map(&elf_file, Some(&function_starts));
+            let map = object_to_map(
+                elf_file,
+                Some(&function_starts),
+                &plt_symbols,
+                &additional_symbols,
+                query.attribute_split_functions_to_parent,
+            );
             return Ok(R::from_full_map(map));
         }
         SymbolicationResultKind::SymbolsForAddresses {
@@ -73,9 +208,12 @@ where
         } => {
             let symbolication_result = get_symbolication_result_for_addresses_from_object(
                 addresses,
-                &elf_file,
+                elf_file,
                 Some(&function_starts),
                 Some(&function_ends),
+                &plt_symbols,
+                &additional_symbols,
+                query.attribute_split_functions_to_parent,
             );
             if !with_debug_info {
                 return Ok(symbolication_result);
@@ -84,18 +222,52 @@ where
         }
     };
 
-    let addresses: Vec<_> = make_address_pairs_for_root_object(addresses, &elf_file);
-    let mut path_mapper = PathMapper::new(base_path);
-    collect_dwarf_address_debug_data(
-        file_contents.full_range(),
-        &elf_file,
-        &addresses,
-        &mut symbolication_result,
-        &mut path_mapper,
-    );
+    let addresses: Vec<_> = make_address_pairs_for_root_object(addresses, elf_file);
+    let mut path_mapper = PathMapper::new(base_path, query.path_mapping_rules);
+    match gopclntab {
+        // Without DWARF, use the file and line tables from the Go function table.
+        Some(gopclntab) if !has_dwarf_debug_info(dwarf_file) => {
+            collect_gopclntab_address_debug_data(
+                &gopclntab,
+                &addresses,
+                &mut symbolication_result,
+                &mut path_mapper,
+            );
+        }
+        _ => {
+            collect_dwarf_address_debug_data(
+                dwarf_data,
+                dwarf_file,
+                &addresses,
+                &mut symbolication_result,
+                &mut path_mapper,
+            );
+        }
+    }
     Ok(symbolication_result)
 }
 
+/// Returns the sorted relative addresses of the function symbols in the .symtab and
+/// .dynsym of `elf_file`.
+fn text_symbol_addresses<'data: 'file, 'file>(
+    elf_file: &'file File<'data, impl ReadRef<'data>>,
+) -> Vec<u32> {
+    let base_address = relative_address_base(elf_file);
+    let address_mask = function_address_mask(elf_file);
+    let mut symbol_addresses: Vec<u32> = elf_file
+        .symbols()
+        .chain(elf_file.dynamic_symbols())
+        .filter(|symbol| symbol.kind() == object::SymbolKind::Text)
+        .filter_map(|symbol| {
+            let address = (symbol.address() & address_mask).checked_sub(base_address)?;
+            u32::try_from(address).ok()
+        })
+        .collect();
+    symbol_addresses.sort_unstable();
+    symbol_addresses.dedup();
+    symbol_addresses
+}
+
 /// Get a list of function addresses as u32 relative addresses.
 pub fn function_start_and_end_addresses<'a: 'b, 'b, T>(object_file: &'b T) -> (Vec<u32>, Vec<u32>)
 where
@@ -108,8 +280,8 @@ where
     // not in .eh_frame, it is in __unwind_info (plus some auxiliary data
     // in __eh_frame, but that's only needed for the actual unwinding, not
     // for the function start addresses).
-    // We also don't handle .debug_frame yet, which is sometimes found
-    // instead of .eh_frame.
+    // Some binaries have .debug_frame instead of (or in addition to) .eh_frame,
+    // and 32-bit ARM binaries usually only have .ARM.exidx. We look at all of them.
     // And we don't have anything for the PE format yet, either.
 
     let eh_frame = object_file.section_by_name(".eh_frame");
@@ -141,33 +313,65 @@ where
         .address_size()
         .unwrap_or(object::AddressSize::U64) as u8;
 
-    let eh_frame = match eh_frame {
-        Some(eh_frame) => eh_frame,
-        None => return (Vec::new(), Vec::new()),
-    };
+    let mut start_addresses = Vec::new();
+    let mut end_addresses = Vec::new();
 
-    let eh_frame_data = match eh_frame.uncompressed_data() {
-        Ok(eh_frame_data) => eh_frame_data,
-        Err(_) => return (Vec::new(), Vec::new()),
-    };
+    if let Some(eh_frame_data) = eh_frame.and_then(|s| s.uncompressed_data().ok()) {
+        let mut eh_frame = EhFrame::new(&eh_frame_data, endian);
+        eh_frame.set_address_size(address_size);
+        add_fde_addresses(&eh_frame, &bases, &mut start_addresses, &mut end_addresses);
+    }
+
+    if let Some(debug_frame_data) = object_file
+        .section_by_name(".debug_frame")
+        .and_then(|s| s.uncompressed_data().ok())
+    {
+        let mut debug_frame = DebugFrame::new(&debug_frame_data, endian);
+        debug_frame.set_address_size(address_size);
+        add_fde_addresses(
+            &debug_frame,
+            &bases,
+            &mut start_addresses,
+            &mut end_addresses,
+        );
+    }
 
-    let mut eh_frame = EhFrame::new(&*eh_frame_data, endian);
-    eh_frame.set_address_size(address_size);
+    if let Some(exidx) = object_file.section_by_name(".ARM.exidx") {
+        if let Ok(exidx_data) = exidx.data() {
+            start_addresses.extend(arm_exidx_function_start_addresses(
+                exidx.address(),
+                exidx_data,
+                object_file.is_little_endian(),
+            ));
+        }
+    }
+
+    (start_addresses, end_addresses)
+}
+
+/// Add the start and end address of every FDE in an .eh_frame or .debug_frame section.
+fn add_fde_addresses<R, U>(
+    unwind_section: &U,
+    bases: &gimli::BaseAddresses,
+    start_addresses: &mut Vec<u32>,
+    end_addresses: &mut Vec<u32>,
+) where
+    R: gimli::Reader,
+    U: UnwindSection<R>,
+{
     let mut cur_cie = None;
-    let mut entries_iter = eh_frame.entries(&bases);
-    let mut start_addresses = Vec::new();
-    let mut end_addresses = Vec::new();
+    let mut entries_iter = unwind_section.entries(bases);
     while let Ok(Some(entry)) = entries_iter.next() {
         match entry {
             CieOrFde::Cie(cie) => cur_cie = Some(cie),
             CieOrFde::Fde(partial_fde) => {
-                if let Ok(fde) = partial_fde.parse(|eh_frame, bases, cie_offset| {
+                if let Ok(fde) = partial_fde.parse(|unwind_section, bases, cie_offset| {
                     if let Some(cie) = &cur_cie {
-                        if cie.offset() == cie_offset.0 {
+                        if cie.offset() == UnwindOffset::into(cie_offset) {
                             return Ok(cie.clone());
                         }
                     }
-                    let cie = eh_frame.cie_from_offset(bases, cie_offset);
+                    let cie = unwind_section.cie_from_offset(bases, cie_offset);
                     if let Ok(cie) = &cie {
                         cur_cie = Some(cie.clone());
                     }
@@ -179,5 +383,392 @@ where
             }
         }
     }
-    (start_addresses, end_addresses)
+}
+
+/// Get the function start addresses from the .ARM.exidx section, which is the
+/// exception handling index table of the ARM EHABI.
+///
+/// Each entry in this table consists of two 32-bit words. The first word is a
+/// "prel31" offset to the start of the function: a 31-bit signed offset which is
+/// relative to the address of the word itself. The second word describes how to
+/// unwind the function, which we don't care about here.
+/// The table is sorted by address and has no information about function sizes;
+/// every entry implicitly extends to the start of the next entry.
+fn arm_exidx_function_start_addresses(
+    section_address: u64,
+    data: &[u8],
+    little_endian: bool,
+) -> Vec<u32> {
+    data.chunks_exact(8)
+        .enumerate()
+        .map(|(i, entry)| {
+            let bytes = [entry[0], entry[1], entry[2], entry[3]];
+            let word = if little_endian {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            };
+            // Sign-extend the 31-bit offset.
+            let offset = ((word << 1) as i32 >> 1) as i64;
+            let entry_address = section_address + i as u64 * 8;
+            // Mask off the Thumb bit, in case it is set.
+            (entry_address as i64 + offset) as u32 & !1
+        })
+        .collect()
+}
+
+/// Synthesize symbols of the form "memcpy@plt" for the entries in the procedure
+/// linkage table. PLT entries don't have symbols, but every entry has a relocation
+/// in .rela.plt (or .rel.plt), in the same order as the entries, and that relocation
+/// refers to the dynamic symbol of the called function.
+///
+/// If the binary has a .plt.sec section (which is the case when it's compiled with
+/// Intel CET), then the code that's called is in that section, and the entries in
+/// .plt are only used for lazy binding.
+pub fn plt_stub_symbols<'a: 'b, 'b, T>(object_file: &'b T) -> Vec<StubSymbol>
+where
+    T: object::Object<'a, 'b>,
+{
+    let (relocation_section, is_rela) = match object_file.section_by_name(".rela.plt") {
+        Some(section) => (section, true),
+        None => match object_file.section_by_name(".rel.plt") {
+            Some(section) => (section, false),
+            None => return Vec::new(),
+        },
+    };
+    let relocation_data = match relocation_section.data() {
+        Ok(data) => data,
+        Err(_) => return Vec::new(),
+    };
+    let symbol_indexes = plt_relocation_symbol_indexes(
+        relocation_data,
+        object_file.is_64(),
+        is_rela,
+        object_file.is_little_endian(),
+    );
+    if symbol_indexes.is_empty() {
+        return Vec::new();
+    }
+
+    // Find out where the entries are, and how large they are. The .plt section
+    // starts with a header entry which is used for lazy binding; its size depends
+    // on the architecture.
+    let (plt, header_size) = match object_file.section_by_name(".plt.sec") {
+        Some(plt_sec) => (plt_sec, 0),
+        None => {
+            let header_size = match object_file.architecture() {
+                object::Architecture::X86_64 | object::Architecture::I386 => 16,
+                object::Architecture::Aarch64 => 32,
+                object::Architecture::Arm => 20,
+                _ => return Vec::new(),
+            };
+            match object_file.section_by_name(".plt") {
+                Some(plt) => (plt, header_size),
+                None => return Vec::new(),
+            }
+        }
+    };
+    let entries_size = match plt.size().checked_sub(header_size) {
+        Some(size) => size,
+        None => return Vec::new(),
+    };
+    let entry_size = entries_size / symbol_indexes.len() as u64;
+    if entry_size == 0 || entry_size * symbol_indexes.len() as u64 != entries_size {
+        // Some architectures have entries of different sizes, which we don't support.
+        return Vec::new();
+    }
+
+    let dynamic_symbols = match object_file.dynamic_symbol_table() {
+        Some(dynamic_symbols) => dynamic_symbols,
+        None => return Vec::new(),
+    };
+    let base_address = relative_address_base(object_file);
+    let first_entry_address = plt.address() + header_size - base_address;
+
+    symbol_indexes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, symbol_index)| {
+            // Relocations without a symbol, such as IRELATIVE relocations, are skipped.
+            if symbol_index == 0 {
+                return None;
+            }
+            let symbol = dynamic_symbols
+                .symbol_by_index(object::SymbolIndex(symbol_index as usize))
+                .ok()?;
+            let name = symbol.name().ok()?;
+            Some(StubSymbol {
+                address: (first_entry_address + i as u64 * entry_size) as u32,
+                size: entry_size as u32,
+                name: format!("{}@plt", name),
+            })
+        })
+        .collect()
+}
+
+/// Return the dynamic symbol index for each relocation in a .rela.plt or .rel.plt section.
+fn plt_relocation_symbol_indexes(
+    data: &[u8],
+    is_64: bool,
+    is_rela: bool,
+    little_endian: bool,
+) -> Vec<u32> {
+    // The entries are Elf{32,64}_Rel{,a} structs: r_offset, r_info, and,
+    // for Rela, r_addend. The fields have the size of an address.
+    let word_size = if is_64 { 8 } else { 4 };
+    let entry_size = if is_rela {
+        3 * word_size
+    } else {
+        2 * word_size
+    };
+    data.chunks_exact(entry_size)
+        .map(|entry| {
+            let r_info = &entry[word_size..2 * word_size];
+            if is_64 {
+                let bytes = r_info.try_into().unwrap();
+                let r_info = if little_endian {
+                    u64::from_le_bytes(bytes)
+                } else {
+                    u64::from_be_bytes(bytes)
+                };
+                (r_info >> 32) as u32
+            } else {
+                let bytes = r_info.try_into().unwrap();
+                let r_info = if little_endian {
+                    u32::from_le_bytes(bytes)
+                } else {
+                    u32::from_be_bytes(bytes)
+                };
+                r_info >> 8
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        arm_exidx_function_start_addresses, get_symbolication_result_impl,
+        plt_relocation_symbol_indexes,
+    };
+    use crate::shared::{
+        BasePath, FileContentsWrapper, QuerySymbolKind, SymbolSource, SymbolicationQuery,
+        SymbolicationResultKind,
+    };
+    use crate::symbolicate::v5::looked_up_addresses::LookedUpAddresses;
+    use debugid::DebugId;
+    use object::File;
+
+    /// Build an x86-64 ELF file with a .text section at 0x1000 and DWARF debug info
+    /// for the functions in `dwarf_functions`. If `symbols` is not empty, the file
+    /// also has a .symtab with these global function symbols.
+    fn elf_with_dwarf_functions(
+        dwarf_functions: &[(&str, u64, u32)],
+        symbols: &[(&str, u64, u64)],
+    ) -> Vec<u8> {
+        // Abbreviation 1 is a compile unit with children, 2 is a subprogram with
+        // DW_AT_name (string), DW_AT_low_pc (addr) and DW_AT_high_pc (data4).
+        let debug_abbrev = vec![
+            1, 0x11, 1, 0, 0, 2, 0x2e, 0, 0x03, 0x08, 0x11, 0x01, 0x12, 0x06, 0, 0, 0,
+        ];
+        let mut dies = vec![1];
+        for &(name, address, size) in dwarf_functions {
+            dies.push(2);
+            dies.extend_from_slice(name.as_bytes());
+            dies.push(0);
+            dies.extend_from_slice(&address.to_le_bytes());
+            dies.extend_from_slice(&size.to_le_bytes());
+        }
+        dies.push(0);
+        let mut debug_info = Vec::new();
+        debug_info.extend_from_slice(&(dies.len() as u32 + 7).to_le_bytes());
+        debug_info.extend_from_slice(&4u16.to_le_bytes()); // version
+        debug_info.extend_from_slice(&0u32.to_le_bytes()); // debug_abbrev_offset
+        debug_info.push(8); // address_size
+        debug_info.extend_from_slice(&dies);
+
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; 24];
+        for &(name, address, size) in symbols {
+            symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+            symtab.push(object::elf::STB_GLOBAL << 4 | object::elf::STT_FUNC);
+            symtab.push(0); // st_other
+            symtab.extend_from_slice(&1u16.to_le_bytes()); // st_shndx: .text
+            symtab.extend_from_slice(&address.to_le_bytes());
+            symtab.extend_from_slice(&size.to_le_bytes());
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        // (name, type, flags, address, contents, link, info, entsize)
+        let mut sections = vec![
+            (
+                ".text",
+                1u32,
+                6u64,
+                0x1000u64,
+                vec![0xccu8; 0x100],
+                0u32,
+                0u32,
+                0u64,
+            ),
+            (".debug_abbrev", 1, 0, 0, debug_abbrev, 0, 0, 0),
+            (".debug_info", 1, 0, 0, debug_info, 0, 0, 0),
+        ];
+        if !symbols.is_empty() {
+            let strtab_index = sections.len() as u32 + 2;
+            sections.push((".symtab", 2, 0, 0, symtab, strtab_index, 1, 24));
+            sections.push((".strtab", 3, 0, 0, strtab, 0, 0, 0));
+        }
+        let mut shstrtab = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for name in sections
+            .iter()
+            .map(|section| section.0)
+            .chain([".shstrtab"])
+        {
+            name_offsets.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(name.as_bytes());
+            shstrtab.push(0);
+        }
+        sections.push((".shstrtab", 3, 0, 0, shstrtab, 0, 0, 0));
+
+        let mut data = Vec::new();
+        let mut offsets = Vec::new();
+        data.resize(64, 0);
+        for section in &sections {
+            offsets.push(data.len() as u64);
+            data.extend_from_slice(&section.4);
+        }
+        data.resize((data.len() + 7) & !7, 0);
+        let shoff = data.len() as u64;
+
+        let mut header = Vec::new();
+        header.extend_from_slice(b"\x7fELF\x02\x01\x01");
+        header.resize(16, 0);
+        header.extend_from_slice(&3u16.to_le_bytes()); // e_type: ET_DYN
+        header.extend_from_slice(&62u16.to_le_bytes()); // e_machine: x86_64
+        header.extend_from_slice(&1u32.to_le_bytes()); // e_version
+        header.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+        header.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+        header.extend_from_slice(&shoff.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        let section_count = sections.len() as u16 + 1;
+        for value in [64u16, 56, 0, 64, section_count, section_count - 1] {
+            // e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        data[..64].copy_from_slice(&header);
+
+        data.resize(data.len() + 64, 0); // The null section.
+        for (i, (_, sh_type, flags, address, contents, link, info, entsize)) in
+            sections.iter().enumerate()
+        {
+            data.extend_from_slice(&name_offsets[i].to_le_bytes());
+            data.extend_from_slice(&sh_type.to_le_bytes());
+            data.extend_from_slice(&flags.to_le_bytes());
+            data.extend_from_slice(&address.to_le_bytes());
+            data.extend_from_slice(&offsets[i].to_le_bytes());
+            data.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            data.extend_from_slice(&link.to_le_bytes());
+            data.extend_from_slice(&info.to_le_bytes());
+            data.extend_from_slice(&1u64.to_le_bytes()); // sh_addralign
+            data.extend_from_slice(&entsize.to_le_bytes());
+        }
+        data
+    }
+
+    fn look_up_addresses(data: &[u8], addresses: &[u32]) -> LookedUpAddresses {
+        let file = File::parse(data).unwrap();
+        let contents = FileContentsWrapper::new(data);
+        let query = SymbolicationQuery {
+            debug_name: "libfoo.so",
+            debug_id: DebugId::nil(),
+            result_kind: SymbolicationResultKind::SymbolsForAddresses {
+                addresses,
+                with_debug_info: false,
+            },
+            attribute_split_functions_to_parent: false,
+            symbol_kind: QuerySymbolKind::Functions,
+            path_mapping_rules: &[],
+        };
+        get_symbolication_result_impl(
+            &file,
+            None::<&File>,
+            &file,
+            contents.full_range(),
+            &BasePath::NoLocalSourceFileAccess,
+            query,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn dwarf_function_symbols() {
+        let dwarf_functions = [("foo", 0x1000, 0x10), ("bar", 0x1020, 0x20)];
+
+        // Without a symbol table, the function symbols come from the debug info.
+        let data = elf_with_dwarf_functions(&dwarf_functions, &[]);
+        let result = look_up_addresses(&data, &[0x1004, 0x1030]);
+        let foo = result.address_results[&0x1004].as_ref().unwrap();
+        assert_eq!(foo.symbol_name, "foo");
+        assert_eq!(foo.symbol_source, SymbolSource::Dwarf);
+        let bar = result.address_results[&0x1030].as_ref().unwrap();
+        assert_eq!(bar.symbol_name, "bar");
+        assert_eq!(bar.symbol_address, 0x1020);
+        assert_eq!(bar.function_size, Some(0x20));
+
+        // With a symbol table, the debug info is not used for symbols.
+        let data = elf_with_dwarf_functions(&dwarf_functions, &[("foo", 0x1000, 0x10)]);
+        let result = look_up_addresses(&data, &[0x1004, 0x1030]);
+        let foo = result.address_results[&0x1004].as_ref().unwrap();
+        assert_eq!(foo.symbol_name, "foo");
+        assert_eq!(foo.symbol_source, SymbolSource::SymbolTable);
+        assert!(result.address_results[&0x1030].is_none());
+    }
+
+    #[test]
+    fn arm_exidx_prel31() {
+        let mut data = Vec::new();
+        // Entry at 0x1000 pointing backwards to 0x800, with EXIDX_CANTUNWIND.
+        data.extend_from_slice(&0x7fff_f800u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        // Entry at 0x1008 pointing forwards to 0x2008, with an inline unwind entry.
+        data.extend_from_slice(&0x0000_1000u32.to_le_bytes());
+        data.extend_from_slice(&0x80b0_b0b0u32.to_le_bytes());
+        // Entry at 0x1010 pointing to 0x1011 (Thumb bit set).
+        data.extend_from_slice(&0x0000_0001u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        assert_eq!(
+            arm_exidx_function_start_addresses(0x1000, &data, true),
+            vec![0x800, 0x2008, 0x1010]
+        );
+    }
+
+    #[test]
+    fn plt_relocations() {
+        // Two Elf64_Rela entries for R_X86_64_JUMP_SLOT (7) with symbols 5 and 2,
+        // and one R_X86_64_IRELATIVE (37) entry without a symbol.
+        let mut data = Vec::new();
+        for (symbol_index, relocation_type) in [(5u64, 7u64), (2, 7), (0, 37)] {
+            data.extend_from_slice(&0x4018u64.to_le_bytes());
+            data.extend_from_slice(&(symbol_index << 32 | relocation_type).to_le_bytes());
+            data.extend_from_slice(&0u64.to_le_bytes());
+        }
+        assert_eq!(
+            plt_relocation_symbol_indexes(&data, true, true, true),
+            vec![5, 2, 0]
+        );
+
+        // Two Elf32_Rel entries for R_ARM_JUMP_SLOT (22), big endian.
+        let mut data = Vec::new();
+        for symbol_index in [3u32, 9] {
+            data.extend_from_slice(&0x2000u32.to_be_bytes());
+            data.extend_from_slice(&(symbol_index << 8 | 22).to_be_bytes());
+        }
+        assert_eq!(
+            plt_relocation_symbol_indexes(&data, false, false, false),
+            vec![3, 9]
+        );
+    }
 }
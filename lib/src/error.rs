@@ -38,6 +38,9 @@ pub enum GetSymbolsError {
     #[error("The dyld shared cache file did not include an entry for the dylib at {0}")]
     NoMatchingDyldCacheImagePath(String),
 
+    #[error("The dyld shared cache file did not include a dylib with the debug ID {}", .0.breakpad())]
+    NoMatchingDyldCacheImageDebugId(DebugId),
+
     #[error("MachOHeader parsing error: {0}")]
     MachOHeaderParseError(#[source] object::read::Error),
 
@@ -57,6 +60,12 @@ pub enum GetSymbolsError {
         #[source] Box<dyn std::error::Error + Send + Sync>,
     ),
 
+    #[error("get_candidate_paths_for_object_file helper callback for {0} returned error: {1}")]
+    HelperErrorDuringGetCandidatePathsForObjectFile(
+        String,
+        #[source] Box<dyn std::error::Error + Send + Sync>,
+    ),
+
     #[error("open_file helper callback for file {0} returned error: {1}")]
     HelperErrorDuringOpenFile(String, #[source] Box<dyn std::error::Error + Send + Sync>),
 
@@ -75,6 +84,17 @@ pub enum GetSymbolsError {
     #[error("Could not parse archive file at {0}, ArchiveFile::parse returned error: {1}.")]
     ArchiveParseError(PathBuf, #[source] Box<dyn std::error::Error + Send + Sync>),
 
+    #[error("No candidate path for the object file {0}")]
+    NoCandidatePathForObjectFile(String),
+
+    #[error(
+        "The archive member {1} was not found in any of the candidate files for the archive {0}"
+    )]
+    ArchiveMemberNotFound(String, String),
+
+    #[error("The object file {0} has the modification time {2}, but the binary was linked with a version from {1}; its debug info was not used.")]
+    ObjectFileModified(String, u64, u64),
+
     #[error("Couldn't parse request: {0}")]
     ParseRequestErrorSerde(#[from] serde_json::error::Error),
 
@@ -143,6 +163,9 @@ impl GetSymbolsError {
             GetSymbolsError::InvalidInputError(_) => "InvalidInputError",
             GetSymbolsError::DyldCacheParseError(_) => "DyldCacheParseError",
             GetSymbolsError::NoMatchingDyldCacheImagePath(_) => "NoMatchingDyldCacheImagePath",
+            GetSymbolsError::NoMatchingDyldCacheImageDebugId(_) => {
+                "NoMatchingDyldCacheImageDebugId"
+            }
             GetSymbolsError::ObjectParseError(_, _) => "ObjectParseError",
             GetSymbolsError::MachOHeaderParseError(_) => "MachOHeaderParseError",
             GetSymbolsError::HelperErrorDuringGetCandidatePathsForBinaryOrPdb(_, _, _) => {
@@ -151,12 +174,18 @@ impl GetSymbolsError {
             GetSymbolsError::HelperErrorDuringGetCandidatePathsForPdb(_, _, _) => {
                 "HelperErrorDuringGetCandidatePathsForPdb"
             }
+            GetSymbolsError::HelperErrorDuringGetCandidatePathsForObjectFile(_, _) => {
+                "HelperErrorDuringGetCandidatePathsForObjectFile"
+            }
             GetSymbolsError::HelperErrorDuringOpenFile(_, _) => "HelperErrorDuringOpenFile",
             GetSymbolsError::HelperErrorDuringFileReading(_, _) => "HelperErrorDuringFileReading",
             GetSymbolsError::NoCandidatePathForBinary(_, _) => "NoCandidatePathForBinary",
             GetSymbolsError::NoDebugInfoInPeBinary(_) => "NoDebugInfoInPeBinary",
             GetSymbolsError::PdbPathDidntEndWithNul(_) => "PdbPathDidntEndWithNul",
             GetSymbolsError::ArchiveParseError(_, _) => "ArchiveParseError",
+            GetSymbolsError::NoCandidatePathForObjectFile(_) => "NoCandidatePathForObjectFile",
+            GetSymbolsError::ArchiveMemberNotFound(_, _) => "ArchiveMemberNotFound",
+            GetSymbolsError::ObjectFileModified(_, _, _) => "ObjectFileModified",
             GetSymbolsError::ParseRequestErrorSerde(_) => "ParseRequestErrorSerde",
             GetSymbolsError::ParseRequestErrorContents(_) => "ParseRequestErrorContents",
             GetSymbolsError::PdbAddr2lineError(_) => "PdbAddr2lineError",
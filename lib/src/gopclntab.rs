@@ -0,0 +1,943 @@
+use crate::dwarf::AddressPair;
+use crate::path_mapper::PathMapper;
+use crate::shared::{
+    AdditionalSymbol, AddressDebugInfo, InlineStackFrame, SymbolSource, SymbolicationResult,
+};
+use object::{Object, ObjectSection, ObjectSymbol, SectionKind};
+use std::convert::{TryFrom, TryInto};
+
+/// The index of the pcdata table which maps a pc to an index in the inline tree.
+const PCDATA_INL_TREE_INDEX: u32 = 2;
+/// The index of the funcdata entry which points to the inline tree of a function.
+const FUNCDATA_INL_TREE: u32 = 3;
+
+/// The layout versions of the Go function table. The layout was changed in Go 1.16,
+/// in Go 1.18 and, to a smaller degree, in Go 1.20.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum GoPcLnTabVersion {
+    Go12,
+    Go116,
+    Go118,
+    Go120,
+}
+
+/// A parsed Go function table ("pclntab"), from the `.gopclntab` section of ELF
+/// binaries or the `__gopclntab` section of mach-O binaries.
+///
+/// Go binaries keep this table even when they are stripped, because the Go runtime
+/// needs it for stack traces. It has the name, the address range and the file and
+/// line tables of every function, and the inline tree of functions with inlined calls.
+pub struct GoPcLnTab<'data> {
+    version: GoPcLnTabVersion,
+    little_endian: bool,
+    /// The instruction size quantum; pc deltas in the pc-value tables are multiples of it.
+    quantum: u32,
+    ptr_size: u8,
+    nfunc: usize,
+    /// The address which the function entry offsets are relative to (Go 1.18+).
+    text_start: u64,
+    /// The table with the (entry, funcoff) pairs.
+    functab: &'data [u8],
+    /// The data which funcoff offsets are relative to.
+    func_data: &'data [u8],
+    funcnametab: &'data [u8],
+    cutab: &'data [u8],
+    filetab: &'data [u8],
+    pctab: &'data [u8],
+    /// The address of the `go:func.*` symbol, which funcdata offsets are relative
+    /// to in Go 1.18+. Without it, inline trees can't be found in those versions.
+    go_func: Option<u64>,
+    /// The offset of the nfuncdata byte in the `_func` struct of a Go 1.2 table,
+    /// after the entry. Up to Go 1.9, nfuncdata is an int32 at 28. Go 1.10 split it
+    /// into a u8 funcID, two bytes of padding and a u8 nfuncdata, at 31.
+    go12_nfuncdata_offset: usize,
+    memory: GoMemory<'data>,
+}
+
+/// Read access to the contents of the binary at virtual addresses. This is needed
+/// to read the inline trees, which are referenced by address.
+#[derive(Default)]
+pub struct GoMemory<'data> {
+    ranges: Vec<(u64, &'data [u8])>,
+}
+
+impl<'data> GoMemory<'data> {
+    pub fn new(ranges: Vec<(u64, &'data [u8])>) -> Self {
+        Self { ranges }
+    }
+
+    fn read(&self, address: u64, len: usize) -> Option<&'data [u8]> {
+        self.ranges.iter().find_map(|(start, data)| {
+            let offset = usize::try_from(address.checked_sub(*start)?).ok()?;
+            data.get(offset..offset.checked_add(len)?)
+        })
+    }
+}
+
+/// A function from the Go function table.
+pub struct GoFunction<'data> {
+    pub start_address: u64,
+    pub end_address: u64,
+    pub name: &'data str,
+}
+
+/// A frame at an address, from the Go function table. The function is either the
+/// real function at the address, or a function which was inlined into it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GoFrame<'data> {
+    pub function: &'data str,
+    pub file: Option<&'data str>,
+    pub line: Option<u32>,
+}
+
+/// The fields of a `_func` struct which we care about.
+struct GoFunc {
+    entry: u64,
+    name_offset: u32,
+    pcfile: u32,
+    pcln: u32,
+    npcdata: u32,
+    cu_offset: u32,
+    nfuncdata: u8,
+    /// The offset of this `_func` in `func_data`.
+    offset: usize,
+}
+
+impl<'data> GoPcLnTab<'data> {
+    /// Find and parse the Go function table of `object`, if it has one.
+    pub fn from_object<'file, O>(object: &'file O) -> Option<Self>
+    where
+        'data: 'file,
+        O: Object<'data, 'file>,
+    {
+        let section = [".gopclntab", ".data.rel.ro.gopclntab", "__gopclntab"]
+            .iter()
+            .find_map(|name| object.section_by_name(name))?;
+        let data = section.data().ok()?;
+        let memory = GoMemory::new(
+            object
+                .sections()
+                .filter(|section| {
+                    !matches!(
+                        section.kind(),
+                        SectionKind::UninitializedData | SectionKind::UninitializedTls
+                    )
+                })
+                .filter_map(|section| Some((section.address(), section.data().ok()?)))
+                .collect(),
+        );
+        let mut table = Self::parse(data, object.is_little_endian(), memory)?;
+        if table.version >= GoPcLnTabVersion::Go118 {
+            table.go_func = find_go_func(object, &table, section.address());
+        }
+        Some(table)
+    }
+
+    /// Parse the Go function table in `data`. `memory` is used to read the inline
+    /// trees; funcdata offsets in Go 1.18+ are only resolved once `go_func` is set.
+    pub fn parse(data: &'data [u8], little_endian: bool, memory: GoMemory<'data>) -> Option<Self> {
+        let header = data.get(..8)?;
+        let magic = read_u32(data, 0, little_endian)?;
+        let version = match magic {
+            0xffff_fffb => GoPcLnTabVersion::Go12,
+            0xffff_fffa => GoPcLnTabVersion::Go116,
+            0xffff_fff0 => GoPcLnTabVersion::Go118,
+            0xffff_fff1 => GoPcLnTabVersion::Go120,
+            _ => return None,
+        };
+        let quantum = header[6] as u32;
+        let ptr_size = header[7];
+        if header[4] != 0 || header[5] != 0 || quantum == 0 || !matches!(ptr_size, 4 | 8) {
+            return None;
+        }
+        let word = |index: usize| {
+            let value = read_uint(data, 8 + index * ptr_size as usize, ptr_size, little_endian)?;
+            usize::try_from(value).ok()
+        };
+        let nfunc = word(0)?;
+        let mut table = GoPcLnTab {
+            version,
+            little_endian,
+            quantum,
+            ptr_size,
+            nfunc,
+            text_start: 0,
+            functab: &[],
+            func_data: data,
+            funcnametab: data,
+            cutab: &[],
+            filetab: data,
+            pctab: data,
+            go_func: None,
+            go12_nfuncdata_offset: 31,
+            memory,
+        };
+        match version {
+            GoPcLnTabVersion::Go12 => {
+                table.functab = data.get(8 + ptr_size as usize..)?;
+                // The offset of the file table follows the functab entries and the end pc.
+                let filetab_offset_offset = (nfunc * 2 + 1) * ptr_size as usize;
+                let filetab_offset = read_u32(table.functab, filetab_offset_offset, little_endian)?;
+                table.filetab = data.get(filetab_offset as usize..)?;
+                table.go12_nfuncdata_offset = table.detect_go12_nfuncdata_offset();
+            }
+            GoPcLnTabVersion::Go116 => {
+                table.funcnametab = data.get(word(2)?..)?;
+                table.cutab = data.get(word(3)?..)?;
+                table.filetab = data.get(word(4)?..)?;
+                table.pctab = data.get(word(5)?..)?;
+                table.functab = data.get(word(6)?..)?;
+                table.func_data = table.functab;
+            }
+            GoPcLnTabVersion::Go118 | GoPcLnTabVersion::Go120 => {
+                table.text_start = word(2)? as u64;
+                table.funcnametab = data.get(word(3)?..)?;
+                table.cutab = data.get(word(4)?..)?;
+                table.filetab = data.get(word(5)?..)?;
+                table.pctab = data.get(word(6)?..)?;
+                table.functab = data.get(word(7)?..)?;
+                table.func_data = table.functab;
+            }
+        }
+        Some(table)
+    }
+
+    /// Returns all functions in the table, sorted by address.
+    pub fn functions(&self) -> Vec<GoFunction<'data>> {
+        (0..self.nfunc)
+            .filter_map(|index| {
+                let func = self.func(index)?;
+                Some(GoFunction {
+                    start_address: func.entry,
+                    end_address: self.functab_entry_pc(index + 1)?,
+                    name: self.func_name(func.name_offset)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the frames at `pc`, innermost frame first. If functions were inlined
+    /// at `pc`, there is one frame for each inlined function, followed by the frame
+    /// for the function which contains `pc`.
+    pub fn find_frames(&self, pc: u64) -> Option<Vec<GoFrame<'data>>> {
+        let func = self.func(self.find_func_index(pc)?)?;
+        let mut frames = Vec::new();
+        let mut pc = pc;
+        if let (Some(tree_address), Some(index_table)) = (
+            self.funcdata_address(&func, FUNCDATA_INL_TREE),
+            self.pcdata_offset(&func, PCDATA_INL_TREE_INDEX),
+        ) {
+            let mut index = self.pc_value(index_table, func.entry, pc);
+            // Every step moves to a parent in the inline tree, so the depth is
+            // bounded by the tree size. Guard against cycles in corrupt data anyway.
+            while let Some(tree_index) = index.filter(|index| *index >= 0) {
+                if frames.len() >= 256 {
+                    break;
+                }
+                let (name_offset, parent_pc) =
+                    self.inlined_call(tree_address, tree_index as u64)?;
+                frames.push(GoFrame {
+                    function: self.func_name(name_offset)?,
+                    file: self.file_at(&func, pc),
+                    line: self.line_at(&func, pc),
+                });
+                // The parent pc is an instruction in the caller, which has the line of
+                // the call site.
+                pc = func.entry + parent_pc as u64;
+                index = self.pc_value(index_table, func.entry, pc);
+            }
+        }
+        frames.push(GoFrame {
+            function: self.func_name(func.name_offset)?,
+            file: self.file_at(&func, pc),
+            line: self.line_at(&func, pc),
+        });
+        Some(frames)
+    }
+
+    /// Returns the index of the function whose address range contains `pc`.
+    fn find_func_index(&self, pc: u64) -> Option<usize> {
+        if self.nfunc == 0
+            || pc < self.functab_entry_pc(0)?
+            || pc >= self.functab_entry_pc(self.nfunc)?
+        {
+            return None;
+        }
+        // Find the last entry whose pc is <= `pc`.
+        let (mut low, mut high) = (0, self.nfunc);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.functab_entry_pc(mid)? <= pc {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some(low)
+    }
+
+    /// Returns the pc of the functab entry `index`. The entry at index `nfunc` has
+    /// the end pc of the last function.
+    fn functab_entry_pc(&self, index: usize) -> Option<u64> {
+        match self.version {
+            GoPcLnTabVersion::Go12 | GoPcLnTabVersion::Go116 => read_uint(
+                self.functab,
+                index * 2 * self.ptr_size as usize,
+                self.ptr_size,
+                self.little_endian,
+            ),
+            GoPcLnTabVersion::Go118 | GoPcLnTabVersion::Go120 => {
+                let entry_offset = read_u32(self.functab, index * 8, self.little_endian)?;
+                self.text_start.checked_add(entry_offset as u64)
+            }
+        }
+    }
+
+    /// Returns the offset of the `_func` struct of the function `index` in
+    /// `func_data`, and the offset of the fields after the entry within the struct.
+    fn func_offset(&self, index: usize) -> Option<(usize, usize)> {
+        Some(match self.version {
+            GoPcLnTabVersion::Go12 | GoPcLnTabVersion::Go116 => {
+                let ptr_size = self.ptr_size as usize;
+                let offset = read_uint(
+                    self.functab,
+                    index * 2 * ptr_size + ptr_size,
+                    self.ptr_size,
+                    self.little_endian,
+                )?;
+                (usize::try_from(offset).ok()?, ptr_size)
+            }
+            GoPcLnTabVersion::Go118 | GoPcLnTabVersion::Go120 => {
+                let offset = read_u32(self.functab, index * 8 + 4, self.little_endian)?;
+                (offset as usize, 4)
+            }
+        })
+    }
+
+    /// Find out which `_func` layout a Go 1.2 table has; see `go12_nfuncdata_offset`.
+    ///
+    /// The int32 nfuncdata of Go 1.2-1.9 is small, so in big-endian tables, both
+    /// layouts have nfuncdata in the byte at 31. In little-endian tables, the byte at
+    /// 31 is always zero in the old layout, and it is non-zero in the new layout for
+    /// every function with funcdata, which nearly all functions have.
+    fn detect_go12_nfuncdata_offset(&self) -> usize {
+        if !self.little_endian {
+            return 31;
+        }
+        let has_nfuncdata_at_31 = (0..self.nfunc).any(|index| {
+            let byte_at_31 = self.func_offset(index).and_then(|(offset, field_base)| {
+                self.func_data.get(offset.checked_add(field_base + 31)?)
+            });
+            matches!(byte_at_31, Some(byte) if *byte != 0)
+        });
+        if has_nfuncdata_at_31 {
+            31
+        } else {
+            28
+        }
+    }
+
+    fn func(&self, index: usize) -> Option<GoFunc> {
+        let (offset, field_base) = self.func_offset(index)?;
+        let field = |field_offset: usize| {
+            read_u32(
+                self.func_data,
+                offset + field_base + field_offset,
+                self.little_endian,
+            )
+        };
+        let entry = match self.version {
+            GoPcLnTabVersion::Go12 | GoPcLnTabVersion::Go116 => {
+                read_uint(self.func_data, offset, self.ptr_size, self.little_endian)?
+            }
+            GoPcLnTabVersion::Go118 | GoPcLnTabVersion::Go120 => {
+                let entry_offset = read_u32(self.func_data, offset, self.little_endian)?;
+                self.text_start.checked_add(entry_offset as u64)?
+            }
+        };
+        // The fields after the entry are: nameoff, args, deferreturn, pcsp, pcfile,
+        // pcln, npcdata, and then version-specific fields up to nfuncdata.
+        let (cu_offset, nfuncdata_offset) = match self.version {
+            GoPcLnTabVersion::Go12 => (0, self.go12_nfuncdata_offset),
+            GoPcLnTabVersion::Go116 | GoPcLnTabVersion::Go118 => (field(28)?, 35),
+            // Go 1.20 added a startLine field after cuOffset.
+            GoPcLnTabVersion::Go120 => (field(28)?, 39),
+        };
+        let nfuncdata = *self.func_data.get(offset + field_base + nfuncdata_offset)?;
+        Some(GoFunc {
+            entry,
+            name_offset: field(0)?,
+            pcfile: field(16)?,
+            pcln: field(20)?,
+            npcdata: field(24)?,
+            cu_offset,
+            nfuncdata,
+            offset,
+        })
+    }
+
+    /// Returns the size of the fixed part of the `_func` struct, which is followed
+    /// by the pcdata and funcdata arrays.
+    fn func_struct_size(&self) -> usize {
+        match self.version {
+            GoPcLnTabVersion::Go12 => self.ptr_size as usize + 32,
+            GoPcLnTabVersion::Go116 => self.ptr_size as usize + 36,
+            GoPcLnTabVersion::Go118 => 40,
+            GoPcLnTabVersion::Go120 => 44,
+        }
+    }
+
+    /// Returns the offset of the pc-value table with the index `table` for `func`,
+    /// if it has one.
+    fn pcdata_offset(&self, func: &GoFunc, table: u32) -> Option<u32> {
+        if table >= func.npcdata {
+            return None;
+        }
+        let offset = func.offset + self.func_struct_size() + table as usize * 4;
+        read_u32(self.func_data, offset, self.little_endian).filter(|offset| *offset != 0)
+    }
+
+    /// Returns the address of the funcdata with the index `index` for `func`.
+    fn funcdata_address(&self, func: &GoFunc, index: u32) -> Option<u64> {
+        if index >= func.nfuncdata as u32 {
+            return None;
+        }
+        let pcdata_end = func.offset + self.func_struct_size() + func.npcdata as usize * 4;
+        match self.version {
+            GoPcLnTabVersion::Go12 | GoPcLnTabVersion::Go116 => {
+                // The funcdata array consists of pointers and is pointer-aligned.
+                let ptr_size = self.ptr_size as usize;
+                let start = pcdata_end.div_ceil(ptr_size) * ptr_size;
+                let offset = start + index as usize * ptr_size;
+                read_uint(self.func_data, offset, self.ptr_size, self.little_endian)
+                    .filter(|address| *address != 0)
+            }
+            GoPcLnTabVersion::Go118 | GoPcLnTabVersion::Go120 => {
+                // The funcdata array consists of offsets relative to go:func.*.
+                let offset = pcdata_end + index as usize * 4;
+                let offset = read_u32(self.func_data, offset, self.little_endian)?;
+                if offset == u32::MAX {
+                    return None;
+                }
+                self.go_func?.checked_add(offset as u64)
+            }
+        }
+    }
+
+    /// Returns the name offset and the parent pc offset of the entry `index` of the
+    /// inline tree at `tree_address`.
+    fn inlined_call(&self, tree_address: u64, index: u64) -> Option<(u32, u32)> {
+        match self.version {
+            GoPcLnTabVersion::Go120 => {
+                // struct { funcID uint8; _ [3]byte; nameOff int32; parentPc int32; startLine int32 }
+                let call = self
+                    .memory
+                    .read(tree_address.checked_add(index * 16)?, 16)?;
+                Some((
+                    read_u32(call, 4, self.little_endian)?,
+                    read_u32(call, 8, self.little_endian)?,
+                ))
+            }
+            _ => {
+                // struct { parent int16; funcID uint8; _ byte; file int32; line int32;
+                //          func_ int32; parentPc int32 }
+                let call = self
+                    .memory
+                    .read(tree_address.checked_add(index * 20)?, 20)?;
+                Some((
+                    read_u32(call, 12, self.little_endian)?,
+                    read_u32(call, 16, self.little_endian)?,
+                ))
+            }
+        }
+    }
+
+    fn func_name(&self, name_offset: u32) -> Option<&'data str> {
+        read_cstr(self.funcnametab, name_offset as usize)
+    }
+
+    fn file_at(&self, func: &GoFunc, pc: u64) -> Option<&'data str> {
+        let file_index = self.pc_value(func.pcfile, func.entry, pc)?;
+        let file_index = u32::try_from(file_index).ok()?;
+        let file_offset = match self.version {
+            GoPcLnTabVersion::Go12 => {
+                read_u32(self.filetab, file_index as usize * 4, self.little_endian)?
+            }
+            _ => {
+                let cu_index = func.cu_offset.checked_add(file_index)? as usize;
+                read_u32(self.cutab, cu_index * 4, self.little_endian)?
+            }
+        };
+        if file_offset == u32::MAX {
+            return None;
+        }
+        match self.version {
+            GoPcLnTabVersion::Go12 => read_cstr(self.func_data, file_offset as usize),
+            _ => read_cstr(self.filetab, file_offset as usize),
+        }
+    }
+
+    fn line_at(&self, func: &GoFunc, pc: u64) -> Option<u32> {
+        let line = self.pc_value(func.pcln, func.entry, pc)?;
+        u32::try_from(line).ok().filter(|line| *line != 0)
+    }
+
+    /// Look up the value for `target_pc` in the pc-value table at `table_offset`.
+    ///
+    /// The table is a sequence of (value delta, pc delta) pairs of varints, starting
+    /// with the value -1 at the function entry. Value deltas are zig-zag encoded, pc
+    /// deltas are in units of the instruction size quantum. The table ends with a
+    /// zero value delta, except for the first pair.
+    fn pc_value(&self, table_offset: u32, entry: u64, target_pc: u64) -> Option<i32> {
+        if table_offset == 0 {
+            return None;
+        }
+        let mut table = self.pctab.get(table_offset as usize..)?;
+        let mut pc = entry;
+        let mut value: i32 = -1;
+        let mut first = true;
+        loop {
+            let (uvdelta, len) = read_varint(table)?;
+            if uvdelta == 0 && !first {
+                return None;
+            }
+            table = &table[len..];
+            let vdelta = if uvdelta & 1 != 0 {
+                !(uvdelta >> 1)
+            } else {
+                uvdelta >> 1
+            } as i32;
+            let (pcdelta, len) = read_varint(table)?;
+            table = &table[len..];
+            pc = pc.checked_add(pcdelta as u64 * self.quantum as u64)?;
+            value = value.wrapping_add(vdelta);
+            if target_pc < pc {
+                return Some(value);
+            }
+            first = false;
+        }
+    }
+}
+
+/// Find the address of the `go:func.*` symbol, which funcdata offsets are relative
+/// to in Go 1.18+. If the binary has no symbol table, the address is read from the
+/// runtime's module data (`runtime.firstmoduledata`), which is found by looking for
+/// a pointer to the function table in the .noptrdata section.
+fn find_go_func<'data: 'file, 'file>(
+    object: &'file impl Object<'data, 'file>,
+    table: &GoPcLnTab<'data>,
+    pclntab_address: u64,
+) -> Option<u64> {
+    if let Some(symbol) = object
+        .symbols()
+        .find(|symbol| matches!(symbol.name(), Ok("go:func.*") | Ok("go.func.*")))
+    {
+        return Some(symbol.address());
+    }
+
+    // The moduledata struct starts with a pointer to the function table header,
+    // followed by six slices. The text start address is at word 22, and gofunc
+    // follows some more fields; Go 1.20 added two more fields before it.
+    const TEXT_WORD: usize = 22;
+    let go_func_word = match table.version {
+        GoPcLnTabVersion::Go120 => 40,
+        _ => 38,
+    };
+    let ptr_size = table.ptr_size as usize;
+    let read_word = |data: &[u8], word: usize| {
+        read_uint(data, word * ptr_size, table.ptr_size, table.little_endian)
+    };
+    object
+        .sections()
+        .filter(|section| matches!(section.name(), Ok(name) if name.ends_with("noptrdata")))
+        .filter_map(|section| section.data().ok())
+        .find_map(|data| {
+            (0..data.len() / ptr_size).find_map(|word| {
+                let moduledata = &data[word * ptr_size..];
+                if read_word(moduledata, 0)? != pclntab_address
+                    || read_word(moduledata, TEXT_WORD)? != table.text_start
+                {
+                    return None;
+                }
+                read_word(moduledata, go_func_word)
+            })
+        })
+}
+
+/// Make function symbols for the functions in the Go function table. The addresses
+/// are made relative to `base_address`.
+pub fn collect_gopclntab_function_symbols(
+    table: &GoPcLnTab,
+    base_address: u64,
+) -> Vec<AdditionalSymbol> {
+    table
+        .functions()
+        .into_iter()
+        .filter_map(|function| {
+            Some(AdditionalSymbol {
+                address: u32::try_from(function.start_address.checked_sub(base_address)?).ok()?,
+                size: Some(u32::try_from(function.end_address - function.start_address).ok()?),
+                name: function.name.to_string(),
+                source: SymbolSource::GoPclntab,
+            })
+        })
+        .collect()
+}
+
+/// Add file, line and inline information from the Go function table for `addresses`.
+/// This is used for Go binaries which don't have DWARF debug info.
+pub fn collect_gopclntab_address_debug_data<R>(
+    table: &GoPcLnTab,
+    addresses: &[AddressPair],
+    symbolication_result: &mut R,
+    path_mapper: &mut PathMapper<()>,
+) where
+    R: SymbolicationResult,
+{
+    for AddressPair {
+        original_relative_address,
+        vmaddr_in_this_object,
+    } in addresses
+    {
+        if let Some(frames) = table.find_frames(*vmaddr_in_this_object) {
+            let frames = frames
+                .into_iter()
+                .map(|frame| InlineStackFrame {
+                    function: Some(frame.function.to_string()),
+                    file_path: frame.file.map(|file| path_mapper.map_path(file)),
+                    file_checksum: None,
+                    line_number: frame.line,
+                    column_number: None,
+                    function_decl_file_path: None,
+                    function_decl_line_number: None,
+                    inline_range_start: None,
+                })
+                .collect();
+            symbolication_result
+                .add_address_debug_info(*original_relative_address, AddressDebugInfo { frames });
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+    Some(if little_endian {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+fn read_uint(data: &[u8], offset: usize, size: u8, little_endian: bool) -> Option<u64> {
+    match size {
+        4 => read_u32(data, offset, little_endian).map(u64::from),
+        8 => {
+            let bytes: [u8; 8] = data.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+            Some(if little_endian {
+                u64::from_le_bytes(bytes)
+            } else {
+                u64::from_be_bytes(bytes)
+            })
+        }
+        _ => None,
+    }
+}
+
+fn read_cstr(data: &[u8], offset: usize) -> Option<&str> {
+    let bytes = data.get(offset..)?;
+    let len = bytes.iter().position(|b| *b == 0)?;
+    std::str::from_utf8(&bytes[..len]).ok()
+}
+
+/// Read an unsigned LEB128 varint, and return its value and length.
+fn read_varint(data: &[u8]) -> Option<(u32, usize)> {
+    let mut value = 0u32;
+    for (i, byte) in data.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Build a Go 1.20 function table with two functions, main.main at 0x1000 and
+    /// main.helper at 0x1040. main.helper is inlined into main.main at 0x1010..0x1020.
+    fn make_go120_table() -> (Vec<u8>, Vec<u8>) {
+        fn push_u32(data: &mut Vec<u8>, value: u32) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let funcnametab = b"main.main\0main.helper\0";
+        let filetab = b"main.go\0helper.go\0";
+        let mut cutab = Vec::new();
+        push_u32(&mut cutab, 0);
+        push_u32(&mut cutab, 8);
+        // The tables for main.main: file, line and inline tree index.
+        let pctab: &[u8] = &[
+            0, // offset 0 means "no table"
+            2, 0x10, 2, 0x10, 1, 0x20, 0, // pcfile at 1: main.go, helper.go, main.go
+            22, 0x10, 9, 0x10, 12, 1, 2, 0x1f, 0, // pcln at 8: 10, 5, 11, 12
+            0, 0x10, 2, 0x10, 1, 0x20, 0, // inline index at 17: -1, 0, -1
+            4, 0x20, 0, // pcfile at 24 for main.helper: helper.go
+            8, 0x20, 0, // pcln at 27 for main.helper: 3
+        ];
+
+        let header_size = 8 + 8 * 8;
+        let funcnametab_offset = header_size;
+        let cutab_offset = funcnametab_offset + funcnametab.len();
+        let filetab_offset = cutab_offset + cutab.len();
+        let pctab_offset = filetab_offset + filetab.len();
+        let functab_offset = pctab_offset + pctab.len();
+
+        let mut functab = Vec::new();
+        let main_offset = 2 * 8 + 4;
+        let helper_offset = main_offset + 44 + 3 * 4 + 4 * 4;
+        for (entry, func_offset) in [(0, main_offset), (0x40, helper_offset)] {
+            push_u32(&mut functab, entry);
+            push_u32(&mut functab, func_offset);
+        }
+        push_u32(&mut functab, 0x60);
+
+        // main.main
+        for value in [0, 0, 0, 0, 0, 1, 8, 3, 0, 10] {
+            push_u32(&mut functab, value);
+        }
+        functab.extend_from_slice(&[0, 0, 0, 4]);
+        for value in [0, 0, 17] {
+            push_u32(&mut functab, value);
+        }
+        for value in [u32::MAX, u32::MAX, u32::MAX, 0] {
+            push_u32(&mut functab, value);
+        }
+        // main.helper
+        for value in [0x40, 10, 0, 0, 0, 24, 27, 0, 0, 3] {
+            push_u32(&mut functab, value);
+        }
+        functab.extend_from_slice(&[0, 0, 0, 0]);
+
+        let mut data = vec![0xf1, 0xff, 0xff, 0xff, 0, 0, 1, 8];
+        for value in [
+            2,
+            2,
+            0x1000,
+            funcnametab_offset,
+            cutab_offset,
+            filetab_offset,
+            pctab_offset,
+            functab_offset,
+        ] {
+            data.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        data.extend_from_slice(funcnametab);
+        data.extend_from_slice(&cutab);
+        data.extend_from_slice(filetab);
+        data.extend_from_slice(pctab);
+        data.extend_from_slice(&functab);
+
+        // The inline tree of main.main: main.helper, called from pc offset 0x20.
+        let mut inline_tree = Vec::new();
+        for value in [0, 10, 0x20, 3] {
+            push_u32(&mut inline_tree, value);
+        }
+        (data, inline_tree)
+    }
+
+    /// Build a Go 1.2 function table with the same functions, line tables and inline
+    /// tree as `make_go120_table`. If `int32_nfuncdata` is true, the `_func` structs
+    /// have the layout from before Go 1.10, with an int32 nfuncdata field.
+    fn make_go12_table(int32_nfuncdata: bool) -> (Vec<u8>, Vec<u8>) {
+        fn push_u32(data: &mut Vec<u8>, value: u32) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        fn push_u64(data: &mut Vec<u8>, value: u64) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // The offsets in Go 1.2 tables are relative to the start of the table.
+        let functab_offset = 16;
+        let main_offset = 64;
+        let helper_offset = main_offset + 8 + 32 + 3 * 4 + 4 + 4 * 8;
+        let funcnametab_offset = helper_offset + 8 + 32;
+        let funcnametab = b"main.main\0main.helper\0";
+        let helper_name_offset = funcnametab_offset + 10;
+        let filenames_offset = funcnametab_offset + funcnametab.len();
+        let filenames = b"main.go\0helper.go\0";
+        let filetab_offset = filenames_offset + filenames.len();
+        let pctab_offset = filetab_offset + 3 * 4;
+        // The tables for main.main: file, line and inline tree index. File indexes
+        // start at 1.
+        let pctab: &[u8] = &[
+            4, 0x10, 2, 0x10, 1, 0x20, 0, // pcfile at 0: main.go, helper.go, main.go
+            22, 0x10, 9, 0x10, 12, 1, 2, 0x1f, 0, // pcln at 7: 10, 5, 11, 12
+            0, 0x10, 2, 0x10, 1, 0x20, 0, // inline index at 16: -1, 0, -1
+            6, 0x20, 0, // pcfile at 23 for main.helper: helper.go
+            8, 0x20, 0, // pcln at 26 for main.helper: 3
+        ];
+        let nfuncdata = |nfuncdata: u8| {
+            if int32_nfuncdata {
+                [nfuncdata, 0, 0, 0]
+            } else {
+                [0, 0, 0, nfuncdata]
+            }
+        };
+
+        let mut data = vec![0xfb, 0xff, 0xff, 0xff, 0, 0, 1, 8];
+        push_u64(&mut data, 2);
+        assert_eq!(data.len(), functab_offset);
+        for (entry, func_offset) in [(0x1000, main_offset), (0x1040, helper_offset)] {
+            push_u64(&mut data, entry);
+            push_u64(&mut data, func_offset as u64);
+        }
+        push_u64(&mut data, 0x1060);
+        push_u32(&mut data, filetab_offset as u32);
+        data.resize(main_offset, 0);
+
+        // main.main, with three pcdata tables, and the inline tree as its fourth
+        // funcdata, after padding to pointer alignment.
+        push_u64(&mut data, 0x1000);
+        for value in [
+            funcnametab_offset,
+            0,
+            0,
+            0,
+            pctab_offset,
+            pctab_offset + 7,
+            3,
+        ] {
+            push_u32(&mut data, value as u32);
+        }
+        data.extend_from_slice(&nfuncdata(4));
+        for value in [0, 0, pctab_offset + 16] {
+            push_u32(&mut data, value as u32);
+        }
+        push_u32(&mut data, 0);
+        for value in [0, 0, 0, 0x5000] {
+            push_u64(&mut data, value);
+        }
+        // main.helper
+        assert_eq!(data.len(), helper_offset);
+        push_u64(&mut data, 0x1040);
+        for value in [
+            helper_name_offset,
+            10,
+            0,
+            0,
+            pctab_offset + 23,
+            pctab_offset + 26,
+            0,
+        ] {
+            push_u32(&mut data, value as u32);
+        }
+        data.extend_from_slice(&nfuncdata(0));
+
+        data.extend_from_slice(funcnametab);
+        data.extend_from_slice(filenames);
+        for value in [3, filenames_offset, filenames_offset + 8] {
+            push_u32(&mut data, value as u32);
+        }
+        data.extend_from_slice(pctab);
+
+        // The inline tree of main.main: main.helper, called from pc offset 0x20.
+        let mut inline_tree = vec![0xff, 0xff, 0, 0];
+        for value in [0, 0, helper_name_offset as u32, 0x20] {
+            push_u32(&mut inline_tree, value);
+        }
+        (data, inline_tree)
+    }
+
+    #[test]
+    fn go12_functions_and_frames() {
+        for int32_nfuncdata in [false, true] {
+            let (data, inline_tree) = make_go12_table(int32_nfuncdata);
+            let memory = GoMemory::new(vec![(0x5000, &inline_tree[..])]);
+            let table = GoPcLnTab::parse(&data, true, memory).unwrap();
+
+            let functions: Vec<_> = table
+                .functions()
+                .into_iter()
+                .map(|f| (f.start_address, f.end_address, f.name))
+                .collect();
+            assert_eq!(
+                functions,
+                vec![
+                    (0x1000, 0x1040, "main.main"),
+                    (0x1040, 0x1060, "main.helper")
+                ]
+            );
+
+            let frame = |function, file, line| GoFrame {
+                function,
+                file: Some(file),
+                line: Some(line),
+            };
+            assert_eq!(
+                table.find_frames(0x1005),
+                Some(vec![frame("main.main", "main.go", 10)])
+            );
+            assert_eq!(
+                table.find_frames(0x1015),
+                Some(vec![
+                    frame("main.helper", "helper.go", 5),
+                    frame("main.main", "main.go", 11)
+                ])
+            );
+            assert_eq!(
+                table.find_frames(0x1045),
+                Some(vec![frame("main.helper", "helper.go", 3)])
+            );
+        }
+    }
+
+    #[test]
+    fn go120_functions_and_frames() {
+        let (data, inline_tree) = make_go120_table();
+        let memory = GoMemory::new(vec![(0x5000, &inline_tree[..])]);
+        let mut table = GoPcLnTab::parse(&data, true, memory).unwrap();
+        table.go_func = Some(0x5000);
+
+        let functions: Vec<_> = table
+            .functions()
+            .into_iter()
+            .map(|f| (f.start_address, f.end_address, f.name))
+            .collect();
+        assert_eq!(
+            functions,
+            vec![
+                (0x1000, 0x1040, "main.main"),
+                (0x1040, 0x1060, "main.helper")
+            ]
+        );
+
+        let frame = |function, file, line| GoFrame {
+            function,
+            file: Some(file),
+            line: Some(line),
+        };
+        assert_eq!(
+            table.find_frames(0x1005),
+            Some(vec![frame("main.main", "main.go", 10)])
+        );
+        assert_eq!(
+            table.find_frames(0x1015),
+            Some(vec![
+                frame("main.helper", "helper.go", 5),
+                frame("main.main", "main.go", 11)
+            ])
+        );
+        assert_eq!(
+            table.find_frames(0x1030),
+            Some(vec![frame("main.main", "main.go", 12)])
+        );
+        assert_eq!(
+            table.find_frames(0x1045),
+            Some(vec![frame("main.helper", "helper.go", 3)])
+        );
+        assert_eq!(table.find_frames(0x1060), None);
+        assert_eq!(table.find_frames(0xfff), None);
+    }
+}
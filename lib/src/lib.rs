@@ -130,24 +130,35 @@ mod chunked_read_buffer_manager;
 mod compact_symbol_table;
 mod debugid_util;
 mod dwarf;
+mod dyld_cache;
 mod elf;
 mod error;
+mod gopclntab;
 mod macho;
 mod path_mapper;
 mod shared;
 mod source;
+mod sourcelink;
 mod symbolicate;
+#[cfg(test)]
+mod test_helper;
 mod windows;
 
 pub use crate::cache::{FileByteSource, FileContentsWithChunkedCaching};
 pub use crate::compact_symbol_table::CompactSymbolTable;
+pub use crate::dyld_cache::{
+    DyldCacheAddress, DyldSharedCache, DyldSharedCacheImage, DyldSharedCacheSegment,
+};
 pub use crate::error::{GetSymbolsError, Result};
+pub use crate::path_mapper::{PathMappingRule, PathMappingTarget};
 use crate::shared::FileContentsWrapper;
 pub use crate::shared::{
-    AddressDebugInfo, CandidatePathInfo, FileAndPathHelper, FileAndPathHelperError,
-    FileAndPathHelperResult, FileContents, FileLocation, FilePath, OptionallySendFuture,
-    SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
+    AddressDebugInfo, AddressSymbolInfo, CandidatePathInfo, FileAndPathHelper,
+    FileAndPathHelperError, FileAndPathHelperResult, FileContents, FileLocation, FilePath,
+    OptionallySendFuture, QuerySymbolKind, ResolvedSpecialPath, SourceFileChecksum, SpecialPath,
+    SymbolSource, SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
 };
+pub use crate::symbolicate::demangle::{DemangleOptions, NameFormat};
 pub use debugid_util::{debug_id_for_object, DebugIdExt};
 
 pub(crate) fn to_debug_id(breakpad_id: &str) -> Result<DebugId> {
@@ -167,12 +178,28 @@ pub async fn get_compact_symbol_table<'h>(
             debug_name,
             debug_id,
             result_kind: SymbolicationResultKind::AllSymbols,
+            attribute_split_functions_to_parent: false,
+            symbol_kind: QuerySymbolKind::Functions,
+            path_mapping_rules: &[],
         },
         helper,
     )
     .await
 }
 
+/// Like `get_compact_symbol_table`, but with the symbol names demangled and
+/// simplified according to `demangle_options`. This is opt-in: `get_compact_symbol_table`,
+/// and the wasm binding which wraps it, return the names as they are stored in the binary.
+pub async fn get_compact_symbol_table_with_options<'h>(
+    debug_name: &str,
+    debug_id: DebugId,
+    demangle_options: &DemangleOptions,
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<CompactSymbolTable> {
+    let table = get_compact_symbol_table(debug_name, debug_id, helper).await?;
+    Ok(table.with_formatted_names(demangle_options))
+}
+
 /// A generic method which is used in the implementation of both `get_compact_symbol_table`
 /// and `query_api`. Allows obtaining symbol data for a given binary. The level of detail
 /// is determined by `query.result_kind`: The caller can
@@ -186,6 +213,23 @@ pub async fn get_symbolication_result<'h, R>(
 where
     R: SymbolicationResult,
 {
+    let helper_path_mapping_rules = helper.get_path_mapping_rules();
+    let path_mapping_rules: Vec<PathMappingRule>;
+    let query = if helper_path_mapping_rules.is_empty() {
+        query
+    } else {
+        path_mapping_rules = query
+            .path_mapping_rules
+            .iter()
+            .cloned()
+            .chain(helper_path_mapping_rules)
+            .collect();
+        SymbolicationQuery {
+            path_mapping_rules: &path_mapping_rules,
+            ..query
+        }
+    };
+
     let candidate_paths_for_binary = helper
         .get_candidate_paths_for_binary_or_pdb(query.debug_name, &query.debug_id)
         .map_err(|e| {
@@ -198,7 +242,17 @@ where
 
     let mut last_err = None;
     for candidate_info in candidate_paths_for_binary {
-        let result = match candidate_info {
+        let symbol_file_location = match &candidate_info {
+            CandidatePathInfo::SingleFile(file_location) => file_location.clone(),
+            CandidatePathInfo::InDyldCache {
+                dyld_cache_path, ..
+            }
+            | CandidatePathInfo::InDyldCacheByDebugId { dyld_cache_path } => {
+                FileLocation::Path(dyld_cache_path.clone())
+            }
+            CandidatePathInfo::BinaryWithDebugFile { debug_file, .. } => debug_file.clone(),
+        };
+        let result: Result<R> = match candidate_info {
             CandidatePathInfo::SingleFile(file_location) => {
                 try_get_symbolication_result_from_path(query.clone(), &file_location, helper).await
             }
@@ -209,7 +263,25 @@ where
                 macho::try_get_symbolication_result_from_dyld_shared_cache(
                     query.clone(),
                     &dyld_cache_path,
-                    &dylib_path,
+                    Some(&dylib_path),
+                    helper,
+                )
+                .await
+            }
+            CandidatePathInfo::InDyldCacheByDebugId { dyld_cache_path } => {
+                macho::try_get_symbolication_result_from_dyld_shared_cache(
+                    query.clone(),
+                    &dyld_cache_path,
+                    None,
+                    helper,
+                )
+                .await
+            }
+            CandidatePathInfo::BinaryWithDebugFile { binary, debug_file } => {
+                try_get_symbolication_result_from_binary_and_debug_file(
+                    query.clone(),
+                    &binary,
+                    &debug_file,
                     helper,
                 )
                 .await
@@ -217,7 +289,10 @@ where
         };
 
         match result {
-            Ok(result) => return Ok(result),
+            Ok(mut result) => {
+                result.set_symbol_file_location(&symbol_file_location);
+                return Ok(result);
+            }
             Err(err) => last_err = Some(err),
         };
     }
@@ -241,6 +316,8 @@ where
 ///    i.e. files, lines and inlines. This is faster.
 ///  - `/source/v1`: Experimental API. Symbolicates an address and lets you read one of the files in the
 ///    symbol information for that address.
+///  - `/source/v1/batch`: Like `/source/v1`, but returns the files of all frames of the address,
+///    including inlined frames, in one call.
 pub async fn query_api<'h>(
     request_url: &str,
     request_json_data: &str,
@@ -252,6 +329,8 @@ pub async fn query_api<'h>(
         symbolicate::v5::query_api_json(request_json_data, helper, true).await
     } else if request_url == "/source/v1" {
         source::query_api_json(request_json_data, helper).await
+    } else if request_url == "/source/v1/batch" {
+        source::query_batch_api_json(request_json_data, helper).await
     } else {
         json!({ "error": format!("Unrecognized URL {}", request_url) }).to_string()
     }
@@ -331,3 +410,83 @@ where
         ))
     }
 }
+
+async fn try_get_symbolication_result_from_binary_and_debug_file<'h, R, H>(
+    query: SymbolicationQuery<'_>,
+    binary_location: &FileLocation,
+    debug_file_location: &FileLocation,
+    helper: &'h H,
+) -> Result<R>
+where
+    R: SymbolicationResult,
+    H: FileAndPathHelper<'h>,
+{
+    let binary_contents = helper.open_file(binary_location).await.map_err(|e| {
+        GetSymbolsError::HelperErrorDuringOpenFile(binary_location.to_string_lossy(), e)
+    })?;
+    let binary_contents = FileContentsWrapper::new(binary_contents);
+    let debug_contents = helper.open_file(debug_file_location).await.map_err(|e| {
+        GetSymbolsError::HelperErrorDuringOpenFile(debug_file_location.to_string_lossy(), e)
+    })?;
+    let debug_contents = FileContentsWrapper::new(debug_contents);
+    let base_path = debug_file_location.to_base_path();
+
+    let binary_kind = FileKind::parse(&binary_contents)
+        .map_err(|_| GetSymbolsError::InvalidInputError("Unrecognized binary file format"))?;
+    let debug_kind = FileKind::parse(&debug_contents)
+        .map_err(|_| GetSymbolsError::InvalidInputError("Unrecognized debug file format"))?;
+
+    match (binary_kind, debug_kind) {
+        (FileKind::Elf32 | FileKind::Elf64, FileKind::Elf32 | FileKind::Elf64) => {
+            elf::get_symbolication_result_with_debug_file(
+                &base_path,
+                binary_kind,
+                binary_contents,
+                debug_kind,
+                debug_contents,
+                query,
+            )
+        }
+        (
+            FileKind::MachO32 | FileKind::MachO64 | FileKind::MachOFat32 | FileKind::MachOFat64,
+            FileKind::MachO32 | FileKind::MachO64 | FileKind::MachOFat32 | FileKind::MachOFat64,
+        ) => {
+            let binary_range = macho_arch_range(binary_kind, &binary_contents, query.debug_id)?;
+            let debug_range = macho_arch_range(debug_kind, &debug_contents, query.debug_id)?;
+            macho::get_symbolication_result_with_dsym(
+                &base_path,
+                binary_contents,
+                binary_range,
+                debug_contents,
+                debug_range,
+                query,
+            )
+        }
+        _ => Err(GetSymbolsError::InvalidInputError(
+            "The binary and the debug file need to be either both ELF files or both mach-O files",
+        )),
+    }
+}
+
+/// Returns the range of the architecture matching `debug_id` if the file is a fat
+/// mach-O file, and `None` for a thin mach-O file.
+fn macho_arch_range(
+    file_kind: FileKind,
+    file_contents: &FileContentsWrapper<impl FileContents>,
+    debug_id: DebugId,
+) -> Result<Option<(u64, u64)>> {
+    let range = match file_kind {
+        FileKind::MachOFat32 => {
+            let arches = FatHeader::parse_arch32(file_contents)
+                .map_err(|e| GetSymbolsError::ObjectParseError(file_kind, e))?;
+            macho::get_arch_range(file_contents, arches, debug_id)?
+        }
+        FileKind::MachOFat64 => {
+            let arches = FatHeader::parse_arch64(file_contents)
+                .map_err(|e| GetSymbolsError::ObjectParseError(file_kind, e))?;
+            macho::get_arch_range(file_contents, arches, debug_id)?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(range))
+}
@@ -1,21 +1,34 @@
 use crate::debugid_util::debug_id_for_object;
 use crate::dwarf::{
-    collect_dwarf_address_debug_data, make_address_pairs_for_root_object, AddressPair,
+    collect_dwarf_address_debug_data, has_dwarf_debug_info, make_address_pairs_for_root_object,
+    AddressPair,
 };
+use crate::dyld_cache::open_dyld_cache_files;
 use crate::error::{GetSymbolsError, Result};
+use crate::gopclntab::{
+    collect_gopclntab_address_debug_data, collect_gopclntab_function_symbols, GoPcLnTab,
+};
 use crate::path_mapper::PathMapper;
 use crate::shared::{
-    get_symbolication_result_for_addresses_from_object, object_to_map, BasePath, FileAndPathHelper,
-    FileContents, FileContentsWrapper, FileLocation, RangeReadRef, SymbolicationQuery,
-    SymbolicationResult, SymbolicationResultKind,
+    get_data_symbolication_result_from_object, get_symbolication_result_for_addresses_from_object,
+    has_poor_symbol_coverage, object_data_symbols, object_function_symbols, object_to_map,
+    relative_address_base, AdditionalSymbol, BasePath, FileAndPathHelper, FileContents,
+    FileContentsWrapper, FileLocation, QuerySymbolKind, RangeReadRef, StubSymbol, SymbolSource,
+    SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
 };
 use debugid::DebugId;
 use macho_unwind_info::UnwindInfo;
-use object::macho::{self, LinkeditDataCommand, MachHeader32, MachHeader64};
-use object::read::macho::{FatArch, LoadCommandIterator, MachHeader};
-use object::read::{archive::ArchiveFile, File, Object, ObjectSection, ObjectSymbol};
-use object::{Endianness, ObjectMapEntry, ReadRef};
-use std::collections::{HashMap, VecDeque};
+use object::macho::{self, DyldCacheHeader, LinkeditDataCommand, MachHeader32, MachHeader64};
+use object::read::macho::{DyldCache, FatArch, LoadCommandIterator, MachHeader, Nlist, Segment};
+use object::read::{
+    archive::ArchiveFile, File, Object, ObjectSection, ObjectSegment, ObjectSymbol,
+};
+use object::{
+    Architecture, Endian, Endianness, ObjectMapEntry, ReadRef, SectionKind, SymbolIndex,
+    SymbolKind, U32,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::{TryFrom, TryInto};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 
@@ -49,62 +62,52 @@ pub fn get_arch_range(
     Err(GetSymbolsError::NoMatchMultiArch(debug_ids, errors))
 }
 
+/// Symbolicate a library in the dyld shared cache at `dyld_cache_path`. The library
+/// is found by its path, if `dylib_path` is given, and by `query.debug_id` otherwise.
 pub async fn try_get_symbolication_result_from_dyld_shared_cache<'h, R, H>(
     query: SymbolicationQuery<'_>,
     dyld_cache_path: &Path,
-    dylib_path: &str,
+    dylib_path: Option<&str>,
     helper: &'h H,
 ) -> Result<R>
 where
     R: SymbolicationResult,
     H: FileAndPathHelper<'h>,
 {
-    let root_contents = helper
-        .open_file(&FileLocation::Path(dyld_cache_path.to_path_buf()))
-        .await
-        .map_err(|e| {
-            GetSymbolsError::HelperErrorDuringOpenFile(
-                dyld_cache_path.to_string_lossy().to_string(),
-                e,
-            )
-        })?;
-    let root_contents = FileContentsWrapper::new(root_contents);
-
-    let dyld_cache_path = dyld_cache_path.to_string_lossy();
-
-    let mut subcache_contents = Vec::new();
-    for subcache_index in 1.. {
-        let subcache_path = format!("{}.{}", dyld_cache_path, subcache_index);
-        match helper
-            .open_file(&FileLocation::Path(subcache_path.into()))
-            .await
-        {
-            Ok(subcache) => subcache_contents.push(FileContentsWrapper::new(subcache)),
-            Err(_) => break,
-        };
-    }
-    let symbols_subcache_path = format!("{}.symbols", dyld_cache_path);
-    if let Ok(subcache) = helper
-        .open_file(&FileLocation::Path(symbols_subcache_path.into()))
-        .await
-    {
-        subcache_contents.push(FileContentsWrapper::new(subcache));
-    };
+    let (root_contents, subcache_contents) = open_dyld_cache_files(dyld_cache_path, helper).await?;
+    get_symbolication_result_from_dyld_cache(query, &root_contents, &subcache_contents, dylib_path)
+}
 
-    let subcache_contents_refs: Vec<&FileContentsWrapper<H::F>> =
-        subcache_contents.iter().collect();
+pub fn get_symbolication_result_from_dyld_cache<R, T>(
+    query: SymbolicationQuery<'_>,
+    root_contents: &FileContentsWrapper<T>,
+    subcache_contents: &[FileContentsWrapper<T>],
+    dylib_path: Option<&str>,
+) -> Result<R>
+where
+    R: SymbolicationResult,
+    T: FileContents,
+{
+    let subcache_contents_refs: Vec<&FileContentsWrapper<T>> = subcache_contents.iter().collect();
     let cache = object::read::macho::DyldCache::<Endianness, _>::parse(
-        &root_contents,
+        root_contents,
         &subcache_contents_refs,
     )
     .map_err(GetSymbolsError::DyldCacheParseError)?;
-    let image = match cache.images().find(|image| image.path() == Ok(dylib_path)) {
-        Some(image) => image,
-        None => {
-            return Err(GetSymbolsError::NoMatchingDyldCacheImagePath(
-                dylib_path.to_string(),
-            ))
-        }
+    let image = match dylib_path {
+        Some(dylib_path) => cache
+            .images()
+            .find(|image| image.path() == Ok(dylib_path))
+            .ok_or_else(|| GetSymbolsError::NoMatchingDyldCacheImagePath(dylib_path.to_string()))?,
+        None => cache
+            .images()
+            .find(|image| {
+                let object = image.parse_object().ok();
+                matches!(object.as_ref().and_then(debug_id_for_object), Some(debug_id) if debug_id == query.debug_id)
+            })
+            .ok_or(GetSymbolsError::NoMatchingDyldCacheImageDebugId(
+                query.debug_id,
+            ))?,
     };
 
     let object = image
@@ -114,13 +117,21 @@ where
     let (data, header_offset) = image
         .image_data_and_offset()
         .map_err(GetSymbolsError::MachOHeaderParseError)?;
-    let macho_data = MachOData::new(data, header_offset, object.is_64());
-    get_symbolication_result_from_macho_object(&object, macho_data, query)
+    let mut cache_data = vec![root_contents];
+    cache_data.extend(subcache_contents_refs.iter().copied());
+    let macho_data = MachOData::new(data, header_offset, object.is_64())
+        .with_dyld_cache_memory(DyldCacheMemory::new(&cache, &cache_data));
+    get_symbolication_result_from_macho_object(&object, macho_data, None::<&File>, query)
 }
 
-pub fn get_symbolication_result_from_macho_object<'a, 'data, R, RR: ReadRef<'data>>(
+/// Symbolicate `macho_file` using its symbol tables, stubs and function starts.
+/// If `symbol_file` is given, for example the DWARF file from a dSYM bundle, its
+/// symbols are used in addition to the symbols of `macho_file`. This is useful if
+/// `macho_file` is stripped.
+pub fn get_symbolication_result_from_macho_object<'a, 'data, 'sdata, R, RR: ReadRef<'data>>(
     macho_file: &File<'data, RR>,
     macho_data: MachOData<'data, RR>,
+    symbol_file: Option<&File<'sdata, impl ReadRef<'sdata>>>,
     query: SymbolicationQuery<'a>,
 ) -> Result<R>
 where
@@ -137,6 +148,18 @@ where
         ));
     }
 
+    if query.symbol_kind == QuerySymbolKind::Data {
+        let extra_symbols = match symbol_file {
+            Some(symbol_file) => object_data_symbols(symbol_file),
+            None => Vec::new(),
+        };
+        return Ok(get_data_symbolication_result_from_object(
+            macho_file,
+            query.result_kind,
+            extra_symbols,
+        ));
+    }
+
     // Get function start addresses from LC_FUNCTION_STARTS
     let mut function_starts = macho_data.get_function_starts()?;
 
@@ -153,20 +176,120 @@ where
         }
     }
 
-    match query.result_kind {
+    // Synthesize symbols for the stubs in __stubs and __auth_stubs. This is best
+    // effort: if the stubs or the indirect symbol table can't be parsed, we still
+    // have the other symbols.
+    let base_address = relative_address_base(macho_file);
+    let stub_symbols: Vec<StubSymbol> = macho_data
+        .get_symbol_stubs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(address, size, symbol_index)| {
+            let symbol = macho_file
+                .symbol_by_index(SymbolIndex(symbol_index as usize))
+                .ok()?;
+            let name = symbol.name().ok()?;
+            Some(StubSymbol {
+                address: u32::try_from(address.checked_sub(base_address)?).ok()?,
+                size,
+                name: format!("stub for {}", name),
+            })
+        })
+        .collect();
+
+    let mut additional_symbols = match symbol_file {
+        Some(symbol_file) => object_function_symbols(symbol_file, base_address),
+        None => Vec::new(),
+    };
+
+    // Go binaries have a function table with the names of all functions, which is
+    // kept even if the binary is stripped.
+    if let Some(gopclntab) = GoPcLnTab::from_object(macho_file) {
+        additional_symbols.extend(collect_gopclntab_function_symbols(&gopclntab, base_address));
+    }
+
+    // The exports trie and the Objective-C metadata name functions even if the symbol
+    // table is stripped. We only need them if the symbol table doesn't cover most
+    // functions.
+    let mut symbol_addresses: Vec<u32> = macho_file
+        .symbols()
+        .filter(|symbol| symbol.kind() == SymbolKind::Text)
+        .filter_map(|symbol| u32::try_from(symbol.address().checked_sub(base_address)?).ok())
+        .chain(additional_symbols.iter().map(|symbol| symbol.address))
+        .collect();
+    symbol_addresses.sort_unstable();
+    symbol_addresses.dedup();
+    let has_poor_coverage = has_poor_symbol_coverage(
+        function_starts.as_deref().unwrap_or(&[]),
+        &symbol_addresses,
+        macho_file.symbol_table().is_some(),
+    );
+    if has_poor_coverage {
+        // The exports trie names all exported functions. Exported variables are in
+        // the trie as well, so we only use exports inside executable sections. The
+        // trie is optional, so don't fail if it's malformed.
+        let text_sections: Vec<(u64, u64)> = macho_file
+            .sections()
+            .filter(|section| section.kind() == SectionKind::Text)
+            .map(|section| (section.address(), section.size()))
+            .collect();
+        let is_arm32 = macho_file.architecture() == Architecture::Arm;
+        additional_symbols.extend(
+            macho_data
+                .get_exports_trie_entries()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(offset, name)| {
+                    // Clear the Thumb bit.
+                    let offset = if is_arm32 { offset & !1 } else { offset };
+                    let address = base_address.checked_add(offset)?;
+                    if !text_sections
+                        .iter()
+                        .any(|&(start, size)| address >= start && address - start < size)
+                    {
+                        return None;
+                    }
+                    Some(AdditionalSymbol {
+                        address: u32::try_from(offset).ok()?,
+                        size: None,
+                        name,
+                        source: SymbolSource::Export,
+                    })
+                }),
+        );
+    }
+
+    // The Objective-C metadata has the names of all methods, even in stripped binaries.
+    // Parsing it is not cheap, so we only do it if the symbol table is incomplete.
+    if has_poor_coverage {
+        additional_symbols.extend(macho_data.get_objc_method_symbols(macho_file, base_address));
+    }
+
+    let mut result: R = match query.result_kind {
         SymbolicationResultKind::AllSymbols => {
-            let map = object_to_map(macho_file, function_starts.as_deref());
-            Ok(R::from_full_map(map))
+            let map = object_to_map(
+                macho_file,
+                function_starts.as_deref(),
+                &stub_symbols,
+                &additional_symbols,
+                query.attribute_split_functions_to_parent,
+            );
+            R::from_full_map(map)
         }
         SymbolicationResultKind::SymbolsForAddresses { addresses, .. } => {
-            Ok(get_symbolication_result_for_addresses_from_object(
+            get_symbolication_result_for_addresses_from_object(
                 addresses,
                 macho_file,
                 function_starts.as_deref(),
                 None,
-            ))
+                &stub_symbols,
+                &additional_symbols,
+                query.attribute_split_functions_to_parent,
+            )
         }
-    }
+    };
+    result.set_symbol_names_have_leading_underscore();
+    Ok(result)
 }
 
 pub async fn get_symbolication_result<'a, 'b, 'h, R>(
@@ -188,14 +311,18 @@ where
     let macho_file = File::parse(range).map_err(GetSymbolsError::MachOHeaderParseError)?;
 
     let macho_data = MachOData::new(range, 0, macho_file.is_64());
-    let mut symbolication_result =
-        get_symbolication_result_from_macho_object(&macho_file, macho_data, query.clone())?;
+    let mut symbolication_result = get_symbolication_result_from_macho_object(
+        &macho_file,
+        macho_data,
+        None::<&File>,
+        query.clone(),
+    )?;
 
     let addresses = match query.result_kind {
         SymbolicationResultKind::SymbolsForAddresses {
             with_debug_info: true,
             addresses,
-        } => addresses,
+        } if query.symbol_kind == QuerySymbolKind::Functions => addresses,
         _ => return Ok(symbolication_result),
     };
 
@@ -216,7 +343,21 @@ where
     // the address that we need to look up in the current object.
 
     let addresses_in_root_object = make_address_pairs_for_root_object(addresses, &macho_file);
-    let mut path_mapper = PathMapper::new(base_path);
+    let mut path_mapper = PathMapper::new(base_path, query.path_mapping_rules);
+
+    // Go binaries without DWARF have file and line tables in their function table.
+    if !has_dwarf_debug_info(&macho_file) {
+        if let Some(gopclntab) = GoPcLnTab::from_object(&macho_file) {
+            collect_gopclntab_address_debug_data(
+                &gopclntab,
+                &addresses_in_root_object,
+                &mut symbolication_result,
+                &mut path_mapper,
+            );
+            return Ok(symbolication_result);
+        }
+    }
+
     let mut object_references = VecDeque::new();
     collect_debug_info_and_object_references(
         range,
@@ -243,6 +384,73 @@ where
     Ok(symbolication_result)
 }
 
+/// Symbolicate a Mach-O binary using the DWARF file from its dSYM bundle.
+///
+/// The symbols from both files are merged: the binary provides the stubs and the
+/// function starts, and the dSYM provides its full symbol table and the DWARF
+/// debug info. Both files must match the requested debug ID; for fat files, the
+/// matching architecture is picked from each file. `base_path` is the base path
+/// of the dSYM file.
+pub fn get_symbolication_result_with_dsym<R>(
+    base_path: &BasePath,
+    binary_contents: FileContentsWrapper<impl FileContents>,
+    binary_range: Option<(u64, u64)>,
+    dsym_contents: FileContentsWrapper<impl FileContents>,
+    dsym_range: Option<(u64, u64)>,
+    query: SymbolicationQuery,
+) -> Result<R>
+where
+    R: SymbolicationResult,
+{
+    let binary_range = match binary_range {
+        Some((start, size)) => binary_contents.range(start, size),
+        None => binary_contents.full_range(),
+    };
+    let dsym_range = match dsym_range {
+        Some((start, size)) => dsym_contents.range(start, size),
+        None => dsym_contents.full_range(),
+    };
+
+    let macho_file = File::parse(binary_range).map_err(GetSymbolsError::MachOHeaderParseError)?;
+    let dsym_file = File::parse(dsym_range).map_err(GetSymbolsError::MachOHeaderParseError)?;
+    let dsym_debug_id = debug_id_for_object(&dsym_file)
+        .ok_or(GetSymbolsError::InvalidInputError("Missing mach-o uuid"))?;
+    if dsym_debug_id != query.debug_id {
+        return Err(GetSymbolsError::UnmatchedDebugId(
+            dsym_debug_id,
+            query.debug_id,
+        ));
+    }
+
+    let macho_data = MachOData::new(binary_range, 0, macho_file.is_64());
+    let mut symbolication_result = get_symbolication_result_from_macho_object(
+        &macho_file,
+        macho_data,
+        Some(&dsym_file),
+        query.clone(),
+    )?;
+
+    if let SymbolicationResultKind::SymbolsForAddresses {
+        with_debug_info: true,
+        addresses,
+    } = query.result_kind
+    {
+        if query.symbol_kind == QuerySymbolKind::Functions {
+            let addresses = make_address_pairs_for_root_object(addresses, &macho_file);
+            let mut path_mapper = PathMapper::new(base_path, query.path_mapping_rules);
+            collect_dwarf_address_debug_data(
+                dsym_range,
+                &dsym_file,
+                &addresses,
+                &mut symbolication_result,
+                &mut path_mapper,
+            );
+        }
+    }
+
+    Ok(symbolication_result)
+}
+
 async fn traverse_object_references_and_collect_debug_info<'h>(
     object_references: VecDeque<ObjectReference>,
     symbolication_result: &mut impl SymbolicationResult,
@@ -254,33 +462,204 @@ async fn traverse_object_references_and_collect_debug_info<'h>(
     // async functions can't easily recurse.
     let mut remaining_object_references = object_references;
     while let Some(obj_ref) = remaining_object_references.pop_front() {
-        let path = obj_ref.path().to_owned();
-        let file_contents = match helper.open_file(&FileLocation::Path(path)).await {
-            Ok(data) => FileContentsWrapper::new(data),
-            Err(_) => {
-                // We probably couldn't find the file, but that's fine.
-                // It would be good to collect this error somewhere.
-                continue;
+        match obj_ref {
+            ObjectReference::Regular {
+                path,
+                functions,
+                modification_time,
+            } => {
+                let object_name = path.to_string_lossy().to_string();
+                let candidate_paths = match helper.get_candidate_paths_for_object_file(&path, None)
+                {
+                    Ok(candidate_paths) => candidate_paths,
+                    Err(e) => {
+                        symbolication_result.add_debug_info_error(
+                            GetSymbolsError::HelperErrorDuringGetCandidatePathsForObjectFile(
+                                object_name,
+                                e,
+                            ),
+                        );
+                        continue;
+                    }
+                };
+
+                // Use the first candidate which can be opened and which hasn't been
+                // modified since linking. The errors are only reported if none of the
+                // candidates could be used.
+                let mut errors = Vec::new();
+                let mut file_contents = None;
+                for location in candidate_paths {
+                    if let (Some(expected_modification_time), Some(modification_time)) = (
+                        modification_time,
+                        helper.get_file_modification_time(&location),
+                    ) {
+                        if modification_time != expected_modification_time {
+                            errors.push(GetSymbolsError::ObjectFileModified(
+                                location.to_string_lossy(),
+                                expected_modification_time,
+                                modification_time,
+                            ));
+                            continue;
+                        }
+                    }
+                    match helper.open_file(&location).await {
+                        Ok(data) => {
+                            file_contents = Some(FileContentsWrapper::new(data));
+                            break;
+                        }
+                        Err(e) => errors.push(GetSymbolsError::HelperErrorDuringOpenFile(
+                            location.to_string_lossy(),
+                            e,
+                        )),
+                    }
+                }
+                let file_contents = match file_contents {
+                    Some(file_contents) => file_contents,
+                    None => {
+                        if errors.is_empty() {
+                            errors.push(GetSymbolsError::NoCandidatePathForObjectFile(object_name));
+                        }
+                        for error in errors {
+                            symbolication_result.add_debug_info_error(error);
+                        }
+                        continue;
+                    }
+                };
+
+                collect_debug_info_from_object(
+                    RangeReadRef::new(&file_contents, 0, file_contents.len()),
+                    functions,
+                    symbolication_result,
+                    &mut remaining_object_references,
+                    path_mapper,
+                )?;
             }
-        };
+            ObjectReference::Archive {
+                path,
+                archive_info,
+                modification_times,
+            } => {
+                let archive_name = path.to_string_lossy().to_string();
 
-        for (data, functions) in obj_ref.into_objects(&file_contents)?.into_iter() {
-            let macho_file = File::parse(data).map_err(GetSymbolsError::MachOHeaderParseError)?;
-            let addresses_in_this_object = translate_addresses_to_object(&macho_file, functions);
-            collect_debug_info_and_object_references(
-                data,
-                &macho_file,
-                &addresses_in_this_object,
-                symbolication_result,
-                &mut remaining_object_references,
-                path_mapper,
-            );
+                // Group the members by their candidate paths, so that each candidate
+                // archive only needs to be opened once.
+                let mut members_by_candidate_paths: Vec<(Vec<FileLocation>, HashMap<_, _>)> =
+                    Vec::new();
+                for (name_in_archive, functions) in archive_info {
+                    let candidate_paths = match helper
+                        .get_candidate_paths_for_object_file(&path, Some(&name_in_archive))
+                    {
+                        Ok(candidate_paths) => candidate_paths,
+                        Err(e) => {
+                            symbolication_result.add_debug_info_error(
+                                GetSymbolsError::HelperErrorDuringGetCandidatePathsForObjectFile(
+                                    format!("{}({})", archive_name, name_in_archive),
+                                    e,
+                                ),
+                            );
+                            continue;
+                        }
+                    };
+                    let index = match members_by_candidate_paths
+                        .iter()
+                        .position(|(paths, _)| *paths == candidate_paths)
+                    {
+                        Some(index) => index,
+                        None => {
+                            members_by_candidate_paths.push((candidate_paths, HashMap::new()));
+                            members_by_candidate_paths.len() - 1
+                        }
+                    };
+                    members_by_candidate_paths[index]
+                        .1
+                        .insert(name_in_archive, functions);
+                }
+
+                for (candidate_paths, mut members) in members_by_candidate_paths {
+                    let mut errors = Vec::new();
+                    let mut opened_any_archive = false;
+                    for location in candidate_paths {
+                        if members.is_empty() {
+                            break;
+                        }
+                        let file_contents = match helper.open_file(&location).await {
+                            Ok(data) => FileContentsWrapper::new(data),
+                            Err(e) => {
+                                errors.push(GetSymbolsError::HelperErrorDuringOpenFile(
+                                    location.to_string_lossy(),
+                                    e,
+                                ));
+                                continue;
+                            }
+                        };
+                        opened_any_archive = true;
+                        let objects = match take_archive_members(
+                            &file_contents,
+                            &location,
+                            &mut members,
+                            &modification_times,
+                            &mut errors,
+                        ) {
+                            Ok(objects) => objects,
+                            Err(e) => {
+                                errors.push(e);
+                                continue;
+                            }
+                        };
+                        for (data, functions) in objects {
+                            collect_debug_info_from_object(
+                                data,
+                                functions,
+                                symbolication_result,
+                                &mut remaining_object_references,
+                                path_mapper,
+                            )?;
+                        }
+                    }
+                    if members.is_empty() {
+                        continue;
+                    }
+                    for error in errors {
+                        symbolication_result.add_debug_info_error(error);
+                    }
+                    if opened_any_archive {
+                        for (name_in_archive, _) in members {
+                            symbolication_result.add_debug_info_error(
+                                GetSymbolsError::ArchiveMemberNotFound(
+                                    archive_name.clone(),
+                                    name_in_archive,
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
         }
     }
 
     Ok(())
 }
 
+fn collect_debug_info_from_object<'data>(
+    data: RangeReadRef<'data, impl ReadRef<'data>>,
+    functions: FunctionsWithAddresses,
+    symbolication_result: &mut impl SymbolicationResult,
+    remaining_object_references: &mut VecDeque<ObjectReference>,
+    path_mapper: &mut PathMapper<()>,
+) -> Result<()> {
+    let macho_file = File::parse(data).map_err(GetSymbolsError::MachOHeaderParseError)?;
+    let addresses_in_this_object = translate_addresses_to_object(&macho_file, functions);
+    collect_debug_info_and_object_references(
+        data,
+        &macho_file,
+        &addresses_in_this_object,
+        symbolication_result,
+        remaining_object_references,
+        path_mapper,
+    );
+    Ok(())
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct FunctionsWithAddresses {
     /// Keys are byte strings of the function name.
@@ -341,54 +720,59 @@ enum ObjectReference {
     Regular {
         path: PathBuf,
         functions: FunctionsWithAddresses,
+        /// The modification time which the linker recorded for this object file.
+        modification_time: Option<u64>,
     },
     Archive {
         path: PathBuf,
         archive_info: HashMap<String, FunctionsWithAddresses>,
+        /// The modification times which the linker recorded for the archive members,
+        /// by member name.
+        modification_times: HashMap<String, u64>,
     },
 }
 
-impl ObjectReference {
-    fn path(&self) -> &Path {
-        match self {
-            ObjectReference::Regular { path, .. } => path,
-            ObjectReference::Archive { path, .. } => path,
-        }
-    }
-
-    fn into_objects<'a, 'b, R: ReadRef<'b>>(
-        self,
-        data: R,
-    ) -> Result<Vec<(RangeReadRef<'b, R>, FunctionsWithAddresses)>> {
-        match self {
-            ObjectReference::Regular { functions, .. } => Ok(vec![(
-                RangeReadRef::new(data, 0, data.len().unwrap_or(0)),
-                functions,
-            )]),
-            ObjectReference::Archive {
-                path, archive_info, ..
-            } => {
-                let archive = ArchiveFile::parse(data)
-                    .map_err(|x| GetSymbolsError::ArchiveParseError(path.clone(), Box::new(x)))?;
-                let archive_members_by_name: HashMap<Vec<u8>, (u64, u64)> = archive
-                    .members()
-                    .filter_map(|member| match member {
-                        Ok(member) => Some((member.name().to_owned(), member.file_range())),
-                        Err(_) => None,
-                    })
-                    .collect();
-                let v: Vec<_> = archive_info
-                    .into_iter()
-                    .filter_map(|(name_in_archive, functions)| {
-                        archive_members_by_name
-                            .get(name_in_archive.as_bytes())
-                            .map(|&(start, size)| (RangeReadRef::new(data, start, size), functions))
-                    })
-                    .collect();
-                Ok(v)
+/// Finds the requested members in the archive `data`, which was opened from `location`,
+/// and removes them from `members`. Members which were modified after linking are left
+/// in `members`, and an error is added to `errors` for them.
+fn take_archive_members<'data, R: ReadRef<'data>>(
+    data: R,
+    location: &FileLocation,
+    members: &mut HashMap<String, FunctionsWithAddresses>,
+    modification_times: &HashMap<String, u64>,
+    errors: &mut Vec<GetSymbolsError>,
+) -> Result<Vec<(RangeReadRef<'data, R>, FunctionsWithAddresses)>> {
+    let archive = ArchiveFile::parse(data).map_err(|x| {
+        GetSymbolsError::ArchiveParseError(location.to_string_lossy().into(), Box::new(x))
+    })?;
+    let mut objects = Vec::new();
+    for member in archive.members() {
+        let member = match member {
+            Ok(member) => member,
+            Err(_) => continue,
+        };
+        let name_in_archive = match std::str::from_utf8(member.name()) {
+            Ok(name) if members.contains_key(name) => name,
+            _ => continue,
+        };
+        if let (Some(&expected_modification_time), Some(date)) =
+            (modification_times.get(name_in_archive), member.date())
+        {
+            if date != expected_modification_time {
+                errors.push(GetSymbolsError::ObjectFileModified(
+                    format!("{}({})", location.to_string_lossy(), name_in_archive),
+                    expected_modification_time,
+                    date,
+                ));
+                continue;
             }
         }
+        if let Some(functions) = members.remove(name_in_archive) {
+            let (start, size) = member.file_range();
+            objects.push((RangeReadRef::new(data, start, size), functions));
+        }
     }
+    Ok(objects)
 }
 
 /// addresses must be sorted by vmaddr_in_this_object
@@ -417,9 +801,13 @@ fn collect_debug_info_and_object_references<'data: 'file, 'file, 'a, O, R>(
         path_mapper,
     );
 
+    let modification_times = MachOData::new(file_data, 0, macho_file.is_64())
+        .get_object_modification_times()
+        .unwrap_or_default();
     let mut archives = HashMap::new();
 
     for (object_index, functions) in external_funs_by_object.into_iter() {
+        let modification_time = modification_times.get(objects[object_index]).copied();
         let object_name = std::str::from_utf8(objects[object_index]).unwrap();
         match object_name.find('(') {
             Some(index) => {
@@ -431,20 +819,33 @@ fn collect_debug_info_and_object_references<'data: 'file, 'file, 'a, O, R>(
                     .trim_start_matches('(')
                     .trim_end_matches(')')
                     .to_string();
-                let archive_info = archives.entry(path).or_insert_with(HashMap::new);
+                let (archive_info, archive_modification_times) = archives
+                    .entry(path)
+                    .or_insert_with(|| (HashMap::new(), HashMap::new()));
+                if let Some(modification_time) = modification_time {
+                    archive_modification_times.insert(name_in_archive.clone(), modification_time);
+                }
                 archive_info.insert(name_in_archive, functions);
             }
             None => {
                 // This is a reference to a regular object file. Example:
                 // "/Users/mstange/code/obj-m-opt/toolkit/library/build/../../components/sessionstore/Unified_cpp_sessionstore0.o"
                 let path: PathBuf = object_name.into();
-                remaining_object_references.push_back(ObjectReference::Regular { path, functions });
+                remaining_object_references.push_back(ObjectReference::Regular {
+                    path,
+                    functions,
+                    modification_time,
+                });
             }
         }
     }
 
-    for (path, archive_info) in archives.into_iter() {
-        remaining_object_references.push_back(ObjectReference::Archive { path, archive_info });
+    for (path, (archive_info, modification_times)) in archives.into_iter() {
+        remaining_object_references.push_back(ObjectReference::Archive {
+            path,
+            archive_info,
+            modification_times,
+        });
     }
 }
 
@@ -526,6 +927,7 @@ pub struct MachOData<'data, R: ReadRef<'data>> {
     data: R,
     header_offset: u64,
     is_64: bool,
+    dyld_cache_memory: Option<DyldCacheMemory<R>>,
     _phantom: PhantomData<&'data ()>,
 }
 
@@ -535,10 +937,18 @@ impl<'data, R: ReadRef<'data>> MachOData<'data, R> {
             data,
             header_offset,
             is_64,
+            dyld_cache_memory: None,
             _phantom: PhantomData,
         }
     }
 
+    /// For images in the dyld shared cache: Supply the memory of the whole cache,
+    /// so that pointers to other parts of the cache can be followed.
+    pub fn with_dyld_cache_memory(mut self, memory: DyldCacheMemory<R>) -> Self {
+        self.dyld_cache_memory = Some(memory);
+        self
+    }
+
     /// Read the list of function start addresses from the LC_FUNCTION_STARTS mach-O load command.
     /// This information is usually present even in stripped binaries. It's a uleb128 encoded list
     /// of deltas between the function addresses, with a zero delta terminator.
@@ -573,6 +983,346 @@ impl<'data, R: ReadRef<'data>> MachOData<'data, R> {
         Ok(Some(function_starts))
     }
 
+    /// Find the stubs in the symbol stub sections, such as __stubs and __auth_stubs.
+    /// Stubs are used to call functions in other libraries. They don't have symbols,
+    /// but each stub has an entry in the indirect symbol table, which refers to the
+    /// symbol of the called function. The section's reserved1 field is the index of
+    /// the section's first entry in the indirect symbol table, and reserved2 is the
+    /// size of each stub.
+    /// Returns (vmaddr, size, symbol index) for every stub.
+    pub fn get_symbol_stubs(&self) -> Result<Vec<(u64, u32, u32)>> {
+        let (stub_sections, indirect_symbol_table) = self
+            .symbol_stub_sections_and_indirect_symbol_table()
+            .map_err(GetSymbolsError::MachOHeaderParseError)?;
+        let (endian, indirect_symbols) = match indirect_symbol_table {
+            Some(indirect_symbol_table) => indirect_symbol_table,
+            None => return Ok(Vec::new()),
+        };
+        let mut stubs = Vec::new();
+        for (address, size, first_index, stub_size) in stub_sections {
+            if stub_size == 0 {
+                continue;
+            }
+            for i in 0..(size / u64::from(stub_size)) {
+                let symbol_index = match indirect_symbols.get(first_index as usize + i as usize) {
+                    Some(symbol_index) => symbol_index.get(endian),
+                    None => break,
+                };
+                if symbol_index & (macho::INDIRECT_SYMBOL_LOCAL | macho::INDIRECT_SYMBOL_ABS) != 0 {
+                    continue;
+                }
+                stubs.push((address + i * u64::from(stub_size), stub_size, symbol_index));
+            }
+        }
+        Ok(stubs)
+    }
+
+    /// Returns (addr, size, reserved1, reserved2) of each S_SYMBOL_STUBS section, and
+    /// the indirect symbol table.
+    #[allow(clippy::type_complexity)]
+    fn symbol_stub_sections_and_indirect_symbol_table(
+        &self,
+    ) -> object::read::Result<(
+        Vec<(u64, u64, u32, u32)>,
+        Option<(Endianness, &'data [U32<Endianness>])>,
+    )> {
+        let (endian, mut commands) = if self.is_64 {
+            self.load_command_iter::<MachHeader64<Endianness>>()?
+        } else {
+            self.load_command_iter::<MachHeader32<Endianness>>()?
+        };
+        let is_stub_section = |flags: u32| flags & macho::SECTION_TYPE == macho::S_SYMBOL_STUBS;
+        let mut stub_sections = Vec::new();
+        let mut indirect_symbol_table = None;
+        while let Ok(Some(command)) = commands.next() {
+            if let Some((segment, section_data)) = command.segment_64()? {
+                for section in segment.sections(endian, section_data)? {
+                    if is_stub_section(section.flags.get(endian)) {
+                        stub_sections.push((
+                            section.addr.get(endian),
+                            section.size.get(endian),
+                            section.reserved1.get(endian),
+                            section.reserved2.get(endian),
+                        ));
+                    }
+                }
+            } else if let Some((segment, section_data)) = command.segment_32()? {
+                for section in segment.sections(endian, section_data)? {
+                    if is_stub_section(section.flags.get(endian)) {
+                        stub_sections.push((
+                            section.addr.get(endian).into(),
+                            section.size.get(endian).into(),
+                            section.reserved1.get(endian),
+                            section.reserved2.get(endian),
+                        ));
+                    }
+                }
+            } else if let Some(dysymtab) = command.dysymtab()? {
+                let offset: u64 = dysymtab.indirectsymoff.get(endian).into();
+                let count = dysymtab.nindirectsyms.get(endian) as usize;
+                indirect_symbol_table = self
+                    .data
+                    .read_slice_at::<U32<Endianness>>(offset, count)
+                    .ok()
+                    .map(|indirect_symbols| (endian, indirect_symbols));
+            }
+        }
+        Ok((stub_sections, indirect_symbol_table))
+    }
+
+    /// Synthesize symbols of the form `-[Class selector]` and `+[Class selector]` for
+    /// the Objective-C methods of the classes and categories in this image. The
+    /// Objective-C metadata describes every method implementation and is kept
+    /// when the binary is stripped.
+    pub fn get_objc_method_symbols(
+        &self,
+        macho_file: &File<'data, R>,
+        base_address: u64,
+    ) -> Vec<AdditionalSymbol> {
+        let section_range = |name: &str| {
+            macho_file
+                .section_by_name(name)
+                .map(|section| (section.address(), section.size()))
+        };
+        let classlist = section_range("__objc_classlist");
+        let catlist = section_range("__objc_catlist");
+        if classlist.is_none() && catlist.is_none() {
+            return Vec::new();
+        }
+
+        let segment_mappings: Vec<MappedRange<R>>;
+        let (mappings, pointer_format, relative_selector_base, bind_names) = match &self
+            .dyld_cache_memory
+        {
+            Some(memory) => (
+                &memory.mappings[..],
+                PointerFormat::DyldCache {
+                    base_address: memory.base_address,
+                },
+                memory.relative_selector_base,
+                Vec::new(),
+            ),
+            None => {
+                segment_mappings = macho_file
+                    .segments()
+                    .map(|segment| {
+                        let (file_offset, size) = segment.file_range();
+                        MappedRange {
+                            address: segment.address(),
+                            size,
+                            file_offset,
+                            data: self.data,
+                        }
+                    })
+                    .collect();
+                let (pointer_format, bind_names) = match self.chained_fixups() {
+                    Ok(Some((format, bind_names))) => (PointerFormat::Chained(format), bind_names),
+                    _ => (PointerFormat::Plain, Vec::new()),
+                };
+                (&segment_mappings[..], pointer_format, None, bind_names)
+            }
+        };
+
+        let reader = ObjcMetadataReader {
+            mappings,
+            endian: if macho_file.is_little_endian() {
+                Endianness::Little
+            } else {
+                Endianness::Big
+            },
+            is_64: self.is_64,
+            pointer_format,
+            image_base: base_address,
+            relative_selector_base,
+            bind_names,
+        };
+        reader
+            .method_implementations(classlist, catlist)
+            .into_iter()
+            .filter_map(|(address, name)| {
+                Some(AdditionalSymbol {
+                    address: u32::try_from(address.checked_sub(base_address)?).ok()?,
+                    size: None,
+                    name,
+                    source: SymbolSource::ObjcMetadata,
+                })
+            })
+            .collect()
+    }
+
+    /// Read the exported functions from the exports trie, which is in the
+    /// LC_DYLD_EXPORTS_TRIE or LC_DYLD_INFO(_ONLY) load command. The exports trie
+    /// is kept when the symbol table of a dylib is stripped.
+    /// Returns (offset from the Mach-O header, name) for every export.
+    pub fn get_exports_trie_entries(&self) -> Result<Vec<(u64, String)>> {
+        let trie = self
+            .exports_trie_data()
+            .map_err(GetSymbolsError::MachOHeaderParseError)?;
+        Ok(trie.map(parse_exports_trie).unwrap_or_default())
+    }
+
+    fn exports_trie_data(&self) -> object::read::Result<Option<&'data [u8]>> {
+        let (endian, mut commands) = if self.is_64 {
+            self.load_command_iter::<MachHeader64<Endianness>>()?
+        } else {
+            self.load_command_iter::<MachHeader32<Endianness>>()?
+        };
+        let mut trie_range = None;
+        let mut linkedit = None;
+        while let Ok(Some(command)) = commands.next() {
+            if command.cmd() == macho::LC_DYLD_EXPORTS_TRIE {
+                let command: &LinkeditDataCommand<_> = command.data()?;
+                trie_range = Some((command.dataoff.get(endian), command.datasize.get(endian)));
+            } else if let Some(dyld_info) = command.dyld_info()? {
+                if trie_range.is_none() {
+                    trie_range = Some((
+                        dyld_info.export_off.get(endian),
+                        dyld_info.export_size.get(endian),
+                    ));
+                }
+            } else if let Some((segment, _)) = command.segment_64()? {
+                if segment.name() == b"__LINKEDIT" {
+                    linkedit = Some((segment.vmaddr(endian), segment.fileoff(endian)));
+                }
+            } else if let Some((segment, _)) = command.segment_32()? {
+                if segment.name() == b"__LINKEDIT" {
+                    linkedit = Some((
+                        segment.vmaddr(endian).into(),
+                        segment.fileoff(endian).into(),
+                    ));
+                }
+            }
+        }
+        let (offset, size) = match trie_range {
+            Some((offset, size)) if size != 0 => (u64::from(offset), u64::from(size)),
+            _ => return Ok(None),
+        };
+        match (&self.dyld_cache_memory, linkedit) {
+            // In the dyld shared cache, __LINKEDIT can be in a different subcache
+            // than the image, so we find the trie by its address.
+            (Some(memory), Some((linkedit_address, linkedit_offset))) => {
+                let address = (linkedit_address + offset).checked_sub(linkedit_offset);
+                Ok(address
+                    .and_then(|address| read_bytes_at_address(&memory.mappings, address, size)))
+            }
+            _ => Ok(self.data.read_bytes_at(offset, size).ok()),
+        }
+    }
+
+    /// Reads the LC_DYLD_CHAINED_FIXUPS data, and returns the pointer format of
+    /// the chained fixups and the symbol names of the imports, by bind ordinal.
+    fn chained_fixups(&self) -> object::read::Result<Option<(u16, Vec<&'data str>)>> {
+        let (endian, mut commands) = if self.is_64 {
+            self.load_command_iter::<MachHeader64<Endianness>>()?
+        } else {
+            self.load_command_iter::<MachHeader32<Endianness>>()?
+        };
+        let mut fixups = None;
+        while let Ok(Some(command)) = commands.next() {
+            if command.cmd() == macho::LC_DYLD_CHAINED_FIXUPS {
+                let command: &LinkeditDataCommand<_> = command.data()?;
+                let dataoff: u64 = command.dataoff.get(endian).into();
+                let datasize: u64 = command.datasize.get(endian).into();
+                fixups = self.data.read_bytes_at(dataoff, datasize).ok();
+                break;
+            }
+        }
+        let fixups = match fixups {
+            Some(fixups) => fixups,
+            None => return Ok(None),
+        };
+        let u16_at = |offset: usize| -> Option<u16> {
+            Some(endian.read_u16_bytes(fixups.get(offset..offset + 2)?.try_into().ok()?))
+        };
+        let u32_at = |offset: usize| -> Option<u32> {
+            Some(endian.read_u32_bytes(fixups.get(offset..offset + 4)?.try_into().ok()?))
+        };
+        let u32_at_usize = |offset: usize| u32_at(offset).map(|value| value as usize);
+
+        // dyld_chained_fixups_header
+        let parse = || -> Option<(u16, Vec<&'data str>)> {
+            let starts_offset = u32_at_usize(4)?;
+            let imports_offset = u32_at_usize(8)?;
+            let symbols_offset = u32_at_usize(12)?;
+            let imports_count = u32_at_usize(16)?;
+            let imports_format = u32_at(20)?;
+            let symbols_format = u32_at(24)?;
+
+            // dyld_chained_starts_in_image, followed by a dyld_chained_starts_in_segment
+            // for each segment with fixups. All of them have the same pointer format.
+            let segment_count = u32_at_usize(starts_offset)?;
+            let pointer_format = (0..segment_count)
+                .filter_map(|i| u32_at_usize(starts_offset + 4 + i * 4))
+                .find(|&segment_info_offset| segment_info_offset != 0)
+                .and_then(|segment_info_offset| u16_at(starts_offset + segment_info_offset + 6))?;
+
+            // The imports, with their name offsets into the symbol names.
+            let mut bind_names = Vec::new();
+            if symbols_format == 0 {
+                for i in 0..imports_count {
+                    let name_offset = match imports_format {
+                        // DYLD_CHAINED_IMPORT and DYLD_CHAINED_IMPORT_ADDEND
+                        1 | 2 => {
+                            let stride = if imports_format == 1 { 4 } else { 8 };
+                            u32_at_usize(imports_offset + i * stride).map(|import| import >> 9)
+                        }
+                        // DYLD_CHAINED_IMPORT_ADDEND64
+                        3 => u32_at_usize(imports_offset + i * 16 + 4),
+                        _ => None,
+                    };
+                    let name = name_offset
+                        .and_then(|name_offset| fixups.get(symbols_offset + name_offset..))
+                        .and_then(|names| names.split(|&b| b == 0).next())
+                        .and_then(|name| std::str::from_utf8(name).ok())
+                        .unwrap_or("");
+                    bind_names.push(name);
+                }
+            }
+            Some((pointer_format, bind_names))
+        };
+        Ok(parse())
+    }
+
+    /// Read the modification times which the linker recorded for the object files in
+    /// the debug map, i.e. the n_value of the N_OSO symbols. The keys are the object
+    /// names from the debug map, e.g. "/path/to/file.o" or "/path/to/libfoo.a(file.o)".
+    /// For archive members, the linker records the date from the archive member header.
+    /// Objects with a zero modification time are left out; the linker writes zero if
+    /// timestamps are disabled, e.g. with ZERO_AR_DATE=1.
+    pub fn get_object_modification_times(&self) -> Result<HashMap<&'data [u8], u64>> {
+        let modification_times = if self.is_64 {
+            self.object_modification_times::<MachHeader64<Endianness>>()
+        } else {
+            self.object_modification_times::<MachHeader32<Endianness>>()
+        };
+        modification_times.map_err(GetSymbolsError::MachOHeaderParseError)
+    }
+
+    fn object_modification_times<M: MachHeader<Endian = Endianness>>(
+        &self,
+    ) -> object::read::Result<HashMap<&'data [u8], u64>> {
+        let (endian, mut commands) = self.load_command_iter::<M>()?;
+        let mut modification_times = HashMap::new();
+        while let Ok(Some(command)) = commands.next() {
+            if let Some(symtab) = command.symtab()? {
+                let symbols = symtab.symbols::<M, _>(endian, self.data)?;
+                for nlist in symbols.iter() {
+                    if nlist.n_type() != macho::N_OSO {
+                        continue;
+                    }
+                    let modification_time: u64 = nlist.n_value(endian).into();
+                    if modification_time == 0 {
+                        continue;
+                    }
+                    if let Ok(name) = nlist.name(endian, symbols.strings()) {
+                        modification_times.insert(name, modification_time);
+                    }
+                }
+            }
+        }
+        Ok(modification_times)
+    }
+
     fn load_command_iter<M: MachHeader>(
         &self,
     ) -> object::read::Result<(M::Endian, LoadCommandIterator<M::Endian>)> {
@@ -601,6 +1351,552 @@ impl<'data, R: ReadRef<'data>> MachOData<'data, R> {
     }
 }
 
+/// The file data which backs a range of virtual memory addresses.
+#[derive(Debug, Clone, Copy)]
+pub struct MappedRange<R> {
+    address: u64,
+    size: u64,
+    file_offset: u64,
+    data: R,
+}
+
+/// The memory of a dyld shared cache, which is needed to read the Objective-C
+/// metadata of its images: Pointers in the metadata can point anywhere in the
+/// cache, including into subcaches, and they are stored in the cache's own
+/// pointer encoding.
+pub struct DyldCacheMemory<R> {
+    /// The mappings of the main cache file and of all subcaches.
+    mappings: Vec<MappedRange<R>>,
+    /// The address of the first mapping. Some pointer encodings are relative to it.
+    base_address: u64,
+    /// The address which selector offsets in relative method lists are relative to.
+    relative_selector_base: Option<u64>,
+}
+
+impl<R: Copy> DyldCacheMemory<R> {
+    /// `cache_data` is the data of the main cache file, followed by the data of
+    /// the subcaches.
+    pub fn new<'data>(cache: &DyldCache<'data, Endianness, R>, cache_data: &[R]) -> Self
+    where
+        R: ReadRef<'data>,
+    {
+        let mut mappings = Vec::new();
+        for &data in cache_data {
+            let header = match DyldCacheHeader::<Endianness>::parse(data) {
+                Ok(header) => header,
+                Err(_) => continue,
+            };
+            let endian = match header.parse_magic() {
+                Ok((_, endian)) => endian,
+                Err(_) => continue,
+            };
+            if let Ok(infos) = header.mappings(endian, data) {
+                mappings.extend(infos.iter().map(|info| MappedRange {
+                    address: info.address.get(endian),
+                    size: info.size.get(endian),
+                    file_offset: info.file_offset.get(endian),
+                    data,
+                }));
+            }
+        }
+        let base_address = mappings.first().map_or(0, |mapping| mapping.address);
+
+        // The base address for selector offsets is stored in the objc_opt_t
+        // structure in libobjc's __objc_opt_ro section, as an offset from the
+        // start of that structure, starting with version 16.
+        let libobjc = cache
+            .images()
+            .find(|image| image.path() == Ok("/usr/lib/libobjc.A.dylib"))
+            .and_then(|image| image.parse_object().ok());
+        let objc_opt_address = libobjc
+            .as_ref()
+            .and_then(|libobjc| libobjc.section_by_name("__objc_opt_ro"))
+            .map(|section| section.address());
+        let endian = cache.endianness();
+        let relative_selector_base = objc_opt_address.and_then(|address| {
+            let version = read_bytes_at_address(&mappings, address, 4)?;
+            if endian.read_u32_bytes(version.try_into().ok()?) < 16 {
+                return None;
+            }
+            let offset = read_bytes_at_address(&mappings, address + 40, 8)?;
+            let offset = endian.read_u64_bytes(offset.try_into().ok()?);
+            Some(address.wrapping_add(offset))
+        });
+
+        Self {
+            mappings,
+            base_address,
+            relative_selector_base,
+        }
+    }
+}
+
+fn read_bytes_at_address<'data, R: ReadRef<'data>>(
+    mappings: &[MappedRange<R>],
+    address: u64,
+    size: u64,
+) -> Option<&'data [u8]> {
+    let mapping = mappings
+        .iter()
+        .find(|mapping| address >= mapping.address && address - mapping.address < mapping.size)?;
+    let offset = address - mapping.address;
+    if size > mapping.size - offset {
+        return None;
+    }
+    mapping
+        .data
+        .read_bytes_at(mapping.file_offset + offset, size)
+        .ok()
+}
+
+// Pointer formats of chained fixups, from the `pointer_format` field of
+// `dyld_chained_starts_in_segment`.
+const DYLD_CHAINED_PTR_ARM64E: u16 = 1;
+const DYLD_CHAINED_PTR_64: u16 = 2;
+const DYLD_CHAINED_PTR_32: u16 = 3;
+const DYLD_CHAINED_PTR_64_OFFSET: u16 = 6;
+const DYLD_CHAINED_PTR_ARM64E_USERLAND: u16 = 9;
+const DYLD_CHAINED_PTR_ARM64E_USERLAND24: u16 = 12;
+
+/// How pointers are stored in the data segments of a Mach-O image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PointerFormat {
+    /// Plain addresses. This is the case for binaries which use rebase opcodes
+    /// rather than chained fixups.
+    Plain,
+    /// Chained fixups, with one of the `DYLD_CHAINED_PTR_*` formats.
+    Chained(u16),
+    /// The pointer encoding of the dyld shared cache, which depends on the version
+    /// of its slide info.
+    DyldCache { base_address: u64 },
+}
+
+/// Reads the Objective-C class list and category list of a Mach-O image, and
+/// finds the implementations of all methods in it.
+struct ObjcMetadataReader<'a, 'data, R: ReadRef<'data>> {
+    mappings: &'a [MappedRange<R>],
+    endian: Endianness,
+    is_64: bool,
+    pointer_format: PointerFormat,
+    /// The address of the __TEXT segment. Pointers in some formats are relative to it.
+    image_base: u64,
+    relative_selector_base: Option<u64>,
+    /// The symbol names of the chained fixups imports, indexed by bind ordinal.
+    bind_names: Vec<&'data str>,
+}
+
+impl<'a, 'data, R: ReadRef<'data>> ObjcMetadataReader<'a, 'data, R> {
+    fn pointer_size(&self) -> u64 {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    fn read_u32(&self, address: u64) -> Option<u32> {
+        let bytes = read_bytes_at_address(self.mappings, address, 4)?;
+        Some(self.endian.read_u32_bytes(bytes.try_into().ok()?))
+    }
+
+    fn read_u64(&self, address: u64) -> Option<u64> {
+        let bytes = read_bytes_at_address(self.mappings, address, 8)?;
+        Some(self.endian.read_u64_bytes(bytes.try_into().ok()?))
+    }
+
+    fn read_raw_pointer(&self, address: u64) -> Option<u64> {
+        if self.is_64 {
+            self.read_u64(address)
+        } else {
+            self.read_u32(address).map(u64::from)
+        }
+    }
+
+    /// Reads the pointer at `address` and returns the address it points to, or
+    /// None if it is null or if it is bound to a symbol in a different image.
+    fn read_pointer(&self, address: u64) -> Option<u64> {
+        self.resolve_pointer(self.read_raw_pointer(address)?)
+    }
+
+    fn read_c_string(&self, address: u64) -> Option<&'data str> {
+        let mapping = self.mappings.iter().find(|mapping| {
+            address >= mapping.address && address - mapping.address < mapping.size
+        })?;
+        let start = mapping.file_offset + (address - mapping.address);
+        let end = mapping.file_offset + mapping.size;
+        let bytes = mapping.data.read_bytes_at_until(start..end, 0).ok()?;
+        std::str::from_utf8(bytes).ok()
+    }
+
+    fn is_readable(&self, address: u64) -> bool {
+        read_bytes_at_address(self.mappings, address, 1).is_some()
+    }
+
+    fn resolve_pointer(&self, raw: u64) -> Option<u64> {
+        if raw == 0 {
+            return None;
+        }
+        match self.pointer_format {
+            PointerFormat::Plain => Some(raw),
+            PointerFormat::Chained(format) => match format {
+                DYLD_CHAINED_PTR_ARM64E
+                | DYLD_CHAINED_PTR_ARM64E_USERLAND
+                | DYLD_CHAINED_PTR_ARM64E_USERLAND24 => {
+                    if raw & (1 << 62) != 0 {
+                        // A bind.
+                        return None;
+                    }
+                    if raw & (1 << 63) != 0 {
+                        // An authenticated rebase, with a 32 bit offset from the image base.
+                        return Some(self.image_base + (raw & 0xffff_ffff));
+                    }
+                    let target = raw & 0x7ff_ffff_ffff;
+                    if format == DYLD_CHAINED_PTR_ARM64E {
+                        let high8 = (raw >> 43) & 0xff;
+                        Some(high8 << 56 | target)
+                    } else {
+                        Some(self.image_base + target)
+                    }
+                }
+                DYLD_CHAINED_PTR_64 | DYLD_CHAINED_PTR_64_OFFSET => {
+                    if raw & (1 << 63) != 0 {
+                        return None;
+                    }
+                    let target = raw & 0xf_ffff_ffff;
+                    if format == DYLD_CHAINED_PTR_64 {
+                        let high8 = (raw >> 36) & 0xff;
+                        Some(high8 << 56 | target)
+                    } else {
+                        Some(self.image_base + target)
+                    }
+                }
+                DYLD_CHAINED_PTR_32 => {
+                    if raw & (1 << 31) != 0 {
+                        return None;
+                    }
+                    Some(raw & 0x3ff_ffff)
+                }
+                _ => None,
+            },
+            PointerFormat::DyldCache { base_address } => {
+                // The encoding depends on the slide info version, which we don't read.
+                // Instead, we try the possible encodings and use the first one which
+                // results in an address inside the cache.
+                let candidates = if raw & (1 << 63) != 0 {
+                    // Authenticated pointers are offsets from the start of the cache,
+                    // with 32 bits in slide info v3 and with 34 bits in v5.
+                    [
+                        Some(base_address + (raw & 0xffff_ffff)),
+                        Some(base_address + (raw & 0x3_ffff_ffff)),
+                        None,
+                    ]
+                } else {
+                    [
+                        // v2 and v3: the address, with other information in the high bits.
+                        Some(raw & 0xff_ffff_ffff),
+                        // v5: a 34 bit offset from the start of the cache.
+                        Some(base_address + (raw & 0x3_ffff_ffff)),
+                        // v2 with a value_add, which is the high part of the cache address.
+                        Some((raw & 0xff_ffff_ffff) | (base_address & !0xff_ffff_ffff)),
+                    ]
+                };
+                candidates
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .find(|&address| self.is_readable(address))
+            }
+        }
+    }
+
+    /// Returns the name of the symbol which the pointer `raw` is bound to, if
+    /// `raw` is a bind in the chained fixups.
+    fn bind_name(&self, raw: u64) -> Option<&'data str> {
+        let ordinal = match self.pointer_format {
+            PointerFormat::Chained(DYLD_CHAINED_PTR_ARM64E)
+            | PointerFormat::Chained(DYLD_CHAINED_PTR_ARM64E_USERLAND)
+                if raw & (1 << 62) != 0 =>
+            {
+                raw & 0xffff
+            }
+            PointerFormat::Chained(DYLD_CHAINED_PTR_ARM64E_USERLAND24) if raw & (1 << 62) != 0 => {
+                raw & 0xff_ffff
+            }
+            PointerFormat::Chained(DYLD_CHAINED_PTR_64)
+            | PointerFormat::Chained(DYLD_CHAINED_PTR_64_OFFSET)
+                if raw & (1 << 63) != 0 =>
+            {
+                raw & 0xff_ffff
+            }
+            PointerFormat::Chained(DYLD_CHAINED_PTR_32) if raw & (1 << 31) != 0 => raw & 0xf_ffff,
+            _ => return None,
+        };
+        self.bind_names.get(ordinal as usize).copied()
+    }
+
+    /// Returns (address, name) for the implementation of every method of the
+    /// classes and categories in the class list and the category list. The
+    /// lists are given as (address, size).
+    fn method_implementations(
+        &self,
+        classlist: Option<(u64, u64)>,
+        catlist: Option<(u64, u64)>,
+    ) -> Vec<(u64, String)> {
+        let pointer_size = self.pointer_size();
+        let slots = |list: Option<(u64, u64)>| {
+            let (address, size) = list.unwrap_or((0, 0));
+            (0..size / pointer_size).map(move |i| address + i * pointer_size)
+        };
+        let mut methods = Vec::new();
+
+        for slot in slots(classlist) {
+            let class = match self.read_pointer(slot) {
+                Some(class) => class,
+                None => continue,
+            };
+            if let Some((class_name, method_list)) = self.class_name_and_methods(class) {
+                if let Some(method_list) = method_list {
+                    self.add_methods(&mut methods, '-', class_name, method_list);
+                }
+                // The class methods are the methods of the metaclass, which is the
+                // class's isa.
+                let metaclass_methods = self
+                    .read_pointer(class)
+                    .and_then(|metaclass| self.class_name_and_methods(metaclass))
+                    .and_then(|(_, method_list)| method_list);
+                if let Some(method_list) = metaclass_methods {
+                    self.add_methods(&mut methods, '+', class_name, method_list);
+                }
+            }
+        }
+
+        for slot in slots(catlist) {
+            let category = match self.read_pointer(slot) {
+                Some(category) => category,
+                None => continue,
+            };
+            let category_name = match self
+                .read_pointer(category)
+                .and_then(|name| self.read_c_string(name))
+            {
+                Some(category_name) => category_name,
+                None => continue,
+            };
+            // The category can extend a class from this image or from a different image.
+            let class_name = self
+                .read_raw_pointer(category + pointer_size)
+                .and_then(|raw| match self.resolve_pointer(raw) {
+                    Some(class) => self.class_name_and_methods(class).map(|(name, _)| name),
+                    None => self
+                        .bind_name(raw)
+                        .and_then(|name| name.strip_prefix("_OBJC_CLASS_$_")),
+                });
+            let name = format!("{}({})", class_name.unwrap_or("?"), category_name);
+            if let Some(method_list) = self.read_pointer(category + 2 * pointer_size) {
+                self.add_methods(&mut methods, '-', &name, method_list);
+            }
+            if let Some(method_list) = self.read_pointer(category + 3 * pointer_size) {
+                self.add_methods(&mut methods, '+', &name, method_list);
+            }
+        }
+
+        methods
+    }
+
+    /// Reads the class_t at `class` and its class_ro_t, and returns the class
+    /// name and the address of the method list.
+    fn class_name_and_methods(&self, class: u64) -> Option<(&'data str, Option<u64>)> {
+        // The low bits of class_t::data are flags.
+        let data_mask = if self.is_64 { !7 } else { !3 };
+        let class_ro = self.read_pointer(class + 4 * self.pointer_size())? & data_mask;
+        let (name_offset, base_methods_offset) = if self.is_64 { (24, 32) } else { (16, 20) };
+        let name = self
+            .read_pointer(class_ro + name_offset)
+            .and_then(|name| self.read_c_string(name))?;
+        Some((name, self.read_pointer(class_ro + base_methods_offset)))
+    }
+
+    fn add_methods(
+        &self,
+        methods: &mut Vec<(u64, String)>,
+        kind: char,
+        class_name: &str,
+        method_list: u64,
+    ) {
+        if method_list & 1 == 0 {
+            self.add_method_list(methods, kind, class_name, method_list);
+            return;
+        }
+
+        // In the dyld shared cache, the low bit marks a relative_list_list_t, a list
+        // of method lists which includes the methods of categories which were
+        // attached to the class when the cache was built. Each entry has a 48 bit
+        // signed offset to a method list, in the high bits.
+        let list = method_list & !1;
+        let (entry_size, count) = match (self.read_u32(list), self.read_u32(list + 4)) {
+            (Some(entry_size), Some(count)) if entry_size >= 8 => (entry_size, count),
+            _ => return,
+        };
+        for i in 0..u64::from(count) {
+            let entry = list + 8 + i * u64::from(entry_size);
+            let offset = match self.read_u64(entry) {
+                Some(value) => (value as i64) >> 16,
+                None => break,
+            };
+            self.add_method_list(methods, kind, class_name, entry.wrapping_add(offset as u64));
+        }
+    }
+
+    fn add_method_list(
+        &self,
+        methods: &mut Vec<(u64, String)>,
+        kind: char,
+        class_name: &str,
+        method_list: u64,
+    ) {
+        let (entry_size_and_flags, count) =
+            match (self.read_u32(method_list), self.read_u32(method_list + 4)) {
+                (Some(entry_size_and_flags), Some(count)) => (entry_size_and_flags, count),
+                _ => return,
+            };
+        let entry_size = u64::from(entry_size_and_flags & 0xfffc);
+        // Relative method lists have 32 bit offsets instead of pointers. The
+        // selector offset points to a selector reference, or, if the list uses
+        // direct selectors, directly to the selector name, relative to the
+        // selector base address of the dyld shared cache.
+        let is_relative = entry_size_and_flags & 0x8000_0000 != 0;
+        let uses_direct_selectors = entry_size_and_flags & 0x4000_0000 != 0;
+        let pointer_size = self.pointer_size();
+        if entry_size < if is_relative { 12 } else { 3 * pointer_size } {
+            return;
+        }
+
+        for i in 0..u64::from(count) {
+            let entry = method_list + 8 + i * entry_size;
+            let first_field = match self.read_u32(entry) {
+                Some(first_field) => first_field,
+                None => break,
+            };
+            let selector_and_implementation = if is_relative {
+                let selector_offset = i64::from(first_field as i32) as u64;
+                let selector = if uses_direct_selectors {
+                    self.relative_selector_base
+                        .map(|base| base.wrapping_add(selector_offset))
+                } else {
+                    self.read_pointer(entry.wrapping_add(selector_offset))
+                };
+                let implementation = self
+                    .read_u32(entry + 8)
+                    .map(|offset| (entry + 8).wrapping_add(i64::from(offset as i32) as u64));
+                selector.zip(implementation)
+            } else {
+                self.read_pointer(entry)
+                    .zip(self.read_pointer(entry + 2 * pointer_size))
+            };
+            let (selector, mut implementation) = match selector_and_implementation {
+                Some(selector_and_implementation) => selector_and_implementation,
+                None => continue,
+            };
+            let selector = match self.read_c_string(selector) {
+                Some(selector) => selector,
+                None => continue,
+            };
+            if !self.is_64 {
+                // Clear the Thumb bit.
+                implementation &= !1;
+            }
+            methods.push((
+                implementation,
+                format!("{}[{} {}]", kind, class_name, selector),
+            ));
+        }
+    }
+}
+
+/// Parse the exports trie of a Mach-O binary, and return (offset from the
+/// Mach-O header, name) for every exported function.
+///
+/// Every node of the trie has optional export info, if a symbol name ends at
+/// this node, and a list of edges to child nodes. The symbol names are the
+/// concatenated edge labels on the path from the root node. Re-exports don't
+/// have an address in this binary, so they are skipped. For stub-and-resolver
+/// entries, both the stub and the resolver function are returned.
+fn parse_exports_trie(trie: &[u8]) -> Vec<(u64, String)> {
+    let mut exports = Vec::new();
+    let mut visited_nodes = HashSet::new();
+    let mut remaining_nodes = vec![(0, Vec::new())];
+    while let Some((offset, name)) = remaining_nodes.pop() {
+        if !visited_nodes.insert(offset) {
+            continue;
+        }
+        let (export_info, children) = match parse_exports_trie_node(trie, offset) {
+            Some(node) => node,
+            None => continue,
+        };
+        if let Some(export_info) = export_info {
+            let name = String::from_utf8_lossy(&name);
+            exports.extend(parse_export_info(export_info, &name));
+        }
+        for (label, child_offset) in children {
+            let mut child_name = name.clone();
+            child_name.extend_from_slice(label);
+            remaining_nodes.push((child_offset, child_name));
+        }
+    }
+    exports
+}
+
+/// Returns the export info of the node at `offset`, if present, and the edge
+/// labels and offsets of its child nodes.
+#[allow(clippy::type_complexity)]
+fn parse_exports_trie_node(
+    trie: &[u8],
+    offset: usize,
+) -> Option<(Option<&[u8]>, Vec<(&[u8], usize)>)> {
+    let (export_info_size, rest) = read_uleb128(trie.get(offset..)?)?;
+    let export_info_size = usize::try_from(export_info_size).ok()?;
+    let export_info = rest.get(..export_info_size)?;
+    let (&child_count, mut rest) = rest.get(export_info_size..)?.split_first()?;
+    let mut children = Vec::with_capacity(child_count.into());
+    for _ in 0..child_count {
+        let label_len = rest.iter().position(|&b| b == 0)?;
+        let label = &rest[..label_len];
+        let (child_offset, after_child) = read_uleb128(&rest[label_len + 1..])?;
+        children.push((label, usize::try_from(child_offset).ok()?));
+        rest = after_child;
+    }
+    let export_info = if export_info_size == 0 {
+        None
+    } else {
+        Some(export_info)
+    };
+    Some((export_info, children))
+}
+
+fn parse_export_info(export_info: &[u8], name: &str) -> Vec<(u64, String)> {
+    let (flags, rest) = match read_uleb128(export_info) {
+        Some(flags) => flags,
+        None => return Vec::new(),
+    };
+    let flags = flags as u32;
+    if flags & macho::EXPORT_SYMBOL_FLAGS_REEXPORT != 0
+        || flags & macho::EXPORT_SYMBOL_FLAGS_KIND_MASK != macho::EXPORT_SYMBOL_FLAGS_KIND_REGULAR
+    {
+        return Vec::new();
+    }
+    let (address, rest) = match read_uleb128(rest) {
+        Some(address) => address,
+        None => return Vec::new(),
+    };
+    let mut entries = vec![(address, name.to_string())];
+    if flags & macho::EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER != 0 {
+        if let Some((resolver_address, _)) = read_uleb128(rest) {
+            entries.push((resolver_address, format!("resolver for {}", name)));
+        }
+    }
+    entries
+}
+
 fn read_uleb128(mut bytes: &[u8]) -> Option<(u64, &[u8])> {
     const CONTINUATION_BIT: u8 = 1 << 7;
 
@@ -625,3 +1921,245 @@ fn read_uleb128(mut bytes: &[u8]) -> Option<(u64, &[u8])> {
     }
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn objc_method_implementations() {
+        let mut data = vec![0u8; 0x800];
+        let put_u32 = |data: &mut Vec<u8>, address: u64, value: u32| {
+            let offset = (address - 0x1000) as usize;
+            data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        let put_u64 = |data: &mut Vec<u8>, address: u64, value: u64| {
+            let offset = (address - 0x1000) as usize;
+            data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        };
+        let put_str = |data: &mut Vec<u8>, address: u64, value: &str| {
+            let offset = (address - 0x1000) as usize;
+            data[offset..offset + value.len()].copy_from_slice(value.as_bytes());
+        };
+
+        // The class list, with one class and its metaclass.
+        put_u64(&mut data, 0x1000, 0x1100);
+        put_u64(&mut data, 0x1100, 0x1200);
+        put_u64(&mut data, 0x1120, 0x1301);
+        put_u64(&mut data, 0x1220, 0x1380);
+        put_u64(&mut data, 0x1318, 0x1600);
+        put_u64(&mut data, 0x1320, 0x1400);
+        put_u64(&mut data, 0x1398, 0x1600);
+        put_u64(&mut data, 0x13a0, 0x1480);
+        // A method list with pointers.
+        put_u32(&mut data, 0x1400, 24);
+        put_u32(&mut data, 0x1404, 1);
+        put_u64(&mut data, 0x1408, 0x1610);
+        put_u64(&mut data, 0x1418, 0x2000);
+        // A relative method list, with an offset to a selector reference.
+        put_u32(&mut data, 0x1480, 0x8000_000c);
+        put_u32(&mut data, 0x1484, 1);
+        put_u32(&mut data, 0x1488, 0x1500 - 0x1488);
+        put_u32(&mut data, 0x1490, 0x2040 - 0x1490);
+        put_u64(&mut data, 0x1500, 0x1620);
+        // The category list, with a category on the class.
+        put_u64(&mut data, 0x1010, 0x1700);
+        put_u64(&mut data, 0x1700, 0x1630);
+        put_u64(&mut data, 0x1708, 0x1100);
+        put_u64(&mut data, 0x1710, 0x1400);
+        put_str(&mut data, 0x1600, "Foo");
+        put_str(&mut data, 0x1610, "bar:");
+        put_str(&mut data, 0x1620, "create");
+        put_str(&mut data, 0x1630, "Extras");
+
+        let mappings = [MappedRange {
+            address: 0x1000,
+            size: data.len() as u64,
+            file_offset: 0,
+            data: &data[..],
+        }];
+        let reader = ObjcMetadataReader {
+            mappings: &mappings,
+            endian: Endianness::Little,
+            is_64: true,
+            pointer_format: PointerFormat::Plain,
+            image_base: 0,
+            relative_selector_base: None,
+            bind_names: Vec::new(),
+        };
+        assert_eq!(
+            reader.method_implementations(Some((0x1000, 8)), Some((0x1010, 8))),
+            vec![
+                (0x2000, "-[Foo bar:]".to_string()),
+                (0x2040, "+[Foo create]".to_string()),
+                (0x2000, "-[Foo(Extras) bar:]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_trie() {
+        #[rustfmt::skip]
+        let trie = [
+            // The root node, at offset 0, with an edge "_" to the node at offset 5.
+            0x00, 0x01, b'_', 0, 5,
+            // The node at offset 5, with edges to the nodes at 22, 27 and 34.
+            0x00, 0x03,
+            b'f', b'o', b'o', 0, 22,
+            b'b', b'a', b'r', 0, 27,
+            b'b', b'a', b'z', 0, 34,
+            // _foo: a regular export at 0x1000.
+            0x03, 0x00, 0x80, 0x20, 0x00,
+            // _bar: a stub at 0x2000 with a resolver at 0x2100.
+            0x05, 0x10, 0x80, 0x40, 0x80, 0x42, 0x00,
+            // _baz: a re-export from the library with ordinal 1.
+            0x03, 0x08, 0x01, 0x00, 0x00,
+        ];
+        let mut exports = parse_exports_trie(&trie);
+        exports.sort();
+        assert_eq!(
+            exports,
+            vec![
+                (0x1000, "_foo".to_string()),
+                (0x2000, "_bar".to_string()),
+                (0x2100, "resolver for _bar".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn object_modification_times() {
+        let symbols: [(&str, u8, u64); 4] = [
+            ("/obj/a.o", macho::N_OSO, 1650000000),
+            ("/obj/libb.a(b.o)", macho::N_OSO, 1650000100),
+            ("/obj/c.o", macho::N_OSO, 0),
+            ("_main", macho::N_SECT | macho::N_EXT, 0x1000),
+        ];
+        let symoff = 32 + 24;
+        let stroff = symoff + 16 * symbols.len() as u32;
+        let mut strings = vec![0u8];
+        let mut nlists = Vec::new();
+        for (name, n_type, n_value) in symbols {
+            nlists.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+            nlists.extend_from_slice(&[n_type, 1, 0, 0]);
+            nlists.extend_from_slice(&n_value.to_le_bytes());
+            strings.extend_from_slice(name.as_bytes());
+            strings.push(0);
+        }
+        let mut data = Vec::new();
+        for value in [
+            macho::MH_MAGIC_64,
+            macho::CPU_TYPE_ARM64,
+            0,
+            macho::MH_EXECUTE,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [1u32, 24, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [
+            macho::LC_SYMTAB,
+            24,
+            symoff,
+            4,
+            stroff,
+            strings.len() as u32,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&nlists);
+        data.extend_from_slice(&strings);
+
+        let macho_data = MachOData::new(&data[..], 0, true);
+        let modification_times = macho_data.get_object_modification_times().unwrap();
+        assert_eq!(modification_times.len(), 2);
+        assert_eq!(
+            modification_times.get(&b"/obj/a.o"[..]).copied(),
+            Some(1650000000)
+        );
+        assert_eq!(
+            modification_times.get(&b"/obj/libb.a(b.o)"[..]).copied(),
+            Some(1650000100)
+        );
+    }
+
+    #[test]
+    fn chained_fixup_pointers() {
+        let mappings: [MappedRange<&[u8]>; 0] = [];
+        let reader = ObjcMetadataReader {
+            mappings: &mappings,
+            endian: Endianness::Little,
+            is_64: true,
+            pointer_format: PointerFormat::Chained(DYLD_CHAINED_PTR_64_OFFSET),
+            image_base: 0x1_0000_0000,
+            relative_selector_base: None,
+            bind_names: vec!["_OBJC_CLASS_$_NSObject"],
+        };
+        assert_eq!(
+            reader.resolve_pointer(0x0010_0000_0000_4000),
+            Some(0x1_0000_4000)
+        );
+        assert_eq!(reader.resolve_pointer(0x8000_0000_0000_0000), None);
+        assert_eq!(
+            reader.bind_name(0x8000_0000_0000_0000),
+            Some("_OBJC_CLASS_$_NSObject")
+        );
+    }
+
+    #[test]
+    fn moved_object_files() {
+        use crate::symbolicate::v5::looked_up_addresses::LookedUpAddresses;
+        use crate::test_helper::{block_on, TestHelper};
+
+        // A 64-bit x86-64 MH_OBJECT file without load commands.
+        let mut object_file = Vec::new();
+        for value in [
+            macho::MH_MAGIC_64,
+            macho::CPU_TYPE_X86_64,
+            3,
+            macho::MH_OBJECT,
+            0,
+            0,
+            0,
+            0,
+        ] {
+            object_file.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut helper = TestHelper::default();
+        helper
+            .files
+            .insert(PathBuf::from("/new/build/foo.o"), object_file);
+        helper.moved_object_files.insert(
+            PathBuf::from("/old/build/foo.o"),
+            PathBuf::from("/new/build/foo.o"),
+        );
+
+        let object_reference = |path: &str| ObjectReference::Regular {
+            path: PathBuf::from(path),
+            functions: FunctionsWithAddresses::new(),
+            modification_time: None,
+        };
+        let object_references: VecDeque<_> = vec![
+            object_reference("/old/build/foo.o"),
+            object_reference("/old/build/bar.o"),
+        ]
+        .into();
+        let mut result = LookedUpAddresses::for_addresses(&[]);
+        let mut path_mapper = PathMapper::new(&BasePath::NoLocalSourceFileAccess, &[]);
+        block_on(traverse_object_references_and_collect_debug_info(
+            object_references,
+            &mut result,
+            &helper,
+            &mut path_mapper,
+        ))
+        .unwrap();
+
+        // foo.o was found at its new location, and bar.o has no candidate paths.
+        assert_eq!(result.debug_info_errors.len(), 1);
+        assert!(matches!(
+            &result.debug_info_errors[0],
+            GetSymbolsError::NoCandidatePathForObjectFile(path) if path == "/old/build/bar.o"
+        ));
+    }
+}
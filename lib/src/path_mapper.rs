@@ -1,5 +1,7 @@
 use regex::Regex;
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 
 use crate::shared::{BasePath, FilePath};
@@ -14,26 +16,135 @@ impl ExtraPathMapper for () {
     }
 }
 
+impl<E: ExtraPathMapper> ExtraPathMapper for Option<E> {
+    fn map_path(&mut self, path: &str) -> Option<String> {
+        self.as_mut()?.map_path(path)
+    }
+}
+
+/// Tries the first mapper, and then the second one.
+impl<A: ExtraPathMapper, B: ExtraPathMapper> ExtraPathMapper for (A, B) {
+    fn map_path(&mut self, path: &str) -> Option<String> {
+        self.0.map_path(path).or_else(|| self.1.map_path(path))
+    }
+}
+
+/// A rule which rewrites raw source file paths from the debug info, for example
+/// to undo the effect of `-fdebug-prefix-map`. Rules apply to DWARF and PDB paths
+/// alike. They are tried in order, before srcsrv, SourceLink and the built-in
+/// mappings for Rust paths, and the first matching rule wins.
+///
+/// In JSON, a rule has either a "prefix" or a "regex" key, and either a "mapped"
+/// or a "local" key, e.g.
+/// `{ "prefix": "/builds/worker/checkouts/gecko/", "mapped": "hg:hg.mozilla.org/mozilla-central:${path}:1706d4d54ec6" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "PathMappingRuleJson")]
+pub struct PathMappingRule {
+    regex: Regex,
+    target: PathMappingTarget,
+}
+
+/// What a `PathMappingRule` turns a matching path into. The string is a template:
+/// `$name` or `${name}` is replaced with the named capture group of the rule's
+/// regex, and `$1` etc. with a numbered group. For prefix rules, `${path}` is the
+/// rest of the path after the prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathMappingTarget {
+    /// A mapped path which is returned instead of the raw path, usually a special
+    /// path like "hg:<repo>:<path>:<rev>". Backslashes are turned into forward slashes.
+    Mapped(String),
+    /// The path of a local file, for example in a local checkout of the sources.
+    /// The source API can read this file even if the symbol file is not local.
+    Local(String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PathPattern {
+    Prefix(String),
+    Regex(String),
+}
+
+#[derive(Deserialize)]
+struct PathMappingRuleJson {
+    #[serde(flatten)]
+    pattern: PathPattern,
+    #[serde(flatten)]
+    target: PathMappingTarget,
+}
+
+impl TryFrom<PathMappingRuleJson> for PathMappingRule {
+    type Error = regex::Error;
+
+    fn try_from(json: PathMappingRuleJson) -> Result<Self, Self::Error> {
+        match json.pattern {
+            PathPattern::Prefix(prefix) => Ok(Self::prefix(&prefix, json.target)),
+            PathPattern::Regex(regex) => Self::regex(&regex, json.target),
+        }
+    }
+}
+
+impl PathMappingRule {
+    /// A rule for paths which start with `prefix`. The match is case-sensitive.
+    pub fn prefix(prefix: &str, target: PathMappingTarget) -> Self {
+        let regex = Regex::new(&format!("^{}(?P<path>.*)$", regex::escape(prefix))).unwrap();
+        PathMappingRule { regex, target }
+    }
+
+    /// A rule for paths which match `regex`.
+    pub fn regex(regex: &str, target: PathMappingTarget) -> Result<Self, regex::Error> {
+        let regex = Regex::new(regex)?;
+        Ok(PathMappingRule { regex, target })
+    }
+
+    /// Whether this rule maps paths to local files.
+    pub fn maps_to_local_path(&self) -> bool {
+        matches!(self.target, PathMappingTarget::Local(_))
+    }
+
+    fn apply(&self, raw_path: &str) -> Option<PathMappingTarget> {
+        let captures = self.regex.captures(raw_path)?;
+        let expand = |template: &str| {
+            let mut result = String::new();
+            captures.expand(template, &mut result);
+            result
+        };
+        Some(match &self.target {
+            PathMappingTarget::Mapped(template) => {
+                PathMappingTarget::Mapped(expand(template).replace('\\', "/"))
+            }
+            PathMappingTarget::Local(template) => PathMappingTarget::Local(expand(template)),
+        })
+    }
+}
+
 pub struct PathMapper<E: ExtraPathMapper> {
     base_path: BasePath,
     cache: HashMap<String, FilePath>,
+    rules: Vec<PathMappingRule>,
     extra_mapper: Option<E>,
     rustc_regex: Regex,
     cargo_dep_regex: Regex,
 }
 
 impl<'a, E: ExtraPathMapper> PathMapper<E> {
-    pub fn new(base_path: &BasePath) -> Self {
-        Self::new_with_maybe_extra_mapper(base_path, None)
+    pub fn new(base_path: &BasePath, rules: &[PathMappingRule]) -> Self {
+        Self::new_with_maybe_extra_mapper(base_path, rules, None)
     }
 
-    pub fn new_with_maybe_extra_mapper(base_path: &BasePath, extra_mapper: Option<E>) -> Self {
+    pub fn new_with_maybe_extra_mapper(
+        base_path: &BasePath,
+        rules: &[PathMappingRule],
+        extra_mapper: Option<E>,
+    ) -> Self {
         PathMapper {
             base_path: base_path.clone(),
             cache: HashMap::new(),
+            rules: rules.to_vec(),
             extra_mapper,
             rustc_regex: Regex::new(r"^/rustc/(?P<rev>[0-9a-f]+)\\?[/\\](?P<path>.*)$").unwrap(),
-            cargo_dep_regex: Regex::new(r"[/\\]\.cargo[/\\]registry[/\\]src[/\\](?P<registry>[^/\\]+)[/\\](?P<crate>[^/]+)-(?P<version>[0-9]+\.[0-9]+\.[0-9]+)[/\\](?P<path>.*)$").unwrap(),
+            cargo_dep_regex: Regex::new(r"[/\\]\.cargo[/\\]registry[/\\]src[/\\](?P<registry>[^/\\]+)[/\\](?P<crate>[^/\\]+?)-(?P<version>[0-9]+\.[0-9]+\.[0-9]+(?:[-+][0-9A-Za-z.+-]*)?)[/\\](?P<path>.*)$").unwrap(),
         }
     }
 
@@ -43,23 +154,34 @@ impl<'a, E: ExtraPathMapper> PathMapper<E> {
     /// a relative or an absolute path on the local machine which is resolved with
     /// respect to `self.base_path`.
     pub fn map_path(&mut self, raw_path: &str) -> FilePath {
-        if let Some(extra_mapper) = &mut self.extra_mapper {
-            if let Some(mapped_path) = extra_mapper.map_path(raw_path) {
-                let file_path = match &self.base_path {
-                    BasePath::NoLocalSourceFileAccess => FilePath::NonLocal(mapped_path),
-                    BasePath::CanReferToLocalFiles(base) => FilePath::LocalMapped {
-                        local: make_abs_path(base, raw_path),
-                        mapped: mapped_path,
-                    },
-                };
-                return file_path;
-            }
-        }
-
         if let Some(value) = self.cache.get(raw_path) {
             return value.clone();
         }
 
+        let file_path = self.map_path_uncached(raw_path);
+        self.cache.insert(raw_path.into(), file_path.clone());
+        file_path
+    }
+
+    fn map_path_uncached(&mut self, raw_path: &str) -> FilePath {
+        let mapped_path = match self.rules.iter().find_map(|rule| rule.apply(raw_path)) {
+            Some(PathMappingTarget::Local(local)) => return FilePath::Local(local.into()),
+            Some(PathMappingTarget::Mapped(mapped)) => Some(mapped),
+            None => match &mut self.extra_mapper {
+                Some(extra_mapper) => extra_mapper.map_path(raw_path),
+                None => None,
+            },
+        };
+        if let Some(mapped_path) = mapped_path {
+            return match &self.base_path {
+                BasePath::NoLocalSourceFileAccess => FilePath::NonLocal(mapped_path),
+                BasePath::CanReferToLocalFiles(base) => FilePath::LocalMapped {
+                    local: make_abs_path(base, raw_path),
+                    mapped: mapped_path,
+                },
+            };
+        }
+
         let mapped_path = if let Some(captures) = self.rustc_regex.captures(raw_path) {
             let rev = captures.name("rev").unwrap().as_str();
             let path = captures.name("path").unwrap().as_str();
@@ -79,7 +201,7 @@ impl<'a, E: ExtraPathMapper> PathMapper<E> {
             None
         };
 
-        let file_path = match &self.base_path {
+        match &self.base_path {
             BasePath::CanReferToLocalFiles(base) => {
                 let rel_or_abs = Path::new(raw_path);
                 if rel_or_abs.is_absolute() {
@@ -100,10 +222,7 @@ impl<'a, E: ExtraPathMapper> PathMapper<E> {
             BasePath::NoLocalSourceFileAccess => {
                 FilePath::NonLocal(mapped_path.unwrap_or_else(|| raw_path.to_owned()))
             }
-        };
-
-        self.cache.insert(raw_path.into(), file_path.clone());
-        file_path
+        }
     }
 }
 
@@ -115,3 +234,66 @@ fn make_abs_path(base: &Path, rel_or_abs: &str) -> PathBuf {
         base.join(rel_or_abs)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{PathMapper, PathMappingRule, PathMappingTarget};
+    use crate::shared::{BasePath, FilePath};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn path_mapping_rules() {
+        let rules: Vec<PathMappingRule> = serde_json::from_str(
+            r#"[
+                { "prefix": "/builds/worker/checkouts/gecko/", "mapped": "hg:hg.mozilla.org/mozilla-central:${path}:1706d4d54ec6" },
+                { "regex": "^/home/ci/src/(?P<project>[^/]+)/(?P<path>.*)$", "local": "/home/me/${project}/${path}" },
+                { "prefix": "C:\\build\\", "mapped": "git:github.com/example/app:${path}:0123abcd" }
+            ]"#,
+        )
+        .unwrap();
+        let mut mapper = PathMapper::<()>::new(&BasePath::NoLocalSourceFileAccess, &rules);
+        assert!(matches!(
+            mapper.map_path("/builds/worker/checkouts/gecko/dom/base/Element.cpp"),
+            FilePath::NonLocal(path) if path == "hg:hg.mozilla.org/mozilla-central:dom/base/Element.cpp:1706d4d54ec6"
+        ));
+        // Rules which map to local paths make local paths even for non-local symbols.
+        assert!(matches!(
+            mapper.map_path("/home/ci/src/app/main.c"),
+            FilePath::Local(path) if path == Path::new("/home/me/app/main.c")
+        ));
+        assert!(matches!(
+            mapper.map_path(r"C:\build\src\main.cpp"),
+            FilePath::NonLocal(path) if path == "git:github.com/example/app:src/main.cpp:0123abcd"
+        ));
+        // Paths which no rule matches get the built-in mappings.
+        assert!(matches!(
+            mapper.map_path("/rustc/0123abcd/library/core/src/ptr/mod.rs"),
+            FilePath::NonLocal(path) if path == "git:github.com/rust-lang/rust:library/core/src/ptr/mod.rs:0123abcd"
+        ));
+        assert!(matches!(
+            mapper.map_path("/home/me/.cargo/registry/src/github.com-1ecc6299db9ec823/tokio-1.0.0-rc.1/src/lib.rs"),
+            FilePath::NonLocal(path) if path == "cargo:github.com-1ecc6299db9ec823:tokio-1.0.0-rc.1:src/lib.rs"
+        ));
+        assert!(matches!(
+            mapper.map_path("/home/other/main.c"),
+            FilePath::NonLocal(path) if path == "/home/other/main.c"
+        ));
+
+        let mut mapper = PathMapper::<()>::new(
+            &BasePath::CanReferToLocalFiles(PathBuf::from("/symbols")),
+            &[PathMappingRule::prefix(
+                "/builds/",
+                PathMappingTarget::Mapped("s3:bucket:${path}:".to_string()),
+            )],
+        );
+        assert!(matches!(
+            mapper.map_path("/builds/foo.c"),
+            FilePath::LocalMapped { local, mapped } if local == Path::new("/builds/foo.c") && mapped == "s3:bucket:foo.c:"
+        ));
+
+        assert!(PathMappingRule::regex("(", PathMappingTarget::Local(String::new())).is_err());
+        assert!(
+            serde_json::from_str::<PathMappingRule>(r#"{ "regex": "(", "local": "" }"#).is_err()
+        );
+    }
+}
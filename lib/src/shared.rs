@@ -1,3 +1,5 @@
+use crate::error::GetSymbolsError;
+use crate::path_mapper::PathMappingRule;
 use debugid::DebugId;
 use object::read::ReadRef;
 use object::{SectionKind, SymbolKind};
@@ -46,6 +48,20 @@ pub enum CandidatePathInfo {
         dyld_cache_path: PathBuf,
         dylib_path: String,
     },
+    /// A library in the dyld shared cache, which is found by its debug ID rather
+    /// than by its path. This is slower than `InDyldCache`, because the headers of
+    /// the images in the cache have to be parsed until the matching one is found.
+    InDyldCacheByDebugId {
+        dyld_cache_path: PathBuf,
+    },
+    /// A binary together with its separate debug file, for example a stripped ELF
+    /// binary and its `.debug` file, or a Mach-O binary and the DWARF file in its
+    /// dSYM bundle. The symbols from both files are merged, and the debug info is
+    /// read from the debug file.
+    BinaryWithDebugFile {
+        binary: FileLocation,
+        debug_file: FileLocation,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -139,6 +155,35 @@ pub trait FileAndPathHelper<'h> {
         Ok(vec![FileLocation::Path(s.into())])
     }
 
+    /// This method can usually be ignored and does not need to be implemented; its default
+    /// implementation is usually what you want.
+    ///
+    /// Mach-O binaries which were linked without a dSYM bundle keep their debug info in the
+    /// object files they were linked from, and refer to those object files by the absolute
+    /// path they had at link time. This method is called for each of these object files,
+    /// and returns the locations where the object file may be found. This allows finding
+    /// object files which were moved after linking, for example if the build directory
+    /// was downloaded from a build machine.
+    ///
+    /// The locations are tried in order, until one of them can be opened and contains the
+    /// requested object.
+    ///
+    /// # Arguments
+    ///
+    ///  - `object_path`: The path of the object file as stored in the binary. For objects
+    ///    from static libraries, this is the path of the archive (the `.a` file).
+    ///  - `archive_member_name`: For objects from static libraries, the name of the object
+    ///    file inside the archive, e.g. "Unified_cpp_js_src13.o". The returned locations
+    ///    need to point to archives which contain this member.
+    ///
+    fn get_candidate_paths_for_object_file(
+        &self,
+        object_path: &Path,
+        _archive_member_name: Option<&str>,
+    ) -> FileAndPathHelperResult<Vec<FileLocation>> {
+        Ok(vec![FileLocation::Path(object_path.to_path_buf())])
+    }
+
     /// This method is the entry point for file access during symbolication.
     /// The implementer needs to return an object which implements the `FileContents` trait.
     /// This method is asynchronous, but once it returns, the file data needs to be
@@ -146,6 +191,48 @@ pub trait FileAndPathHelper<'h> {
     /// If there is no file at the requested path, an error should be returned (or in any
     /// other error case).
     fn open_file(&'h self, location: &FileLocation) -> Self::OpenFileFuture;
+
+    /// Returns the modification time of the file at `location`, in seconds since
+    /// the Unix epoch, or `None` if it's not known.
+    ///
+    /// This is used for the object files which are referenced by the debug map of
+    /// a mach-O binary: The linker records the modification time of each object
+    /// file, and object files which have been modified since then (for example by
+    /// an incremental rebuild) are skipped, because their debug info no longer
+    /// matches the binary. The default implementation returns `None`, which skips
+    /// this check.
+    fn get_file_modification_time(&self, _location: &FileLocation) -> Option<u64> {
+        None
+    }
+
+    /// Returns rules which rewrite the source file paths from the debug info, for
+    /// example to map the paths of a build machine to a local checkout. They apply
+    /// to all queries, after the rules in `SymbolicationQuery::path_mapping_rules`.
+    /// The default implementation returns no rules.
+    fn get_path_mapping_rules(&self) -> Vec<PathMappingRule> {
+        Vec::new()
+    }
+
+    /// This method can usually be ignored and does not need to be implemented; its default
+    /// implementation is usually what you want.
+    ///
+    /// This is called by the `/source/v1` API if the requested file has a special path,
+    /// like "git:github.com/rust-lang/rust:library/core/src/ptr/mod.rs:<rev>", and it can't
+    /// read the file at the original path, for example because the symbol file was
+    /// downloaded from a symbol server. The implementer can return the location of the
+    /// file in a local checkout of the repository or in `~/.cargo/registry`, or the
+    /// contents of the file, e.g. downloaded from the URL of a `SpecialPath::Url`. The requested path always comes from the debug info for
+    /// the requested address, mapped by the built-in mappings or by the rules from
+    /// `get_path_mapping_rules`; paths which were produced by the path mapping rules
+    /// of a request are never passed to this method. Special paths with absolute paths
+    /// or `..` components are rejected by `SpecialPath::parse`. The default
+    /// implementation returns `Ok(None)`.
+    fn resolve_special_path(
+        &self,
+        _special_path: &SpecialPath,
+    ) -> FileAndPathHelperResult<Option<ResolvedSpecialPath>> {
+        Ok(None)
+    }
 }
 
 /// Provides synchronous access to the raw bytes of a file.
@@ -183,6 +270,124 @@ pub trait FileContents {
     ) -> FileAndPathHelperResult<()>;
 }
 
+#[derive(Debug, Clone)]
+pub struct AddressSymbolInfo<'a> {
+    /// The closest (<= address) symbol address.
+    pub symbol_address: u32,
+    pub symbol_name: &'a str,
+    pub function_size: Option<u32>,
+    /// True if the symbol is for a part of a function which the compiler has split off,
+    /// in which case `symbol_name` is the name of the parent function. This only happens
+    /// if the query has `attribute_split_functions_to_parent` set.
+    pub is_split_function_part: bool,
+    /// Where `symbol_name` came from.
+    pub source: SymbolSource,
+    /// The name of the section which contains the looked-up address, if known.
+    pub section_name: Option<&'a str>,
+    /// True if the looked-up address is not inside any executable section. This can
+    /// indicate that the address is bogus, or that the symbol is not trustworthy.
+    pub is_outside_text: bool,
+}
+
+/// Where a symbol name came from. This lets consumers judge the quality of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolSource {
+    /// The regular symbol table of the binary (.symtab, LC_SYMTAB or COFF symbols).
+    SymbolTable,
+    /// The dynamic symbol table of an ELF binary (.dynsym).
+    DynamicSymbolTable,
+    /// The export table of a PE binary, or the exports trie of a Mach-O binary.
+    Export,
+    /// A PDB file.
+    Pdb,
+    /// DWARF debug info.
+    Dwarf,
+    /// The function table of a Go binary (.gopclntab).
+    GoPclntab,
+    /// A synthesized name of the form "-[Class selector]", for an Objective-C method
+    /// implementation found in the Objective-C class metadata of a Mach-O binary.
+    ObjcMetadata,
+    /// A synthesized name for a stub, such as "memcpy@plt".
+    Stub,
+    /// A synthesized placeholder name of the form "fun_abcdef", for a function
+    /// whose start address is known but which has no symbol.
+    Synthesized,
+}
+
+impl SymbolSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolSource::SymbolTable => "symtab",
+            SymbolSource::DynamicSymbolTable => "dynsym",
+            SymbolSource::Export => "export",
+            SymbolSource::Pdb => "pdb",
+            SymbolSource::Dwarf => "dwarf",
+            SymbolSource::GoPclntab => "gopclntab",
+            SymbolSource::ObjcMetadata => "objc",
+            SymbolSource::Stub => "stub",
+            SymbolSource::Synthesized => "synthesized",
+        }
+    }
+}
+
+/// The relative address range of a section, used to find the section for an address.
+pub struct SectionInfo {
+    pub start: u32,
+    pub end: u32,
+    pub name: String,
+    pub is_executable: bool,
+}
+
+impl SectionInfo {
+    /// Returns the section which contains `address`, or None.
+    pub fn find(sections: &[SectionInfo], address: u32) -> Option<&SectionInfo> {
+        sections
+            .iter()
+            .find(|section| section.start <= address && address < section.end)
+    }
+}
+
+/// Collect the sections of an object file, with addresses relative to the base address.
+/// Mach-O section names are prefixed with the segment name, e.g. "__TEXT,__text".
+fn object_section_infos<'data: 'file, 'file>(
+    object_file: &'file impl object::Object<'data, 'file>,
+) -> Vec<SectionInfo> {
+    use object::ObjectSection;
+    let base_address = relative_address_base(object_file);
+    object_file
+        .sections()
+        // ELF .tbss sections don't take up any address space; their addresses
+        // overlap with the sections that follow them.
+        .filter(|section| section.kind() != SectionKind::UninitializedTls)
+        .filter_map(|section| {
+            let start = u32::try_from(section.address().checked_sub(base_address)?).ok()?;
+            let end = start.checked_add(u32::try_from(section.size()).ok()?)?;
+            let name = section.name().ok()?;
+            let name = match section.segment_name() {
+                Ok(Some(segment_name)) => format!("{},{}", segment_name, name),
+                _ => name.to_string(),
+            };
+            // Mach-O sections like __stubs contain code but don't have the Text kind,
+            // so check their attributes, too.
+            let is_executable = match section.flags() {
+                object::SectionFlags::MachO { flags } => {
+                    flags
+                        & (object::macho::S_ATTR_PURE_INSTRUCTIONS
+                            | object::macho::S_ATTR_SOME_INSTRUCTIONS)
+                        != 0
+                }
+                _ => section.kind() == SectionKind::Text,
+            };
+            Some(SectionInfo {
+                start,
+                end,
+                name,
+                is_executable,
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct AddressDebugInfo {
     /// Must be non-empty. Ordered from inside to outside.
@@ -194,7 +399,18 @@ pub struct AddressDebugInfo {
 pub struct InlineStackFrame {
     pub function: Option<String>,
     pub file_path: Option<FilePath>, // maybe PathBuf?
+    /// The checksum of the source file's contents at compile time, if the debug
+    /// info records one for `file_path`.
+    pub file_checksum: Option<SourceFileChecksum>,
     pub line_number: Option<u32>,
+    pub column_number: Option<u32>,
+    /// The file and line where this frame's function is declared, i.e. where its
+    /// source code starts.
+    pub function_decl_file_path: Option<FilePath>,
+    pub function_decl_line_number: Option<u32>,
+    /// For inlined frames: The relative address where the inlined code which
+    /// contains the looked-up address starts.
+    pub inline_range_start: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -251,6 +467,143 @@ pub enum FilePath {
     NonLocal(String),
 }
 
+/// A special path, which refers to a file without depending on the machine on which
+/// the file was compiled, parsed into its parts. Special paths are produced by the
+/// path mapping for Rust paths, srcsrv, SourceLink and `PathMappingRule`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecialPath {
+    /// "<vcs>:<repo>:<path>:<rev>", a file at a revision of a repository, where
+    /// `vcs` is "git", "hg", "svn", "p4" or "tfs". The repository can contain a
+    /// port number, the path and the revision don't contain colons.
+    Repository {
+        vcs: String,
+        repo: String,
+        path: String,
+        rev: String,
+    },
+    /// "cargo:<registry>:<crate>-<version>:<path>", a file in a crate from a cargo registry.
+    Cargo {
+        registry: String,
+        crate_name: String,
+        version: String,
+        path: String,
+    },
+    /// "s3:<bucket>:<digest_and_path>:", a file in an S3 bucket.
+    S3 {
+        bucket: String,
+        digest_and_path: String,
+    },
+    /// An "http://" or "https://" URL of the file, from a srcsrv download command
+    /// whose URL isn't recognized as a file in a repository or in an S3 bucket.
+    Url(String),
+}
+
+impl SpecialPath {
+    /// Parses a special path. Returns `None` if `path` is not a special path.
+    ///
+    /// Special paths are resolved to local files by `FileAndPathHelper::resolve_special_path`,
+    /// so paths which could escape the checkout or registry directory, i.e. absolute
+    /// paths and paths with `..` components, are rejected.
+    pub fn parse(path: &str) -> Option<Self> {
+        let (scheme, rest) = path.split_once(':')?;
+        match scheme {
+            "git" | "hg" | "svn" | "p4" | "tfs" => {
+                let (rest, rev) = rest.rsplit_once(':')?;
+                let (repo, path) = rest.rsplit_once(':')?;
+                if has_parent_components(repo) || !is_contained_relative_path(path) {
+                    return None;
+                }
+                Some(SpecialPath::Repository {
+                    vcs: scheme.to_string(),
+                    repo: repo.to_string(),
+                    path: path.to_string(),
+                    rev: rev.to_string(),
+                })
+            }
+            "cargo" => {
+                let (registry, rest) = rest.split_once(':')?;
+                let (crate_and_version, path) = rest.split_once(':')?;
+                if !is_path_component(registry)
+                    || !is_path_component(crate_and_version)
+                    || !is_contained_relative_path(path)
+                {
+                    return None;
+                }
+                let (crate_name, version) = split_crate_and_version(crate_and_version)?;
+                Some(SpecialPath::Cargo {
+                    registry: registry.to_string(),
+                    crate_name: crate_name.to_string(),
+                    version: version.to_string(),
+                    path: path.to_string(),
+                })
+            }
+            "s3" => {
+                let (bucket, digest_and_path) = rest.split_once(':')?;
+                let digest_and_path = digest_and_path.strip_suffix(':')?;
+                if !is_path_component(bucket) || !is_contained_relative_path(digest_and_path) {
+                    return None;
+                }
+                Some(SpecialPath::S3 {
+                    bucket: bucket.to_string(),
+                    digest_and_path: digest_and_path.to_string(),
+                })
+            }
+            "http" | "https" if rest.starts_with("//") => Some(SpecialPath::Url(path.to_string())),
+            _ => None,
+        }
+    }
+}
+
+const PATH_SEPARATORS: &[char] = &['/', '\\'];
+
+/// Splits "<crate>-<version>" at the first dash which is followed by a version of
+/// the form "1.2.3". Both crate names and pre-release versions can contain dashes,
+/// e.g. "tokio-1.0.0-rc.1" or "sha-1-0.10.0".
+fn split_crate_and_version(crate_and_version: &str) -> Option<(&str, &str)> {
+    crate_and_version
+        .match_indices('-')
+        .map(|(index, _)| (&crate_and_version[..index], &crate_and_version[index + 1..]))
+        .find(|(crate_name, version)| {
+            let version_core = version.split(&['-', '+'][..]).next().unwrap_or_default();
+            let parts: Vec<&str> = version_core.split('.').collect();
+            !crate_name.is_empty()
+                && parts.len() == 3
+                && parts.iter().all(|part| is_version_number(part))
+        })
+}
+
+fn is_version_number(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn has_parent_components(path: &str) -> bool {
+    path.split(PATH_SEPARATORS)
+        .any(|component| component == "..")
+}
+
+/// Whether `path` is a relative path which stays inside the directory it is
+/// relative to: it's not absolute, has no drive letter and no `..` components.
+fn is_contained_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with(PATH_SEPARATORS)
+        && !path.contains(':')
+        && !has_parent_components(path)
+}
+
+/// Whether `name` can be used as a single component of a path.
+fn is_path_component(name: &str) -> bool {
+    is_contained_relative_path(name) && !name.contains(PATH_SEPARATORS) && name != "."
+}
+
+/// What `FileAndPathHelper::resolve_special_path` resolved a special path to.
+pub enum ResolvedSpecialPath {
+    /// A file which contains the source, e.g. in a local checkout. It is opened
+    /// with `FileAndPathHelper::open_file`.
+    File(FileLocation),
+    /// The contents of the file.
+    Contents(Vec<u8>),
+}
+
 impl FilePath {
     pub fn mapped_path(&self) -> Cow<str> {
         match self {
@@ -285,6 +638,57 @@ impl FilePath {
     }
 }
 
+/// A checksum of a source file, as recorded in the debug info when the file was
+/// compiled. PDB files can have MD5, SHA1 or SHA256 checksums, DWARF 5 line tables
+/// can have MD5 checksums.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceFileChecksum {
+    Md5([u8; 16]),
+    Sha1([u8; 20]),
+    Sha256([u8; 32]),
+}
+
+impl SourceFileChecksum {
+    /// The name of the hash algorithm, "md5", "sha1" or "sha256".
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            SourceFileChecksum::Md5(_) => "md5",
+            SourceFileChecksum::Sha1(_) => "sha1",
+            SourceFileChecksum::Sha256(_) => "sha256",
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            SourceFileChecksum::Md5(bytes) => bytes,
+            SourceFileChecksum::Sha1(bytes) => bytes,
+            SourceFileChecksum::Sha256(bytes) => bytes,
+        }
+    }
+
+    /// Hashes `contents` with this checksum's algorithm and returns whether the
+    /// result matches.
+    pub fn matches(&self, contents: &[u8]) -> bool {
+        use sha2::Digest;
+        match self {
+            SourceFileChecksum::Md5(bytes) => md5::Md5::digest(contents)[..] == bytes[..],
+            SourceFileChecksum::Sha1(bytes) => sha1::Sha1::digest(contents)[..] == bytes[..],
+            SourceFileChecksum::Sha256(bytes) => sha2::Sha256::digest(contents)[..] == bytes[..],
+        }
+    }
+}
+
+/// Formats the checksum as "<algorithm>:<hex bytes>", e.g. "md5:d41d8cd9...".
+impl std::fmt::Display for SourceFileChecksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.algorithm())?;
+        for byte in self.bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SymbolicationResultKind<'a> {
     AllSymbols,
@@ -305,6 +709,17 @@ impl<'a> SymbolicationResultKind<'a> {
     }
 }
 
+/// Which kind of symbols a query looks up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuerySymbolKind {
+    /// Function symbols, for symbolicating code addresses, e.g. in stacks.
+    Functions,
+    /// Symbols for global, static and thread-local variables, for symbolicating
+    /// pointers into data sections such as .data, .rodata, .bss or __DATA.
+    /// No debug info is returned for these symbols.
+    Data,
+}
+
 /// A trait which allows many "get_symbolication_result" functions to share code between
 /// the implementation that constructs a full symbol table and the implementation that
 /// constructs a JSON response with data per looked-up address.
@@ -321,18 +736,19 @@ pub trait SymbolicationResult {
     /// and potentially `add_address_debug_info`.
     fn for_addresses(addresses: &[u32]) -> Self;
 
-    /// Called to supply the symbol name for a symbol.
+    /// Called to supply the symbol for an address.
     /// Only called if `result_kind` is `SymbolicationResultKind::SymbolsForAddresses`, and
     /// only on objects constructed by a call to `for_addresses`.
     /// `address` is the address that the consumer wants to look up, and may fall anywhere
-    /// inside a function. `symbol_address` is the closest (<= address) symbol address.
-    fn add_address_symbol(
-        &mut self,
-        address: u32,
-        symbol_address: u32,
-        symbol_name: &str,
-        function_size: Option<u32>,
-    );
+    /// inside a function.
+    fn add_address_symbol(&mut self, address: u32, symbol: AddressSymbolInfo);
+
+    /// Called to supply the names of other symbols at the same symbol address, for example
+    /// when multiple functions were folded into one by the linker. `aliases` is ordered from
+    /// best to worst and does not contain the name that was passed to `add_address_symbol`.
+    /// Only called after `add_address_symbol` for the same address, and only if there are
+    /// any aliases.
+    fn add_address_symbol_aliases(&mut self, address: u32, aliases: &[&str]);
 
     /// Called to supply debug info for the address.
     /// Only called if `result_kind` is `SymbolicationResultKind::SymbolsForAddresses { with_debug_info: true }`.
@@ -342,6 +758,34 @@ pub trait SymbolicationResult {
     /// Only called if `result_kind` is `SymbolicationResultKind::SymbolsForAddresses`, and
     /// only on objects constructed by a call to `for_addresses`.
     fn set_total_symbol_count(&mut self, total_symbol_count: u32);
+
+    /// Called when some of the debug info couldn't be used, for example because an
+    /// object file which is referenced by a mach-O binary is out of date. The lookup
+    /// continues without this debug info. The default implementation ignores the error.
+    fn add_debug_info_error(&mut self, _error: GetSymbolsError) {}
+
+    /// Called for mach-O binaries, whose symbol table and export trie prefix every
+    /// C symbol name with an extra underscore, e.g. `_main`. Names from other
+    /// formats keep their leading underscores. The default implementation ignores this.
+    fn set_symbol_names_have_leading_underscore(&mut self) {}
+
+    /// Whether the source code which is embedded in the debug info should be supplied,
+    /// via `add_embedded_source`, for the files which the debug info of the looked-up
+    /// addresses refers to. Reading it has a cost, so the default implementation
+    /// returns false.
+    fn wants_embedded_source() -> bool {
+        false
+    }
+
+    /// Called to supply the source code of a file, if it is embedded in the debug info,
+    /// for example by clang's `-gembed-source` or as an injected source in a PDB file.
+    /// Only called if `wants_embedded_source` returns true.
+    fn add_embedded_source(&mut self, _file_path: FilePath, _source: String) {}
+
+    /// Called once the result is complete, with the location of the file which the
+    /// symbols came from. For a binary with a separate debug file, this is the debug
+    /// file, and for a dylib in the dyld shared cache, it is the cache.
+    fn set_symbol_file_location(&mut self, _location: &FileLocation) {}
 }
 
 /// A struct that wraps a number of parameters for various "get_symbolication_result" functions.
@@ -353,6 +797,16 @@ pub struct SymbolicationQuery<'a> {
     pub debug_id: DebugId,
     /// The kind of data which this query wants have returned.
     pub result_kind: SymbolicationResultKind<'a>,
+    /// Whether symbols for parts of functions which were split off by the compiler,
+    /// such as `foo.cold.1` or `foo.part.0`, should be reported under the name of
+    /// their parent function, i.e. `foo`.
+    pub attribute_split_functions_to_parent: bool,
+    /// Whether to look up function symbols or data symbols.
+    pub symbol_kind: QuerySymbolKind,
+    /// Rules which rewrite the source file paths from the debug info. They are
+    /// applied in order, in addition to the rules from
+    /// `FileAndPathHelper::get_path_mapping_rules`.
+    pub path_mapping_rules: &'a [PathMappingRule],
 }
 
 /// In the symbolication query, the requested addresses are in "relative address" form.
@@ -419,18 +873,55 @@ pub fn relative_address_base<'data: 'file, 'file>(
     object_file.relative_address_base()
 }
 
+/// Returns true if fewer than half of the functions in `function_starts` have a
+/// function symbol, given the sorted addresses of all function symbols in
+/// `symbol_addresses`. This is the case for stripped binaries, such as ELF binaries
+/// whose .symtab was removed, where .dynsym only has symbols for the exported functions.
+pub fn has_poor_symbol_coverage(
+    function_starts: &[u32],
+    symbol_addresses: &[u32],
+    has_symbol_table: bool,
+) -> bool {
+    if function_starts.is_empty() {
+        return !has_symbol_table;
+    }
+    let covered_count = function_starts
+        .iter()
+        .filter(|address| symbol_addresses.binary_search(address).is_ok())
+        .count();
+    covered_count * 2 < function_starts.len()
+}
+
+/// Returns a mask which needs to be applied to function symbol addresses.
+///
+/// On 32-bit ARM, the lowest bit of a function symbol's address is set if the
+/// function consists of Thumb code. The code itself starts at the address with
+/// this bit cleared, and that's the address that sampled addresses line up with.
+pub fn function_address_mask<'data: 'file, 'file>(
+    object_file: &'file impl object::Object<'data, 'file>,
+) -> u64 {
+    match object_file.architecture() {
+        object::Architecture::Arm => !1,
+        _ => !0,
+    }
+}
+
 /// Return a Vec that contains address -> symbol name entries.
 /// The address is relative to the address of the __TEXT segment (if present).
 /// We discard the symbol "size"; the address is where the symbol starts.
 pub fn object_to_map<'a: 'b, 'b, T>(
     object_file: &'b T,
     function_start_addresses: Option<&[u32]>,
+    stub_symbols: &[StubSymbol],
+    additional_symbols: &[AdditionalSymbol],
+    attribute_split_functions_to_parent: bool,
 ) -> Vec<(u32, String)>
 where
     T: object::Object<'a, 'b>,
 {
     use object::ObjectSymbol;
     let image_base = relative_address_base(object_file);
+    let address_mask = function_address_mask(object_file);
 
     let mut map: Vec<(u32, String)> = Vec::new();
 
@@ -447,19 +938,54 @@ where
         );
     }
 
-    // Add any symbols found in this library.
+    // Add synthesized symbols for stubs, such as PLT entries. Real symbols for
+    // the same address, if present, are added later and take precedence.
     map.extend(
+        stub_symbols
+            .iter()
+            .map(|stub| (stub.address, stub.name.clone())),
+    );
+
+    // Add function symbols from other sources, such as a separate debug file.
+    // Symbol table entries of this object for the same address take precedence.
+    map.extend(additional_symbols.iter().rev().map(|symbol| {
+        let name = match split_function_parent_name(&symbol.name) {
+            Some(parent_name) if attribute_split_functions_to_parent => parent_name,
+            _ => &symbol.name,
+        };
+        (symbol.address, name.to_string())
+    }));
+
+    // Add any symbols found in this library.
+    // When the map is turned into a symbol table, the last entry for each address wins.
+    // So we add aliases in the order worst to best, so that the best alias is kept.
+    let mut symbols: Vec<(u32, String, u8)> = Vec::new();
+    symbols.extend(
         object_file
             .dynamic_symbols()
             .chain(object_file.symbols())
-            .filter(|symbol| symbol.kind() == SymbolKind::Text)
+            .filter(is_function_symbol)
             .filter_map(|symbol| {
-                symbol
-                    .name()
-                    .ok()
-                    .map(|name| ((symbol.address() - image_base) as u32, name.to_string()))
+                symbol.name().ok().map(|name| {
+                    let address = symbol.address() & address_mask;
+                    let name = match split_function_parent_name(name) {
+                        Some(parent_name) if attribute_split_functions_to_parent => parent_name,
+                        _ => name,
+                    };
+                    (
+                        (address - image_base) as u32,
+                        name.to_string(),
+                        symbol_alias_rank(&symbol),
+                    )
+                })
             }),
     );
+    symbols.sort_by_key(|(_, _, rank)| std::cmp::Reverse(*rank));
+    map.extend(
+        symbols
+            .into_iter()
+            .map(|(address, name, _)| (address, name)),
+    );
 
     // For PE files, add the exports.
     if let Ok(exports) = object_file.exports() {
@@ -472,18 +998,136 @@ where
     map
 }
 
-enum FullSymbolListEntry<'a, Symbol: object::ObjectSymbol<'a>> {
+/// Returns true if the symbol is an ELF symbol of type `STT_GNU_IFUNC`.
+///
+/// The address of such a symbol is not the address of the function with that name,
+/// it is the address of a "resolver" function which picks an implementation of the
+/// function at load time.
+fn is_ifunc_symbol<'data>(symbol: &impl object::ObjectSymbol<'data>) -> bool {
+    match symbol.flags() {
+        object::SymbolFlags::Elf { st_info, .. } => st_info & 0xf == object::elf::STT_GNU_IFUNC,
+        _ => false,
+    }
+}
+
+fn is_function_symbol<'data>(symbol: &impl object::ObjectSymbol<'data>) -> bool {
+    symbol.kind() == SymbolKind::Text || is_ifunc_symbol(symbol)
+}
+
+/// Ranks a function symbol among the other symbols at the same address. Lower is better.
+///
+/// Multiple symbols at the same address are common, for example due to aliases or
+/// identical code folding. We prefer regular functions over IFUNC resolvers, global
+/// symbols over local symbols, and strong symbols over weak symbols, in that order.
+fn symbol_alias_rank<'data>(symbol: &impl object::ObjectSymbol<'data>) -> u8 {
+    (is_ifunc_symbol(symbol) as u8) << 2 | (symbol.is_local() as u8) << 1 | symbol.is_weak() as u8
+}
+
+/// A synthesized symbol for a stub, i.e. a small piece of code which jumps to a
+/// function that is usually in a different library. Examples are PLT entries in ELF
+/// files, `__stubs` entries in Mach-O files, and import thunks in PE files.
+/// These usually don't have symbols of their own.
+pub struct StubSymbol {
+    /// The relative address of the stub.
+    pub address: u32,
+    /// The size of the stub, in bytes.
+    pub size: u32,
+    /// The synthesized name, for example "memcpy@plt".
+    pub name: String,
+}
+
+/// A function symbol which does not come from the symbol tables of the object
+/// that is being symbolicated. This is either a symbol from a separate file, such
+/// as the .symtab of a separate debug file or of an ELF .gnu_debugdata section,
+/// or a symbol derived from debug info or metadata, e.g. from a DWARF
+/// `DW_TAG_subprogram` entry or from the Objective-C class list.
+pub struct AdditionalSymbol {
+    /// The relative address of the function.
+    pub address: u32,
+    /// The size of the function in bytes, if known.
+    pub size: Option<u32>,
+    pub name: String,
+    pub source: SymbolSource,
+}
+
+/// Collect the function symbols from the symbol table of `object_file`, to be used
+/// as additional symbols when symbolicating a different file, for example the
+/// stripped binary which `object_file` is the debug file of. The addresses are made
+/// relative to `base_address`, which is the relative address base of that binary.
+pub fn object_function_symbols<'data: 'file, 'file>(
+    object_file: &'file impl object::Object<'data, 'file>,
+    base_address: u64,
+) -> Vec<AdditionalSymbol> {
+    use object::ObjectSymbol;
+    let address_mask = function_address_mask(object_file);
+    let mut symbols: Vec<_> = object_file
+        .symbols()
+        .filter(is_function_symbol)
+        .filter(|symbol| !symbol.is_undefined())
+        .collect();
+    symbols.sort_by_key(symbol_alias_rank);
+    symbols
+        .into_iter()
+        .filter_map(|symbol| {
+            let address = (symbol.address() & address_mask).checked_sub(base_address)?;
+            Some(AdditionalSymbol {
+                address: u32::try_from(address).ok()?,
+                size: match symbol.size() {
+                    0 => None,
+                    size => Some(u32::try_from(size).ok()?),
+                },
+                name: symbol.name().ok()?.to_string(),
+                source: SymbolSource::SymbolTable,
+            })
+        })
+        .collect()
+}
+
+/// If `name` is the name of a part of a function which the compiler has split off
+/// from its parent function, returns the name that this part should be reported as.
+///
+/// GCC and clang name such parts `foo.cold`, `foo.cold.12` or `foo.part.0`, and we
+/// return the parent name `foo` for them. Functions created by the machine outliner
+/// are named `OUTLINED_FUNCTION_42`; they can be shared by multiple parent functions,
+/// so we can't tell which parent they belong to, and we return their name unchanged.
+fn split_function_parent_name(name: &str) -> Option<&str> {
+    if let Some(number) = name
+        .strip_prefix('_')
+        .unwrap_or(name)
+        .strip_prefix("OUTLINED_FUNCTION_")
+    {
+        if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(name);
+        }
+    }
+    let mut parent_end = None;
+    let mut offset = 0;
+    for (i, component) in name.split('.').enumerate() {
+        if i > 0 && (component == "cold" || component == "part") {
+            parent_end = Some(offset - 1);
+            break;
+        }
+        offset += component.len() + 1;
+    }
+    parent_end.map(|end| &name[..end])
+}
+
+enum FullSymbolListEntry<'a, 's, Symbol: object::ObjectSymbol<'a>> {
     Synthesized,
-    Symbol(Symbol),
+    Stub(&'s StubSymbol),
+    Additional(&'s AdditionalSymbol),
+    Symbol(Symbol, SymbolSource),
     Export(object::Export<'a>),
     EndAddress,
 }
 
-impl<'a, Symbol: object::ObjectSymbol<'a>> FullSymbolListEntry<'a, Symbol> {
+impl<'a, 's, Symbol: object::ObjectSymbol<'a>> FullSymbolListEntry<'a, 's, Symbol> {
     fn name(&self, addr: u32) -> Result<Cow<str>, ()> {
         match self {
             FullSymbolListEntry::Synthesized => Ok(format!("fun_{:x}", addr).into()),
-            FullSymbolListEntry::Symbol(symbol) => match symbol.name() {
+            FullSymbolListEntry::Stub(stub) => Ok(Cow::Borrowed(&stub.name)),
+            FullSymbolListEntry::Additional(symbol) => Ok(Cow::Borrowed(&symbol.name)),
+            FullSymbolListEntry::Symbol(symbol, _) => match symbol.name() {
                 Ok(name) => Ok(Cow::Borrowed(name)),
                 Err(_) => Err(()),
             },
@@ -494,16 +1138,54 @@ impl<'a, Symbol: object::ObjectSymbol<'a>> FullSymbolListEntry<'a, Symbol> {
             FullSymbolListEntry::EndAddress => Err(()),
         }
     }
+
+    /// Entries at the same address are sorted by this rank, best entry first.
+    fn rank(&self) -> u8 {
+        match self {
+            FullSymbolListEntry::Symbol(symbol, _) => symbol_alias_rank(symbol),
+            FullSymbolListEntry::Additional(_) => 8,
+            FullSymbolListEntry::Export(_) => 9,
+            FullSymbolListEntry::Stub(_) => 10,
+            FullSymbolListEntry::Synthesized => 11,
+            FullSymbolListEntry::EndAddress => 12,
+        }
+    }
+
+    /// The size of the function, if known from the symbol table or the stub.
+    fn size(&self) -> Option<u32> {
+        match self {
+            FullSymbolListEntry::Symbol(symbol, _) if symbol.size() != 0 => {
+                u32::try_from(symbol.size()).ok()
+            }
+            FullSymbolListEntry::Stub(stub) => Some(stub.size),
+            FullSymbolListEntry::Additional(symbol) => symbol.size,
+            _ => None,
+        }
+    }
+
+    fn source(&self) -> Option<SymbolSource> {
+        match self {
+            FullSymbolListEntry::Synthesized => Some(SymbolSource::Synthesized),
+            FullSymbolListEntry::Stub(_) => Some(SymbolSource::Stub),
+            FullSymbolListEntry::Additional(symbol) => Some(symbol.source),
+            FullSymbolListEntry::Symbol(_, source) => Some(*source),
+            FullSymbolListEntry::Export(_) => Some(SymbolSource::Export),
+            FullSymbolListEntry::EndAddress => None,
+        }
+    }
 }
 
 /// Return a Vec that contains address -> symbol name entries.
 /// The address is relative to the address of the __TEXT segment (if present).
-/// We discard the symbol "size"; the address is where the symbol starts.
+/// If the symbol table has symbol sizes, they are used to determine where functions end.
 pub fn get_symbolication_result_for_addresses_from_object<'a: 'b, 'b, T, R>(
     addresses: &[u32],
     object_file: &'b T,
     function_start_addresses: Option<&[u32]>,
     function_end_addresses: Option<&[u32]>,
+    stub_symbols: &[StubSymbol],
+    additional_symbols: &[AdditionalSymbol],
+    attribute_split_functions_to_parent: bool,
 ) -> R
 where
     T: object::Object<'a, 'b>,
@@ -512,6 +1194,7 @@ where
     let mut entries: Vec<_> = Vec::new();
 
     let base_address = relative_address_base(object_file);
+    let address_mask = function_address_mask(object_file);
 
     // Add entries in the order "best to worst".
 
@@ -521,17 +1204,35 @@ where
     entries.extend(
         object_file
             .symbols()
-            .chain(object_file.dynamic_symbols())
-            .filter(|symbol| symbol.kind() == SymbolKind::Text)
+            .filter(is_function_symbol)
+            .map(|symbol| {
+                (
+                    ((symbol.address() & address_mask) - base_address) as u32,
+                    FullSymbolListEntry::Symbol(symbol, SymbolSource::SymbolTable),
+                )
+            }),
+    );
+    entries.extend(
+        object_file
+            .dynamic_symbols()
+            .filter(is_function_symbol)
             .map(|symbol| {
                 (
-                    (symbol.address() - base_address) as u32,
-                    FullSymbolListEntry::Symbol(symbol),
+                    ((symbol.address() & address_mask) - base_address) as u32,
+                    FullSymbolListEntry::Symbol(symbol, SymbolSource::DynamicSymbolTable),
                 )
             }),
     );
 
-    // 3. Exports (only used by exe / dll objects)
+    // 3. Additional symbols, e.g. from a separate debug file, or from debug info
+    //    if the symbol table is stripped
+    entries.extend(
+        additional_symbols
+            .iter()
+            .map(|symbol| (symbol.address, FullSymbolListEntry::Additional(symbol))),
+    );
+
+    // 4. Exports (only used by exe / dll objects)
     if let Ok(exports) = object_file.exports() {
         for export in exports {
             entries.push((
@@ -541,7 +1242,14 @@ where
         }
     }
 
-    // 4. Placeholder symbols based on function start addresses
+    // 5. Synthesized symbols for stubs, such as PLT entries
+    entries.extend(
+        stub_symbols
+            .iter()
+            .map(|stub| (stub.address, FullSymbolListEntry::Stub(stub))),
+    );
+
+    // 6. Placeholder symbols based on function start addresses
     if let Some(function_start_addresses) = function_start_addresses {
         // Use function start addresses with synthesized symbols of the form fun_abcdef
         // as the ultimate fallback.
@@ -555,7 +1263,7 @@ where
         );
     }
 
-    // 5. End addresses from text section ends
+    // 7. End addresses from text section ends
     // These entries serve to "terminate" the last function of each section,
     // so that addresses in the following section are not considered
     // to be part of the last function of that previous section.
@@ -571,7 +1279,7 @@ where
             }),
     );
 
-    // 6. End addresses for known functions ends
+    // 8. End addresses for known functions ends
     // These addresses serve to "terminate" functions from function_start_addresses.
     // They come from .eh_frame or .pdata info, which has the function size.
     if let Some(function_end_addresses) = function_end_addresses {
@@ -583,38 +1291,316 @@ where
     }
 
     // Done.
-    // Now that all entries are added, sort and de-duplicate so that we only
-    // have one entry per address.
-    // If multiple entries for the same address are present, only the first
-    // entry for that address is kept. (That's also why we use a stable sort
-    // here.)
-    // We have added entries in the order best to worst, so we keep the "best"
-    // symbol for each address.
-    entries.sort_by_key(|(address, _)| *address);
-    entries.dedup_by_key(|(address, _)| *address);
+    // Now that all entries are added, sort them by address. Multiple entries for the
+    // same address are sorted by rank, so that the first entry for each address is
+    // the "best" one. The remaining entries at that address are its aliases.
+    // Entries with the same rank stay in the order in which they were added,
+    // which is why we use a stable sort here.
+    entries.sort_by_key(|(address, entry)| (*address, entry.rank()));
+
+    let sections = object_section_infos(object_file);
+
+    let mut symbolication_result = R::for_addresses(addresses);
+    let unique_address_count = entries
+        .windows(2)
+        .filter(|pair| pair[0].0 != pair[1].0)
+        .count()
+        + usize::from(!entries.is_empty());
+    symbolication_result.set_total_symbol_count(unique_address_count as u32);
+
+    for &address in addresses {
+        // Find all entries at the closest address <= `address`.
+        let end_index = entries.partition_point(|&(addr, _)| addr <= address);
+        if end_index == 0 {
+            continue;
+        }
+        let start_addr = entries[end_index - 1].0;
+        let start_index = entries[..end_index].partition_point(|&(addr, _)| addr < start_addr);
+        let (_, entry) = &entries[start_index];
+        let next_entry = entries.get(end_index);
+        let name = match entry.name(start_addr) {
+            Ok(name) => name,
+            // The found entry is an EndAddress entry. This means that `address` falls
+            // in the dead space between known functions, and we consider it to be not found.
+            Err(()) => continue,
+        };
+        // Use the symbol size if we know it, otherwise assume that the function
+        // extends up to the next entry.
+        let function_size = match (entry.size(), next_entry) {
+            (Some(size), _) => size,
+            (None, Some((end_addr, _))) => end_addr - start_addr,
+            (None, None) => continue,
+        };
+        if address - start_addr >= function_size {
+            // `address` is after the end of the function, for example in the padding
+            // between two functions.
+            continue;
+        }
+        let parent_name = match entry {
+            FullSymbolListEntry::Symbol(..) | FullSymbolListEntry::Additional(_)
+                if attribute_split_functions_to_parent =>
+            {
+                split_function_parent_name(&name)
+            }
+            _ => None,
+        };
+        let section = SectionInfo::find(&sections, address);
+        symbolication_result.add_address_symbol(
+            address,
+            AddressSymbolInfo {
+                symbol_address: start_addr,
+                symbol_name: parent_name.unwrap_or(&name),
+                function_size: Some(function_size),
+                is_split_function_part: parent_name.is_some(),
+                source: entry.source().expect("EndAddress entries have no name"),
+                section_name: section.map(|section| section.name.as_str()),
+                is_outside_text: !matches!(section, Some(section) if section.is_executable),
+            },
+        );
+
+        // Placeholder names are not aliases; they only stand in for missing symbols.
+        let alias_names: Vec<_> = entries[start_index + 1..end_index]
+            .iter()
+            .filter(|(_, alias)| !matches!(alias, FullSymbolListEntry::Synthesized))
+            .filter_map(|(_, alias)| alias.name(start_addr).ok())
+            .filter(|alias_name| *alias_name != name)
+            .collect();
+        let mut aliases: Vec<&str> = Vec::new();
+        for alias_name in &alias_names {
+            if !aliases.contains(&alias_name.as_ref()) {
+                aliases.push(alias_name);
+            }
+        }
+        if !aliases.is_empty() {
+            symbolication_result.add_address_symbol_aliases(address, &aliases);
+        }
+    }
+    symbolication_result
+}
+
+/// A symbol for a global, static or thread-local variable, used for queries with
+/// `QuerySymbolKind::Data`.
+pub struct DataSymbol {
+    /// The relative address of the variable.
+    pub address: u32,
+    /// The size of the variable in bytes, if known.
+    pub size: Option<u32>,
+    pub name: String,
+    pub source: SymbolSource,
+}
+
+/// Returns the address at which the initialization image for thread-local variables
+/// starts, for ELF files. The values of ELF TLS symbols are offsets relative to this
+/// address. This is the address of the first TLS section, i.e. `.tdata` or `.tbss`.
+pub fn elf_tls_template_address<'data: 'file, 'file>(
+    object_file: &'file impl object::Object<'data, 'file>,
+) -> Option<u64> {
+    use object::ObjectSection;
+    object_file
+        .sections()
+        .filter(|section| {
+            matches!(section.flags(), object::SectionFlags::Elf { .. })
+                && matches!(
+                    section.kind(),
+                    SectionKind::Tls | SectionKind::UninitializedTls
+                )
+        })
+        .map(|section| section.address())
+        .min()
+}
+
+/// Collect the data and TLS symbols from the symbol table, the dynamic symbol table,
+/// and, for PE files, the exports which point outside of executable sections.
+/// The returned symbols are ordered from best to worst, see `get_data_symbolication_result`.
+pub fn object_data_symbols<'data: 'file, 'file>(
+    object_file: &'file impl object::Object<'data, 'file>,
+) -> Vec<DataSymbol> {
+    let base_address = relative_address_base(object_file);
+    let tls_template_address = elf_tls_template_address(object_file);
+
+    let mut data_symbols = Vec::new();
+    add_data_symbols(
+        &mut data_symbols,
+        object_file.symbols(),
+        SymbolSource::SymbolTable,
+        base_address,
+        tls_template_address,
+    );
+    add_data_symbols(
+        &mut data_symbols,
+        object_file.dynamic_symbols(),
+        SymbolSource::DynamicSymbolTable,
+        base_address,
+        tls_template_address,
+    );
+
+    if let Ok(exports) = object_file.exports() {
+        let sections = object_section_infos(object_file);
+        for export in exports {
+            let address = match export
+                .address()
+                .checked_sub(base_address)
+                .and_then(|address| u32::try_from(address).ok())
+            {
+                Some(address) => address,
+                None => continue,
+            };
+            match SectionInfo::find(&sections, address) {
+                Some(section) if !section.is_executable => {}
+                _ => continue,
+            }
+            if let Ok(name) = std::str::from_utf8(export.name()) {
+                data_symbols.push(DataSymbol {
+                    address,
+                    size: None,
+                    name: name.to_string(),
+                    source: SymbolSource::Export,
+                });
+            }
+        }
+    }
+    data_symbols
+}
+
+fn add_data_symbols<'data>(
+    data_symbols: &mut Vec<DataSymbol>,
+    symbols: impl Iterator<Item = impl object::ObjectSymbol<'data>>,
+    source: SymbolSource,
+    base_address: u64,
+    tls_template_address: Option<u64>,
+) {
+    let mut symbols: Vec<_> = symbols
+        .filter(|symbol| matches!(symbol.kind(), SymbolKind::Data | SymbolKind::Tls))
+        .filter(|symbol| !symbol.is_undefined())
+        .collect();
+    // Prefer global symbols over local symbols, and strong symbols over weak symbols.
+    symbols.sort_by_key(symbol_alias_rank);
+    data_symbols.extend(symbols.into_iter().filter_map(|symbol| {
+        let address = match (symbol.kind(), tls_template_address) {
+            (SymbolKind::Tls, Some(tls_template_address)) => {
+                tls_template_address.checked_add(symbol.address())?
+            }
+            _ => symbol.address(),
+        };
+        Some(DataSymbol {
+            address: u32::try_from(address.checked_sub(base_address)?).ok()?,
+            // Mach-O symbols don't have sizes. For those, the lookup uses the
+            // address of the next symbol.
+            size: match symbol.size() {
+                0 => None,
+                size => Some(u32::try_from(size).ok()?),
+            },
+            name: symbol.name().ok()?.to_string(),
+            source,
+        })
+    }));
+}
+
+/// Make a symbolication result for a query with `QuerySymbolKind::Data` from the
+/// object's data symbols and any `extra_symbols`, for example from debug info.
+pub fn get_data_symbolication_result_from_object<'data: 'file, 'file, R>(
+    object_file: &'file impl object::Object<'data, 'file>,
+    result_kind: SymbolicationResultKind,
+    extra_symbols: Vec<DataSymbol>,
+) -> R
+where
+    R: SymbolicationResult,
+{
+    let mut symbols = object_data_symbols(object_file);
+    symbols.extend(extra_symbols);
+    let sections = object_section_infos(object_file);
+    get_data_symbolication_result(result_kind, symbols, &sections)
+}
+
+/// Make a symbolication result for a query with `QuerySymbolKind::Data`.
+///
+/// `symbols` must be ordered from best to worst; if there are multiple symbols at
+/// the same address, the first one is used and the others are reported as aliases.
+/// Symbols without a size are assumed to extend up to the next symbol or to the end
+/// of their section, whichever comes first.
+pub fn get_data_symbolication_result<R>(
+    result_kind: SymbolicationResultKind,
+    symbols: Vec<DataSymbol>,
+    sections: &[SectionInfo],
+) -> R
+where
+    R: SymbolicationResult,
+{
+    let addresses = match result_kind {
+        SymbolicationResultKind::AllSymbols => {
+            // The compact symbol table keeps the last entry for each address.
+            let map = symbols
+                .into_iter()
+                .rev()
+                .map(|symbol| (symbol.address, symbol.name))
+                .collect();
+            return R::from_full_map(map);
+        }
+        SymbolicationResultKind::SymbolsForAddresses { addresses, .. } => addresses,
+    };
+
+    // Section ends are represented as `None` entries, so that symbols without a size
+    // don't extend into the next section.
+    let mut entries: Vec<(u32, Option<&DataSymbol>)> = symbols
+        .iter()
+        .map(|symbol| (symbol.address, Some(symbol)))
+        .chain(sections.iter().map(|section| (section.end, None)))
+        .collect();
+    entries.sort_by_key(|(address, symbol)| (*address, symbol.is_none()));
 
     let mut symbolication_result = R::for_addresses(addresses);
-    symbolication_result.set_total_symbol_count(entries.len() as u32);
+    let mut symbol_addresses: Vec<u32> = symbols.iter().map(|symbol| symbol.address).collect();
+    symbol_addresses.sort_unstable();
+    symbol_addresses.dedup();
+    symbolication_result.set_total_symbol_count(symbol_addresses.len() as u32);
 
     for &address in addresses {
-        let index = match entries.binary_search_by_key(&address, |&(addr, _)| addr) {
-            Err(0) => continue,
-            Ok(i) => i,
-            Err(i) => i - 1,
+        // Find all entries at the closest address <= `address`.
+        let end_index = entries.partition_point(|&(addr, _)| addr <= address);
+        if end_index == 0 {
+            continue;
+        }
+        let start_addr = entries[end_index - 1].0;
+        let start_index = entries[..end_index].partition_point(|&(addr, _)| addr < start_addr);
+        let symbol = match entries[start_index].1 {
+            Some(symbol) => symbol,
+            // `address` is after the end of a section, and before the first symbol
+            // of the next section.
+            None => continue,
+        };
+        let size = match (symbol.size, entries.get(end_index)) {
+            (Some(size), _) => size,
+            (None, Some((end_addr, _))) => end_addr - start_addr,
+            (None, None) => continue,
         };
-        let (start_addr, entry) = &entries[index];
-        let next_entry = entries.get(index + 1);
-        // Add the symbol.
-        // If the found entry is an EndAddress entry, this means that `address` falls
-        // in the dead space between known functions, and we consider it to be not found.
-        if let (Ok(name), Some((end_addr, _))) = (entry.name(*start_addr), next_entry) {
-            let function_size = end_addr - *start_addr;
-            symbolication_result.add_address_symbol(
-                address,
-                *start_addr,
-                &name,
-                Some(function_size),
-            );
+        if address - start_addr >= size {
+            continue;
+        }
+        let section = SectionInfo::find(sections, address);
+        symbolication_result.add_address_symbol(
+            address,
+            AddressSymbolInfo {
+                symbol_address: start_addr,
+                symbol_name: &symbol.name,
+                function_size: Some(size),
+                is_split_function_part: false,
+                source: symbol.source,
+                section_name: section.map(|section| section.name.as_str()),
+                is_outside_text: !matches!(section, Some(section) if section.is_executable),
+            },
+        );
+
+        let mut aliases: Vec<&str> = Vec::new();
+        for alias in entries[start_index + 1..end_index]
+            .iter()
+            .filter_map(|(_, alias)| *alias)
+        {
+            if alias.name != symbol.name && !aliases.contains(&alias.name.as_str()) {
+                aliases.push(&alias.name);
+            }
+        }
+        if !aliases.is_empty() {
+            symbolication_result.add_address_symbol_aliases(address, &aliases);
         }
     }
     symbolication_result
@@ -909,3 +1895,402 @@ impl<'data, T: ReadRef<'data>> ReadRef<'data> for RangeReadRef<'data, T> {
         self.original_readref.read_bytes_at_until(range, delimiter)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        get_data_symbolication_result, get_symbolication_result_for_addresses_from_object,
+        object_function_symbols, relative_address_base, split_function_parent_name, DataSymbol,
+        SectionInfo, SourceFileChecksum, SpecialPath, SymbolSource, SymbolicationResultKind,
+    };
+    use crate::symbolicate::v5::looked_up_addresses::LookedUpAddresses;
+    use std::convert::TryInto;
+
+    #[test]
+    fn split_function_parents() {
+        assert_eq!(split_function_parent_name("foo.cold"), Some("foo"));
+        assert_eq!(split_function_parent_name("foo.cold.12"), Some("foo"));
+        assert_eq!(split_function_parent_name("foo.part.0"), Some("foo"));
+        assert_eq!(
+            split_function_parent_name("_Z3foov.constprop.0.cold"),
+            Some("_Z3foov.constprop.0")
+        );
+        assert_eq!(split_function_parent_name("_foo.cold.1"), Some("_foo"));
+        assert_eq!(
+            split_function_parent_name("OUTLINED_FUNCTION_42"),
+            Some("OUTLINED_FUNCTION_42")
+        );
+        assert_eq!(
+            split_function_parent_name("_OUTLINED_FUNCTION_0"),
+            Some("_OUTLINED_FUNCTION_0")
+        );
+        assert_eq!(split_function_parent_name("foo.constprop.0"), None);
+        assert_eq!(split_function_parent_name("cold"), None);
+        assert_eq!(split_function_parent_name("foo_cold"), None);
+        assert_eq!(split_function_parent_name("OUTLINED_FUNCTION_"), None);
+    }
+
+    #[test]
+    fn source_file_checksums() {
+        fn from_hex(hex: &str) -> Vec<u8> {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect()
+        }
+        let contents = b"int main() {}\n";
+        let md5 = SourceFileChecksum::Md5(
+            from_hex("f881137628fc8dd673b761eb7a1e2432")
+                .try_into()
+                .unwrap(),
+        );
+        let sha1 = SourceFileChecksum::Sha1(
+            from_hex("4f2310e53f6278e813db033183e3d08420721956")
+                .try_into()
+                .unwrap(),
+        );
+        let sha256 = SourceFileChecksum::Sha256(
+            from_hex("bc8bb8e433bf65214540115414c821c904b2a30d60a3ac0424bf9b77a00024b7")
+                .try_into()
+                .unwrap(),
+        );
+        for checksum in &[&md5, &sha1, &sha256] {
+            assert!(checksum.matches(contents));
+            assert!(!checksum.matches(b"int main() { return 1; }\n"));
+        }
+        assert_eq!(md5.to_string(), "md5:f881137628fc8dd673b761eb7a1e2432");
+        assert_eq!(
+            sha1.to_string(),
+            "sha1:4f2310e53f6278e813db033183e3d08420721956"
+        );
+    }
+
+    #[test]
+    fn data_symbols() {
+        let symbol = |address, size, name: &str| DataSymbol {
+            address,
+            size,
+            name: name.to_string(),
+            source: SymbolSource::SymbolTable,
+        };
+        let symbols = vec![
+            symbol(0x1000, Some(0x10), "table"),
+            symbol(0x1000, Some(0x10), "table_alias"),
+            symbol(0x1020, None, "unsized"),
+            symbol(0x2000, None, "last"),
+        ];
+        let sections = vec![
+            SectionInfo {
+                start: 0x1000,
+                end: 0x1040,
+                name: ".data".to_string(),
+                is_executable: false,
+            },
+            SectionInfo {
+                start: 0x2000,
+                end: 0x2008,
+                name: ".bss".to_string(),
+                is_executable: false,
+            },
+        ];
+        let addresses = [0x1008, 0x1018, 0x1030, 0x1040, 0x2004, 0x2008];
+        let result: LookedUpAddresses = get_data_symbolication_result(
+            SymbolicationResultKind::SymbolsForAddresses {
+                addresses: &addresses,
+                with_debug_info: false,
+            },
+            symbols,
+            &sections,
+        );
+        assert_eq!(result.symbol_count, 3);
+        let results = &result.address_results;
+
+        let table = results[&0x1008].as_ref().unwrap();
+        assert_eq!(table.symbol_name, "table");
+        assert_eq!(table.symbol_aliases, vec!["table_alias".to_string()]);
+        assert_eq!(table.function_size, Some(0x10));
+        assert_eq!(table.section_name.as_deref(), Some(".data"));
+        assert!(table.is_outside_text);
+
+        // After the end of "table", before "unsized".
+        assert!(results[&0x1018].is_none());
+
+        // "unsized" extends up to the end of its section.
+        let unsized_symbol = results[&0x1030].as_ref().unwrap();
+        assert_eq!(unsized_symbol.symbol_name, "unsized");
+        assert_eq!(unsized_symbol.function_size, Some(0x20));
+        assert!(results[&0x1040].is_none());
+
+        assert_eq!(results[&0x2004].as_ref().unwrap().symbol_name, "last");
+        assert!(results[&0x2008].is_none());
+    }
+
+    /// Builds a little-endian ELF64 file with a 0x100 byte .text section at 0x1000
+    /// and a symbol table with the given `(name, address, size, st_info)` symbols.
+    fn elf_with_function_symbols(symbols: &[(&str, u64, u64, u8)]) -> Vec<u8> {
+        let text_offset = 0x40u64;
+        let text_size = 0x100u64;
+
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; 24];
+        for &(name, address, size, st_info) in symbols {
+            symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+            symtab.push(st_info);
+            symtab.push(0); // st_other
+            symtab.extend_from_slice(&1u16.to_le_bytes()); // st_shndx: .text
+            symtab.extend_from_slice(&address.to_le_bytes());
+            symtab.extend_from_slice(&size.to_le_bytes());
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+        let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+        // sh_info of .symtab is the index of the first non-local symbol.
+        let first_global = 1 + symbols
+            .iter()
+            .take_while(|(_, _, _, st_info)| st_info >> 4 == object::elf::STB_LOCAL)
+            .count() as u32;
+
+        let symtab_offset = text_offset + text_size;
+        let strtab_offset = symtab_offset + symtab.len() as u64;
+        let shstrtab_offset = strtab_offset + strtab.len() as u64;
+        let shoff = (shstrtab_offset + shstrtab.len() as u64 + 7) & !7;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(b"\x7fELF\x02\x01\x01");
+        data.resize(16, 0);
+        data.extend_from_slice(&3u16.to_le_bytes()); // e_type: ET_DYN
+        data.extend_from_slice(&62u16.to_le_bytes()); // e_machine: x86_64
+        data.extend_from_slice(&1u32.to_le_bytes()); // e_version
+        data.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+        data.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+        data.extend_from_slice(&shoff.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        for value in [64u16, 56, 0, 64, 5, 4] {
+            // e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize((text_offset + text_size) as usize, 0);
+        data.extend_from_slice(&symtab);
+        data.extend_from_slice(&strtab);
+        data.extend_from_slice(shstrtab);
+        data.resize(shoff as usize, 0);
+
+        // (name, type, flags, address, offset, size, link, info, entsize)
+        let sections = [
+            (0u32, 0u32, 0u64, 0u64, 0u64, 0u64, 0u32, 0u32, 0u64),
+            (1, 1, 6, 0x1000, text_offset, text_size, 0, 0, 0),
+            (
+                7,
+                2,
+                0,
+                0,
+                symtab_offset,
+                symtab.len() as u64,
+                3,
+                first_global,
+                24,
+            ),
+            (15, 3, 0, 0, strtab_offset, strtab.len() as u64, 0, 0, 0),
+            (23, 3, 0, 0, shstrtab_offset, shstrtab.len() as u64, 0, 0, 0),
+        ];
+        for (name, sh_type, flags, address, offset, size, link, info, entsize) in sections {
+            data.extend_from_slice(&name.to_le_bytes());
+            data.extend_from_slice(&sh_type.to_le_bytes());
+            data.extend_from_slice(&flags.to_le_bytes());
+            data.extend_from_slice(&address.to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&size.to_le_bytes());
+            data.extend_from_slice(&link.to_le_bytes());
+            data.extend_from_slice(&info.to_le_bytes());
+            data.extend_from_slice(&1u64.to_le_bytes()); // sh_addralign
+            data.extend_from_slice(&entsize.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn function_symbols() {
+        use object::elf::{STB_GLOBAL, STB_LOCAL, STB_WEAK, STT_FUNC, STT_GNU_IFUNC};
+        let info = |bind: u8, kind: u8| bind << 4 | kind;
+        let data = elf_with_function_symbols(&[
+            ("bar_local", 0x1020, 0x10, info(STB_LOCAL, STT_FUNC)),
+            ("baz_local", 0x1040, 0x10, info(STB_LOCAL, STT_FUNC)),
+            ("foo", 0x1000, 0x10, info(STB_GLOBAL, STT_FUNC)),
+            ("bar_ifunc", 0x1020, 0x10, info(STB_GLOBAL, STT_GNU_IFUNC)),
+            ("bar_weak", 0x1020, 0x10, info(STB_WEAK, STT_FUNC)),
+            ("bar", 0x1020, 0x10, info(STB_GLOBAL, STT_FUNC)),
+            ("baz_ifunc", 0x1040, 0x10, info(STB_GLOBAL, STT_GNU_IFUNC)),
+        ]);
+        let file = object::File::parse(&data[..]).unwrap();
+        let addresses = [0x1004, 0x1018, 0x1024, 0x1048];
+        let result: LookedUpAddresses = get_symbolication_result_for_addresses_from_object(
+            &addresses,
+            &file,
+            None,
+            None,
+            &[],
+            &[],
+            false,
+        );
+        let results = &result.address_results;
+
+        let foo = results[&0x1004].as_ref().unwrap();
+        assert_eq!(foo.symbol_name, "foo");
+        assert_eq!(foo.function_size, Some(0x10));
+        assert!(foo.symbol_aliases.is_empty());
+
+        // After the end of "foo", in the padding before "bar".
+        assert!(results[&0x1018].is_none());
+
+        // Regular functions win over weak symbols, weak symbols over local
+        // symbols, and local symbols over IFUNC resolvers.
+        let bar = results[&0x1024].as_ref().unwrap();
+        assert_eq!(bar.symbol_name, "bar");
+        assert_eq!(bar.symbol_address, 0x1020);
+        assert_eq!(bar.symbol_source, SymbolSource::SymbolTable);
+        assert_eq!(
+            bar.symbol_aliases,
+            vec![
+                "bar_weak".to_string(),
+                "bar_local".to_string(),
+                "bar_ifunc".to_string()
+            ]
+        );
+        let baz = results[&0x1048].as_ref().unwrap();
+        assert_eq!(baz.symbol_name, "baz_local");
+        assert_eq!(baz.symbol_aliases, vec!["baz_ifunc".to_string()]);
+    }
+
+    #[test]
+    fn debug_file_function_symbols() {
+        use object::elf::{STB_GLOBAL, STB_LOCAL, STT_FUNC};
+        let info = |bind: u8, kind: u8| bind << 4 | kind;
+        // The binary only kept its exported function; the debug file has all of them.
+        let binary_data =
+            elf_with_function_symbols(&[("foo", 0x1000, 0x10, info(STB_GLOBAL, STT_FUNC))]);
+        let debug_data = elf_with_function_symbols(&[
+            ("bar_local", 0x1020, 0x10, info(STB_LOCAL, STT_FUNC)),
+            ("foo", 0x1000, 0x10, info(STB_GLOBAL, STT_FUNC)),
+            ("baz", 0x1040, 0x10, info(STB_GLOBAL, STT_FUNC)),
+        ]);
+        let binary = object::File::parse(&binary_data[..]).unwrap();
+        let debug_file = object::File::parse(&debug_data[..]).unwrap();
+        let additional_symbols =
+            object_function_symbols(&debug_file, relative_address_base(&binary));
+        let addresses = [0x1004, 0x1024, 0x1048];
+        let result: LookedUpAddresses = get_symbolication_result_for_addresses_from_object(
+            &addresses,
+            &binary,
+            None,
+            None,
+            &[],
+            &additional_symbols,
+            false,
+        );
+        let results = &result.address_results;
+
+        let foo = results[&0x1004].as_ref().unwrap();
+        assert_eq!(foo.symbol_name, "foo");
+        assert_eq!(foo.symbol_source, SymbolSource::SymbolTable);
+        assert!(foo.symbol_aliases.is_empty());
+        let bar = results[&0x1024].as_ref().unwrap();
+        assert_eq!(bar.symbol_name, "bar_local");
+        assert_eq!(bar.symbol_address, 0x1020);
+        assert_eq!(bar.function_size, Some(0x10));
+        let baz = results[&0x1048].as_ref().unwrap();
+        assert_eq!(baz.symbol_name, "baz");
+        assert_eq!(baz.symbol_address, 0x1040);
+    }
+
+    #[test]
+    fn special_paths() {
+        assert_eq!(
+            SpecialPath::parse(
+                "git:github.com/rust-lang/rust:library/core/src/ptr/mod.rs:0123abcd"
+            ),
+            Some(SpecialPath::Repository {
+                vcs: "git".to_string(),
+                repo: "github.com/rust-lang/rust".to_string(),
+                path: "library/core/src/ptr/mod.rs".to_string(),
+                rev: "0123abcd".to_string(),
+            })
+        );
+        assert_eq!(
+            SpecialPath::parse("p4:perforce.example.com:1666:depot/main/foo.cpp:42"),
+            Some(SpecialPath::Repository {
+                vcs: "p4".to_string(),
+                repo: "perforce.example.com:1666".to_string(),
+                path: "depot/main/foo.cpp".to_string(),
+                rev: "42".to_string(),
+            })
+        );
+        assert_eq!(
+            SpecialPath::parse("cargo:github.com-1ecc6299db9ec823:tokio-util-0.7.3:src/lib.rs"),
+            Some(SpecialPath::Cargo {
+                registry: "github.com-1ecc6299db9ec823".to_string(),
+                crate_name: "tokio-util".to_string(),
+                version: "0.7.3".to_string(),
+                path: "src/lib.rs".to_string(),
+            })
+        );
+        assert_eq!(
+            SpecialPath::parse("cargo:github.com-1ecc6299db9ec823:tokio-1.0.0-rc.1:src/lib.rs"),
+            Some(SpecialPath::Cargo {
+                registry: "github.com-1ecc6299db9ec823".to_string(),
+                crate_name: "tokio".to_string(),
+                version: "1.0.0-rc.1".to_string(),
+                path: "src/lib.rs".to_string(),
+            })
+        );
+        assert_eq!(
+            SpecialPath::parse("cargo:github.com-1ecc6299db9ec823:sha-1-0.10.0:src/lib.rs"),
+            Some(SpecialPath::Cargo {
+                registry: "github.com-1ecc6299db9ec823".to_string(),
+                crate_name: "sha-1".to_string(),
+                version: "0.10.0".to_string(),
+                path: "src/lib.rs".to_string(),
+            })
+        );
+        assert_eq!(
+            SpecialPath::parse("cargo:github.com-1ecc6299db9ec823:tokio-latest:src/lib.rs"),
+            None
+        );
+        assert_eq!(
+            SpecialPath::parse("s3:gecko-generated-sources:a5d3747707d6877b0e5cb0a364e3cb9fea8aa4feb6ead138952ab2d43cb33a9f/ipc/ipdl/PContentChild.cpp:"),
+            Some(SpecialPath::S3 {
+                bucket: "gecko-generated-sources".to_string(),
+                digest_and_path: "a5d3747707d6877b0e5cb0a364e3cb9fea8aa4feb6ead138952ab2d43cb33a9f/ipc/ipdl/PContentChild.cpp".to_string(),
+            })
+        );
+        assert_eq!(SpecialPath::parse("/home/me/src/main.c"), None);
+        assert_eq!(SpecialPath::parse(r"C:\src\main.c"), None);
+        assert_eq!(
+            SpecialPath::parse("https://example.com/main.c"),
+            Some(SpecialPath::Url("https://example.com/main.c".to_string()))
+        );
+        assert_eq!(SpecialPath::parse("https:example.com/main.c"), None);
+        assert_eq!(SpecialPath::parse("git:github.com/example/app"), None);
+        assert_eq!(
+            SpecialPath::parse("git:github.com/example/app:../../../etc/passwd:rev"),
+            None
+        );
+        assert_eq!(
+            SpecialPath::parse("git:github.com/example/app:src/../../main.c:rev"),
+            None
+        );
+        assert_eq!(
+            SpecialPath::parse("git:github.com/example/app:/etc/passwd:rev"),
+            None
+        );
+        assert_eq!(SpecialPath::parse(r"hg:../..:src\main.c:rev"), None);
+        assert_eq!(
+            SpecialPath::parse("cargo:github.com-1ecc6299db9ec823:../tokio-1.0.0:src/lib.rs"),
+            None
+        );
+        assert_eq!(
+            SpecialPath::parse("cargo:github.com-1ecc6299db9ec823:tokio-1.0.0:C:/src/lib.rs"),
+            None
+        );
+        assert_eq!(SpecialPath::parse("s3:bucket:/etc/passwd:"), None);
+    }
+}
@@ -1,8 +1,12 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 
+use crate::path_mapper::PathMappingRule;
 use crate::shared::{
-    AddressDebugInfo, FileAndPathHelper, FileAndPathHelperError, FileContentsWrapper, FileLocation,
-    InlineStackFrame, SymbolicationQuery, SymbolicationResultKind,
+    AddressDebugInfo, AddressSymbolInfo, FileAndPathHelper, FileAndPathHelperError,
+    FileContentsWrapper, FileLocation, FilePath, InlineStackFrame, QuerySymbolKind,
+    ResolvedSpecialPath, SourceFileChecksum, SpecialPath, SymbolicationQuery,
+    SymbolicationResultKind,
 };
 use crate::{to_debug_id, GetSymbolsError, SymbolicationResult};
 use serde_json::json;
@@ -13,6 +17,10 @@ mod response_json;
 pub struct FramesForSingleAddress {
     pub address: u32,
     pub frames: Option<Vec<InlineStackFrame>>,
+    /// The source code which is embedded in the debug info, keyed by mapped path.
+    pub embedded_sources: HashMap<String, String>,
+    /// The file which the symbols came from.
+    pub symbol_file_location: Option<FileLocation>,
 }
 
 impl SymbolicationResult for FramesForSingleAddress {
@@ -28,25 +36,36 @@ impl SymbolicationResult for FramesForSingleAddress {
         FramesForSingleAddress {
             address: addresses[0],
             frames: None,
+            embedded_sources: HashMap::new(),
+            symbol_file_location: None,
         }
     }
 
-    fn add_address_symbol(
-        &mut self,
-        address: u32,
-        _symbol_address: u32,
-        _symbol_name: &str,
-        _function_size: Option<u32>,
-    ) {
+    fn add_address_symbol(&mut self, address: u32, _symbol: AddressSymbolInfo) {
         assert!(address == self.address, "Unexpected address");
     }
 
+    fn add_address_symbol_aliases(&mut self, _address: u32, _aliases: &[&str]) {}
+
     fn add_address_debug_info(&mut self, address: u32, info: AddressDebugInfo) {
         assert!(address == self.address, "Unexpected address");
         self.frames = Some(info.frames);
     }
 
     fn set_total_symbol_count(&mut self, _total_symbol_count: u32) {}
+
+    fn wants_embedded_source() -> bool {
+        true
+    }
+
+    fn add_embedded_source(&mut self, file_path: FilePath, source: String) {
+        self.embedded_sources
+            .insert(file_path.into_mapped_path(), source);
+    }
+
+    fn set_symbol_file_location(&mut self, location: &FileLocation) {
+        self.symbol_file_location = Some(location.clone());
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -66,6 +85,12 @@ enum SourceError {
     #[error("The symbol file came from a non-local origin, so we cannot treat file paths in it as local.")]
     NonLocalSymbols,
 
+    #[error("Path mapping rules in the request can't map to local paths")]
+    LocalPathMappingInRequest,
+
+    #[error("The file doesn't match the checksum {0} from the debug info")]
+    ChecksumMismatch(String),
+
     #[error("An error occurred when reading the file: {0}")]
     FileAndPathHelperError(#[from] FileAndPathHelperError),
 }
@@ -89,58 +114,489 @@ async fn query_api_fallible_json<'h>(
     Ok(serde_json::to_string(&response)?)
 }
 
-async fn query_api<'h>(
-    request: &request_json::Request,
+/// Like `query_api_json`, but returns the source for all frames of the address,
+/// i.e. for the function and for all functions which were inlined at the address.
+pub async fn query_batch_api_json<'h>(
+    request_json: &str,
     helper: &'h impl FileAndPathHelper<'h>,
-) -> Result<response_json::Response, SourceError> {
-    let request_json::Request {
-        debug_id,
-        debug_name,
-        module_offset,
-        file: requested_file,
-    } = &request;
+) -> String {
+    match query_batch_api_fallible_json(request_json, helper).await {
+        Ok(response_json) => response_json,
+        Err(err) => json!({ "error": err.to_string() }).to_string(),
+    }
+}
+
+async fn query_batch_api_fallible_json<'h>(
+    request_json: &str,
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<String, SourceError> {
+    let request: request_json::BatchRequest = serde_json::from_str(request_json)?;
+    let response = query_batch_api(&request, helper).await?;
+    Ok(serde_json::to_string(&response)?)
+}
+
+/// Looks up the address, to see which file paths we are allowed to read.
+async fn frames_for_address<'h>(
+    debug_name: &str,
+    debug_id: &str,
+    module_offset: u32,
+    path_mappings: &[PathMappingRule],
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<FramesForSingleAddress, SourceError> {
+    // Otherwise the request could map any path from the debug info to any local file.
+    if path_mappings.iter().any(|rule| rule.maps_to_local_path()) {
+        return Err(SourceError::LocalPathMappingInRequest);
+    }
 
-    // Look up the address to see which file paths we are allowed to read.
-    let symbol_result: FramesForSingleAddress = crate::get_symbolication_result(
+    Ok(crate::get_symbolication_result(
         SymbolicationQuery {
             debug_name,
             debug_id: to_debug_id(debug_id)?,
             result_kind: SymbolicationResultKind::SymbolsForAddresses {
-                addresses: &[*module_offset],
+                addresses: &[module_offset],
                 with_debug_info: true,
             },
+            attribute_split_functions_to_parent: false,
+            symbol_kind: QuerySymbolKind::Functions,
+            path_mapping_rules: path_mappings,
         },
         helper,
     )
-    .await?;
+    .await?)
+}
+
+/// Returns the mapped paths of the frames at the address, mapped without the path
+/// mapping rules from the request, or `None` if the request has no rules.
+///
+/// Only these paths may be passed to `FileAndPathHelper::resolve_special_path`:
+/// a rule from the request could otherwise turn any path from the debug info into
+/// a special path of the client's choosing.
+async fn paths_without_request_rules<'h>(
+    debug_name: &str,
+    debug_id: &str,
+    module_offset: u32,
+    path_mappings: &[PathMappingRule],
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<Option<HashSet<String>>, SourceError> {
+    if path_mappings.is_empty() {
+        return Ok(None);
+    }
+    let symbol_result =
+        frames_for_address(debug_name, debug_id, module_offset, &[], helper).await?;
+    Ok(Some(
+        symbol_result
+            .frames
+            .into_iter()
+            .flatten()
+            .filter_map(|frame| Some(frame.file_path?.into_mapped_path()))
+            .collect(),
+    ))
+}
+
+async fn query_api<'h>(
+    request: &request_json::Request,
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<response_json::Response, SourceError> {
+    let request_json::Request {
+        debug_id,
+        debug_name,
+        module_offset,
+        file: requested_file,
+        reject_checksum_mismatch,
+        path_mappings,
+        start_line,
+        end_line,
+    } = &request;
+
+    let symbol_result =
+        frames_for_address(debug_name, debug_id, *module_offset, path_mappings, helper).await?;
+    let trusted_paths =
+        paths_without_request_rules(debug_name, debug_id, *module_offset, path_mappings, helper)
+            .await?;
 
     // Find the FilePath whose mapped path matches the requested file. This gives us the raw path.
     // This is where we check that the requested file path is permissible.
-    let file_path = symbol_result
+    let (file_path, file_checksum) = symbol_result
         .frames
         .ok_or(SourceError::NoDebugInfo)?
         .into_iter()
-        .filter_map(|frame| frame.file_path)
-        .find(|file_path| *file_path.mapped_path() == *requested_file)
+        .filter_map(|frame| Some((frame.file_path?, frame.file_checksum)))
+        .find(|(file_path, _)| *file_path.mapped_path() == *requested_file)
         .ok_or(SourceError::InvalidPath)?;
 
-    // One last verification step: Make sure that there's actually a local path for this
-    // source file. We will only have a local path if the path was referred to by a local
-    // symbol file.
-    let local_path = file_path
-        .into_local_path()
-        .ok_or(SourceError::NonLocalSymbols)?;
-
-    // If we got here, it means that the file access is allowed. Read the file.
-    let file_contents = helper.open_file(&FileLocation::Path(local_path)).await?;
-    let file_contents = FileContentsWrapper::new(file_contents);
-    let file_contents = file_contents.read_entire_data()?;
-    let source = String::from_utf8_lossy(file_contents).to_string();
+    let source_file = read_source_file(
+        file_path,
+        file_checksum,
+        &symbol_result.embedded_sources,
+        *reject_checksum_mismatch,
+        trusted_paths.as_ref(),
+        helper,
+    )
+    .await?;
+    let line_range = match (start_line, end_line) {
+        (None, None) => None,
+        _ => Some((start_line.unwrap_or(1), end_line.unwrap_or(u32::MAX))),
+    };
+    let (source, line_range) = slice_lines(source_file.source, line_range);
 
     Ok(response_json::Response {
-        symbols_last_modified: None,
-        source_last_modified: None,
+        symbols_last_modified: symbol_result
+            .symbol_file_location
+            .as_ref()
+            .and_then(|location| helper.get_file_modification_time(location))
+            .map(iso_8601_date_time),
+        source_last_modified: source_file.last_modified.map(iso_8601_date_time),
         file: requested_file.to_string(),
         source,
+        start_line: line_range.map(|(start, _)| start),
+        end_line: line_range.map(|(_, end)| end),
+        file_checksum: source_file
+            .file_checksum
+            .map(|checksum| checksum.to_string()),
+        checksum_matches: source_file.checksum_matches,
+    })
+}
+
+async fn query_batch_api<'h>(
+    request: &request_json::BatchRequest,
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<response_json::BatchResponse, SourceError> {
+    let request_json::BatchRequest {
+        debug_id,
+        debug_name,
+        module_offset,
+        reject_checksum_mismatch,
+        path_mappings,
+        context_lines,
+    } = &request;
+
+    let symbol_result =
+        frames_for_address(debug_name, debug_id, *module_offset, path_mappings, helper).await?;
+    let frames = symbol_result.frames.ok_or(SourceError::NoDebugInfo)?;
+    let trusted_paths =
+        paths_without_request_rules(debug_name, debug_id, *module_offset, path_mappings, helper)
+            .await?;
+
+    // The same file can be used by multiple frames, so only read it once.
+    let mut source_files: HashMap<String, Result<SourceFile, String>> = HashMap::new();
+    let mut frame_sources = Vec::new();
+    for frame in frames {
+        let file_path = match frame.file_path {
+            Some(file_path) => file_path,
+            None => {
+                frame_sources.push(response_json::FrameSource {
+                    file: None,
+                    line: frame.line_number,
+                    error: Some("The debug info has no file for this frame".to_string()),
+                    ..Default::default()
+                });
+                continue;
+            }
+        };
+        let file = file_path.mapped_path().into_owned();
+        if !source_files.contains_key(&file) {
+            let source_file = read_source_file(
+                file_path,
+                frame.file_checksum,
+                &symbol_result.embedded_sources,
+                *reject_checksum_mismatch,
+                trusted_paths.as_ref(),
+                helper,
+            )
+            .await
+            .map_err(|err| err.to_string());
+            source_files.insert(file.clone(), source_file);
+        }
+        let frame_source = match &source_files[&file] {
+            Ok(source_file) => {
+                let line_range = match (context_lines, frame.line_number) {
+                    (Some(context_lines), Some(line)) => Some((
+                        line.saturating_sub(*context_lines),
+                        line.saturating_add(*context_lines),
+                    )),
+                    _ => None,
+                };
+                let (source, line_range) = slice_lines(source_file.source.clone(), line_range);
+                response_json::FrameSource {
+                    file: Some(file),
+                    line: frame.line_number,
+                    source_last_modified: source_file.last_modified.map(iso_8601_date_time),
+                    source: Some(source),
+                    start_line: line_range.map(|(start, _)| start),
+                    end_line: line_range.map(|(_, end)| end),
+                    file_checksum: source_file
+                        .file_checksum
+                        .as_ref()
+                        .map(|checksum| checksum.to_string()),
+                    checksum_matches: source_file.checksum_matches,
+                    error: None,
+                }
+            }
+            Err(err) => response_json::FrameSource {
+                file: Some(file),
+                line: frame.line_number,
+                error: Some(err.clone()),
+                ..Default::default()
+            },
+        };
+        frame_sources.push(frame_source);
+    }
+
+    Ok(response_json::BatchResponse {
+        symbols_last_modified: symbol_result
+            .symbol_file_location
+            .as_ref()
+            .and_then(|location| helper.get_file_modification_time(location))
+            .map(iso_8601_date_time),
+        frames: frame_sources,
+    })
+}
+
+/// A source file which was read for a frame.
+struct SourceFile {
+    source: String,
+    /// In seconds since the Unix epoch. Not known for embedded sources.
+    last_modified: Option<u64>,
+    file_checksum: Option<SourceFileChecksum>,
+    checksum_matches: Option<bool>,
+}
+
+/// Reads the source file at `file_path`, which needs to come from the frames of
+/// the looked-up address. If `trusted_paths` is set, only these paths are passed
+/// to `FileAndPathHelper::resolve_special_path`.
+async fn read_source_file<'h>(
+    file_path: FilePath,
+    file_checksum: Option<SourceFileChecksum>,
+    embedded_sources: &HashMap<String, String>,
+    reject_checksum_mismatch: bool,
+    trusted_paths: Option<&HashSet<String>>,
+    helper: &'h impl FileAndPathHelper<'h>,
+) -> Result<SourceFile, SourceError> {
+    let requested_file = file_path.mapped_path().into_owned();
+
+    // Prefer source code which is embedded in the debug info. It is exactly what was
+    // compiled, and it doesn't need a local file, so it also works for symbol files
+    // which were downloaded from a symbol server.
+    let local_file_contents;
+    let resolved_contents;
+    let mut last_modified = None;
+    let file_contents = match embedded_sources.get(&requested_file) {
+        Some(source) => source.as_bytes(),
+        None => {
+            // One last verification step: Make sure that there's actually a local path for this
+            // source file. We will only have a local path if the path was referred to by a local
+            // symbol file.
+            let local_file = match file_path.into_local_path() {
+                // If we got here, it means that the file access is allowed. Read the file.
+                Some(local_path) => {
+                    let location = FileLocation::Path(local_path);
+                    helper
+                        .open_file(&location)
+                        .await
+                        .map(|file_contents| (file_contents, location))
+                        .map_err(SourceError::from)
+                }
+                None => Err(SourceError::NonLocalSymbols),
+            };
+            match local_file {
+                Ok((file_contents, location)) => {
+                    last_modified = helper.get_file_modification_time(&location);
+                    local_file_contents = FileContentsWrapper::new(file_contents);
+                    local_file_contents.read_entire_data()?
+                }
+                Err(err) => {
+                    // If the requested file has a special path, the helper may know where
+                    // to find it, e.g. in a local checkout of the repository.
+                    let is_trusted = match trusted_paths {
+                        Some(paths) => paths.contains(&requested_file),
+                        None => true,
+                    };
+                    let resolved = match SpecialPath::parse(&requested_file) {
+                        Some(special_path) if is_trusted => {
+                            helper.resolve_special_path(&special_path)?
+                        }
+                        _ => None,
+                    };
+                    match resolved {
+                        Some(ResolvedSpecialPath::File(location)) => {
+                            let file_contents = helper.open_file(&location).await?;
+                            last_modified = helper.get_file_modification_time(&location);
+                            local_file_contents = FileContentsWrapper::new(file_contents);
+                            local_file_contents.read_entire_data()?
+                        }
+                        Some(ResolvedSpecialPath::Contents(contents)) => {
+                            resolved_contents = contents;
+                            &resolved_contents
+                        }
+                        None => return Err(err),
+                    }
+                }
+            }
+        }
+    };
+
+    // If the debug info has a checksum for the file, check that the file hasn't
+    // been modified since it was compiled. Otherwise the line numbers may be off.
+    let checksum_matches = file_checksum
+        .as_ref()
+        .map(|checksum| checksum.matches(file_contents));
+    if let (Some(checksum), Some(false)) = (&file_checksum, checksum_matches) {
+        if reject_checksum_mismatch {
+            return Err(SourceError::ChecksumMismatch(checksum.to_string()));
+        }
+    }
+
+    Ok(SourceFile {
+        source: String::from_utf8_lossy(file_contents).to_string(),
+        last_modified,
+        file_checksum,
+        checksum_matches,
     })
 }
+
+/// Returns the lines `start..=end` of `source`, 1-based, and the range of lines
+/// which was returned. The range is clamped to the lines which exist. Returns the
+/// entire source and `None` if `line_range` is `None`, and an empty string and
+/// `None` if none of the requested lines exist.
+fn slice_lines(source: String, line_range: Option<(u32, u32)>) -> (String, Option<(u32, u32)>) {
+    let (start, end) = match line_range {
+        Some(line_range) => line_range,
+        None => return (source, None),
+    };
+    let start = start.max(1);
+    let line_count = source.split_inclusive('\n').count() as u32;
+    let end = end.min(line_count);
+    if start > end {
+        return (String::new(), None);
+    }
+    let lines: String = source
+        .split_inclusive('\n')
+        .skip(start as usize - 1)
+        .take((end - start + 1) as usize)
+        .collect();
+    (lines, Some((start, end)))
+}
+
+/// Formats a time in seconds since the Unix epoch as an ISO 8601 date and time in
+/// UTC, e.g. "2022-07-19T13:37:00Z".
+fn iso_8601_date_time(seconds_since_epoch: u64) -> String {
+    let days = seconds_since_epoch / 86400;
+    let seconds_of_day = seconds_since_epoch % 86400;
+
+    // Convert the day count to a civil date in the proleptic Gregorian calendar,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{iso_8601_date_time, read_source_file, slice_lines, SourceError};
+    use crate::path_mapper::{PathMapper, PathMappingRule};
+    use crate::shared::{BasePath, SpecialPath};
+    use crate::test_helper::{block_on, TestHelper};
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn request_rules_dont_reach_resolver() {
+        let helper = TestHelper {
+            special_path_contents: Some(b"int main() {}".to_vec()),
+            ..Default::default()
+        };
+        let raw_path = "/builds/worker/main.c";
+        let request_rules: Vec<PathMappingRule> = serde_json::from_str(
+            r#"[{ "regex": ".*", "mapped": "git:example.com/secrets:keys.txt:rev" }]"#,
+        )
+        .unwrap();
+        let base_path = BasePath::NoLocalSourceFileAccess;
+        let forged_path = PathMapper::<()>::new(&base_path, &request_rules).map_path(raw_path);
+        let trusted_paths: HashSet<String> =
+            std::iter::once(PathMapper::<()>::new(&base_path, &[]).map_path(raw_path))
+                .map(|path| path.into_mapped_path())
+                .collect();
+
+        // The path from the request rule is not passed to the helper.
+        let result = block_on(read_source_file(
+            forged_path.clone(),
+            None,
+            &HashMap::new(),
+            false,
+            Some(&trusted_paths),
+            &helper,
+        ));
+        assert!(matches!(result, Err(SourceError::NonLocalSymbols)));
+        assert!(helper.resolved_special_paths.borrow().is_empty());
+
+        // The same path is resolved if it came from the debug info or from helper rules.
+        let result = block_on(read_source_file(
+            forged_path,
+            None,
+            &HashMap::new(),
+            false,
+            None,
+            &helper,
+        ));
+        assert_eq!(result.unwrap().source, "int main() {}");
+        assert_eq!(
+            *helper.resolved_special_paths.borrow(),
+            vec![SpecialPath::Repository {
+                vcs: "git".to_string(),
+                repo: "example.com/secrets".to_string(),
+                path: "keys.txt".to_string(),
+                rev: "rev".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn line_slices() {
+        let source = "one\ntwo\nthree\nfour".to_string();
+        assert_eq!(slice_lines(source.clone(), None), (source.clone(), None));
+        assert_eq!(
+            slice_lines(source.clone(), Some((2, 3))),
+            ("two\nthree\n".to_string(), Some((2, 3)))
+        );
+        assert_eq!(
+            slice_lines(source.clone(), Some((0, 1))),
+            ("one\n".to_string(), Some((1, 1)))
+        );
+        assert_eq!(
+            slice_lines(source.clone(), Some((3, u32::MAX))),
+            ("three\nfour".to_string(), Some((3, 4)))
+        );
+        assert_eq!(
+            slice_lines(source.clone(), Some((6, 8))),
+            ("".to_string(), None)
+        );
+        assert_eq!(slice_lines(source, Some((3, 2))), ("".to_string(), None));
+    }
+
+    #[test]
+    fn date_times() {
+        assert_eq!(iso_8601_date_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso_8601_date_time(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(iso_8601_date_time(1_658_237_820), "2022-07-19T13:37:00Z");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::path_mapper::PathMappingRule;
 use serde::Deserialize;
 use serde_hex::{CompactPfx, SerHex};
 
@@ -23,12 +24,58 @@ pub struct Request {
     /// /symbolicate/v5 returned in its response json for the give
     /// address.
     pub file: String,
+
+    /// Whether to return an error instead of the source if the debug info has
+    /// a checksum for the file and the file's contents don't match it.
+    #[serde(default)]
+    pub reject_checksum_mismatch: bool,
+
+    /// The same path mapping rules as in the /symbolicate/v5 request which
+    /// returned `file`, so that the paths match. Rules which map to local
+    /// paths are not allowed here.
+    #[serde(default)]
+    pub path_mappings: Vec<PathMappingRule>,
+
+    /// If set, only the lines from `start_line` (1-based, inclusive) are returned.
+    pub start_line: Option<u32>,
+
+    /// If set, only the lines up to `end_line` (1-based, inclusive) are returned.
+    pub end_line: Option<u32>,
+}
+
+/// A request for the source of all frames of an address, i.e. of the function
+/// and of all functions which were inlined at the address.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRequest {
+    /// The debugName of the library.
+    pub debug_name: String,
+
+    /// The debugId / "breakpadId" of the library.
+    pub debug_id: String,
+
+    /// An address, as a "0x"-prefixed hex string, interpreted as a
+    /// library-relative offset in bytes.
+    #[serde(with = "SerHex::<CompactPfx>")]
+    pub module_offset: u32,
+
+    /// See `Request::reject_checksum_mismatch`. Applies to each frame separately.
+    #[serde(default)]
+    pub reject_checksum_mismatch: bool,
+
+    /// See `Request::path_mappings`.
+    #[serde(default)]
+    pub path_mappings: Vec<PathMappingRule>,
+
+    /// If set, only this many lines before and after the line of each frame are
+    /// returned, instead of the entire file.
+    pub context_lines: Option<u32>,
 }
 
 #[cfg(test)]
 mod test {
 
-    use super::Request;
+    use super::{BatchRequest, Request};
     use serde_json::Result;
 
     #[test]
@@ -43,6 +90,39 @@ mod test {
 
         let r: Request = serde_json::from_str(data)?;
         assert_eq!(r.module_offset, 30426946);
+        assert!(!r.reject_checksum_mismatch);
+        assert_eq!(r.start_line, None);
+
+        let data = r#"
+        {
+          "debugName": "xul.pdb",
+          "debugId": "A14CAFD390A3E1884C4C44205044422E1",
+          "moduleOffset": "0x1d04742",
+          "file": "hg:hg.mozilla.org/mozilla-central:layout/base/nsLayoutUtils.cpp:1706d4d54ec6",
+          "startLine": 100,
+          "endLine": 140
+        }"#;
+
+        let r: Request = serde_json::from_str(data)?;
+        assert_eq!(r.start_line, Some(100));
+        assert_eq!(r.end_line, Some(140));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_batch() -> Result<()> {
+        let data = r#"
+        {
+          "debugName": "xul.pdb",
+          "debugId": "A14CAFD390A3E1884C4C44205044422E1",
+          "moduleOffset": "0x1d04742",
+          "contextLines": 20
+        }"#;
+
+        let r: BatchRequest = serde_json::from_str(data)?;
+        assert_eq!(r.module_offset, 30426946);
+        assert_eq!(r.context_lines, Some(20));
+        assert!(r.path_mappings.is_empty());
         Ok(())
     }
 }
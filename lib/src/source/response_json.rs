@@ -14,8 +14,78 @@ pub struct Response {
     /// The path of the requested file.
     pub file: String,
 
-    /// The full source code of the requested file.
+    /// The source code of the requested file, or of the requested lines.
     pub source: String,
+
+    /// The first line in `source`, if the request asked for a range of lines.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+
+    /// The last line in `source`, if the request asked for a range of lines.
+    /// This is clamped to the number of lines in the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+
+    /// The checksum which the debug info records for the file, formatted as
+    /// "<algorithm>:<hex bytes>", if present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_checksum: Option<String>,
+
+    /// Whether the returned source matches `file_checksum`. Not present if
+    /// there is no checksum.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum_matches: Option<bool>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResponse {
+    /// If present, ISO string representation of the date and time at which the
+    /// symbols file was last modified.
+    pub symbols_last_modified: Option<String>,
+
+    /// One entry per frame of the address, from the innermost inlined function
+    /// to the outer function.
+    pub frames: Vec<FrameSource>,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameSource {
+    /// The path of the frame's file, as in the /symbolicate/v5 response.
+    pub file: Option<String>,
+
+    /// The frame's line number.
+    pub line: Option<u32>,
+
+    /// If present, ISO string representation of the date and time at which the
+    /// source file was last modified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_last_modified: Option<String>,
+
+    /// The source code of the file, or of the lines around `line`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// The first line in `source`, if only the lines around `line` were requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+
+    /// The last line in `source`, if only the lines around `line` were requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+
+    /// See `Response::file_checksum`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_checksum: Option<String>,
+
+    /// See `Response::checksum_matches`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum_matches: Option<bool>,
+
+    /// Why the source for this frame couldn't be returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 #[cfg(test)]
@@ -34,6 +104,10 @@ mod test {
 */
 "#
             .to_string(),
+            start_line: None,
+            end_line: None,
+            file_checksum: None,
+            checksum_matches: None,
         };
         let response = serde_json::to_string_pretty(&response)?;
         let expected = r#"{
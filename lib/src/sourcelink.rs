@@ -0,0 +1,179 @@
+use crate::path_mapper::ExtraPathMapper;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The contents of a SourceLink JSON file, as stored in the "sourcelink" stream of
+/// PDB files. See https://github.com/dotnet/designs/blob/main/accepted/2020/diagnostics/source-link.md
+#[derive(Deserialize, Debug)]
+struct SourceLinkJson {
+    documents: HashMap<String, String>,
+}
+
+/// A mapping from a file path, or from a path prefix if `is_prefix` is true, to a URL.
+#[derive(Debug)]
+struct SourceLinkDocument {
+    /// The normalized path, see `normalize_path`, without the trailing '*' for prefixes.
+    path: String,
+    /// The URL. For prefixes, the '*' in the URL is replaced with the rest of the path.
+    url: String,
+    is_prefix: bool,
+}
+
+/// Map raw file paths to special "permalink" paths, using SourceLink JSON. This is
+/// what MSVC (with `/SOURCELINK`) and rustc on Windows use instead of srcsrv.
+/// SourceLink maps full paths, or path prefixes with a '*' wildcard, to URLs from
+/// which the raw file can be downloaded.
+/// URLs of GitHub, GitLab and Azure DevOps repositories are turned into special
+/// paths of the form "git:<repo>:<path>:<rev>". Other URLs are returned as they are.
+pub struct SourceLinkPathMapper {
+    /// Sorted from the longest to the shortest path, because the longest match wins.
+    documents: Vec<SourceLinkDocument>,
+    cache: HashMap<String, Option<String>>,
+    github_regex: Regex,
+    gitlab_regex: Regex,
+    azure_regex: Regex,
+}
+
+impl ExtraPathMapper for SourceLinkPathMapper {
+    fn map_path(&mut self, path: &str) -> Option<String> {
+        if let Some(value) = self.cache.get(path) {
+            return value.clone();
+        }
+
+        let value = self
+            .url_for_path(path)
+            .map(|url| self.url_to_special_path(&url));
+        self.cache.insert(path.to_string(), value.clone());
+        value
+    }
+}
+
+impl SourceLinkPathMapper {
+    /// Parses the SourceLink JSON.
+    pub fn parse(json: &[u8]) -> Result<Self, serde_json::Error> {
+        let source_link: SourceLinkJson = serde_json::from_slice(json)?;
+        let mut documents: Vec<SourceLinkDocument> = source_link
+            .documents
+            .into_iter()
+            .map(|(path, url)| match path.strip_suffix('*') {
+                Some(prefix) => SourceLinkDocument {
+                    path: normalize_path(prefix),
+                    url,
+                    is_prefix: true,
+                },
+                None => SourceLinkDocument {
+                    path: normalize_path(&path),
+                    url,
+                    is_prefix: false,
+                },
+            })
+            .collect();
+        documents.sort_by_key(|document| std::cmp::Reverse(document.path.len()));
+
+        Ok(SourceLinkPathMapper {
+            documents,
+            cache: HashMap::new(),
+            github_regex: Regex::new(r"^https://raw\.githubusercontent\.com/(?P<repo>[^/]+/[^/]+)/(?P<rev>[^/]+)/(?P<path>.*)$").unwrap(),
+            gitlab_regex: Regex::new(r"^https://(?P<repo>[^/]+/.+?)/-/raw/(?P<rev>[^/]+)/(?P<path>.*)$").unwrap(),
+            azure_regex: Regex::new(r"^https://(?P<project>[^/]+/.+)/_apis/git/repositories/(?P<repo>[^/?]+)/items\?api-version=[^&]*&versionType=commit&version=(?P<rev>[^&]+)&path=/?(?P<path>.*)$").unwrap(),
+        })
+    }
+
+    fn url_for_path(&self, path: &str) -> Option<String> {
+        let normalized_path = normalize_path(path);
+        self.documents.iter().find_map(|document| {
+            if document.is_prefix {
+                let rest = normalized_path.strip_prefix(&document.path)?;
+                // Keep the case of the original path. normalize_path doesn't change
+                // the length of the path, so the offsets are the same.
+                let rest = &path[path.len() - rest.len()..];
+                Some(document.url.replacen('*', &rest.replace('\\', "/"), 1))
+            } else if normalized_path == document.path {
+                Some(document.url.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    fn url_to_special_path(&self, url: &str) -> String {
+        if let Some(captures) = self.github_regex.captures(url) {
+            // https://raw.githubusercontent.com/mozilla/gecko-dev/e8ac2e6fc8a9dcd7ff1a1a2b3fe3a0a3c5e6a1ea/xpcom/base/nsCOMPtr.h
+            // -> "git:github.com/mozilla/gecko-dev:xpcom/base/nsCOMPtr.h:e8ac2e6fc8a9dcd7ff1a1a2b3fe3a0a3c5e6a1ea"
+            let repo = captures.name("repo").unwrap().as_str();
+            let path = captures.name("path").unwrap().as_str();
+            let rev = captures.name("rev").unwrap().as_str();
+            format!("git:github.com/{}:{}:{}", repo, path, rev)
+        } else if let Some(captures) = self.gitlab_regex.captures(url) {
+            // https://gitlab.com/gnome/glib/-/raw/6b7a1f9d5b2b1b0f1e0f0c7b7d8a2b9e4b5c6d7e/glib/gmain.c
+            // -> "git:gitlab.com/gnome/glib:glib/gmain.c:6b7a1f9d5b2b1b0f1e0f0c7b7d8a2b9e4b5c6d7e"
+            let repo = captures.name("repo").unwrap().as_str();
+            let path = captures.name("path").unwrap().as_str();
+            let rev = captures.name("rev").unwrap().as_str();
+            format!("git:{}:{}:{}", repo, path, rev)
+        } else if let Some(captures) = self.azure_regex.captures(url) {
+            // https://dev.azure.com/org/project/_apis/git/repositories/repo/items?api-version=1.0&versionType=commit&version=0123abcd&path=/src/main.cpp
+            // -> "git:dev.azure.com/org/project/_git/repo:src/main.cpp:0123abcd"
+            let project = captures.name("project").unwrap().as_str();
+            let repo = captures.name("repo").unwrap().as_str();
+            let path = captures.name("path").unwrap().as_str();
+            let rev = captures.name("rev").unwrap().as_str();
+            format!("git:{}/_git/{}:{}:{}", project, repo, path, rev)
+        } else {
+            url.to_string()
+        }
+    }
+}
+
+/// Paths are compared case-insensitively and regardless of the kind of slashes,
+/// because SourceLink is mostly used on Windows. This doesn't change the length
+/// of the path.
+fn normalize_path(path: &str) -> String {
+    path.to_ascii_lowercase().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::SourceLinkPathMapper;
+    use crate::path_mapper::ExtraPathMapper;
+
+    #[test]
+    fn map_paths() {
+        let json = br#"{
+            "documents": {
+                "C:\\build\\src\\*": "https://raw.githubusercontent.com/mozilla/gecko-dev/e8ac2e6fc8a9dcd7ff1a1a2b3fe3a0a3c5e6a1ea/*",
+                "C:\\build\\src\\third_party\\glib\\*": "https://gitlab.com/gnome/glib/-/raw/6b7a1f9d/*",
+                "C:\\build\\src\\vendor\\*": "https://dev.azure.com/org/project/_apis/git/repositories/vendor/items?api-version=1.0&versionType=commit&version=0123abcd&path=/*",
+                "C:\\build\\generated\\config.h": "https://example.com/artifacts/config.h",
+                "D:\\other\\*": "https://example.com/other/*"
+            }
+        }"#;
+        let mut mapper = SourceLinkPathMapper::parse(json).unwrap();
+        assert_eq!(
+            mapper.map_path(r"C:\build\src\xpcom\base\nsCOMPtr.h"),
+            Some(
+                "git:github.com/mozilla/gecko-dev:xpcom/base/nsCOMPtr.h:e8ac2e6fc8a9dcd7ff1a1a2b3fe3a0a3c5e6a1ea"
+                    .to_string()
+            )
+        );
+        // Paths are matched case-insensitively, and the case of the rest is kept.
+        assert_eq!(
+            mapper.map_path(r"c:\BUILD\src\third_party\glib\glib\GMain.c"),
+            Some("git:gitlab.com/gnome/glib:glib/GMain.c:6b7a1f9d".to_string())
+        );
+        assert_eq!(
+            mapper.map_path("C:/build/src/vendor/lib/main.cpp"),
+            Some("git:dev.azure.com/org/project/_git/vendor:lib/main.cpp:0123abcd".to_string())
+        );
+        assert_eq!(
+            mapper.map_path(r"C:\build\generated\config.h"),
+            Some("https://example.com/artifacts/config.h".to_string())
+        );
+        assert_eq!(
+            mapper.map_path(r"D:\other\foo.c"),
+            Some("https://example.com/other/foo.c".to_string())
+        );
+        assert_eq!(mapper.map_path(r"C:\build\generated\other.h"), None);
+    }
+}
@@ -1,36 +1,316 @@
 use crate::symbolicate::demangle_ocaml;
+use crate::symbolicate::demangle_swift::{self, SwiftDemangleStyle};
 use msvc_demangler::DemangleFlags;
+use serde::Deserialize;
 
-pub fn demangle_any(name: &str) -> String {
-    if name.starts_with('?') {
-        let flags = DemangleFlags::NO_ACCESS_SPECIFIERS
-            | DemangleFlags::NO_FUNCTION_RETURNS
-            | DemangleFlags::NO_MEMBER_TYPE
-            | DemangleFlags::NO_MS_KEYWORDS
-            | DemangleFlags::NO_THISTYPE
-            | DemangleFlags::NO_CLASS_TYPE
-            | DemangleFlags::SPACE_AFTER_COMMA
-            | DemangleFlags::HUG_TYPE;
-        return msvc_demangler::demangle(name, flags).unwrap_or_else(|_| name.to_string());
+/// Which form of the function names should be returned.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NameFormat {
+    /// The demangled name. This is the default.
+    #[default]
+    Demangled,
+    /// The name as it is stored in the binary or in the debug info.
+    Raw,
+    /// The demangled name, and the raw name next to it in "function_raw".
+    /// Compact symbol tables only have room for one name per symbol, so
+    /// they treat this like `Demangled`.
+    Both,
+}
+
+/// Options which control how symbol names are demangled and simplified.
+/// These apply to all names, regardless of whether they come from a symbol
+/// table, from DWARF or from a PDB file.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DemangleOptions {
+    /// Whether to return the raw name, the demangled name, or both.
+    pub format: NameFormat,
+
+    /// Whether demangled names keep their parameter list, e.g. `foo(int)`
+    /// instead of `foo`. On by default.
+    pub with_params: bool,
+
+    /// Whether to remove template and generic arguments from demangled names,
+    /// e.g. `foo<int>` becomes `foo`.
+    pub strip_template_args: bool,
+
+    /// Whether to remove the hash suffix from demangled Rust names, e.g.
+    /// `core::ptr::drop_in_place::h0123456789abcdef`. On by default.
+    pub strip_rust_hash: bool,
+
+    /// If set, longer names are cut off after this many characters, with the
+    /// last character replaced by "…".
+    pub max_length: Option<usize>,
+}
+
+impl Default for DemangleOptions {
+    fn default() -> Self {
+        DemangleOptions {
+            format: NameFormat::Demangled,
+            with_params: true,
+            strip_template_args: false,
+            strip_rust_hash: true,
+            max_length: None,
+        }
+    }
+}
+
+impl DemangleOptions {
+    /// Returns the name which should be reported for the symbol `name`.
+    pub fn format_name(&self, name: &str) -> String {
+        self.format(name, false)
     }
-    if let Ok(demangled_symbol) = rustc_demangle::try_demangle(name) {
-        return format!("{:#}", demangled_symbol);
+
+    /// Like `format_name`, for names from the symbol table or the export trie
+    /// of a mach-O binary. If the name can't be demangled, the extra leading
+    /// underscore which mach-O adds to C names is removed, so `_main` becomes `main`.
+    pub fn format_macho_symbol_name(&self, name: &str) -> String {
+        self.format(name, true)
     }
 
-    let options = cpp_demangle::DemangleOptions::default().no_return_type();
-    if let Ok(symbol) = cpp_demangle::Symbol::new(name) {
-        if let Ok(demangled_string) = symbol.demangle(&options) {
-            return demangled_string;
+    fn format(&self, name: &str, strip_macho_underscore: bool) -> String {
+        let name = match self.format {
+            NameFormat::Raw => name.to_owned(),
+            NameFormat::Demangled | NameFormat::Both => {
+                self.demangle_and_simplify(name, strip_macho_underscore)
+            }
+        };
+        match self.max_length {
+            Some(max_length) if name.chars().count() > max_length => {
+                let mut truncated: String =
+                    name.chars().take(max_length.saturating_sub(1)).collect();
+                truncated.push('…');
+                truncated
+            }
+            _ => name,
         }
     }
 
-    if let Some(symbol) = demangle_ocaml::demangle(name) {
-        return symbol;
+    /// Returns the raw name if it should be reported in addition to the
+    /// formatted name.
+    pub fn raw_name(&self, name: &str) -> Option<String> {
+        match self.format {
+            NameFormat::Both => Some(name.to_owned()),
+            NameFormat::Demangled | NameFormat::Raw => None,
+        }
     }
 
-    if name.starts_with('_') {
-        return name.split_at(1).1.to_owned();
+    fn demangle_and_simplify(&self, name: &str, strip_macho_underscore: bool) -> String {
+        let mut name = self.demangle(name, strip_macho_underscore);
+        if self.strip_template_args {
+            if let Some(stripped) = strip_template_args(&name) {
+                name = stripped;
+            }
+        }
+        // Names which were demangled with the parameter list omitted don't end
+        // in a parameter list anymore, but pre-formatted names (from PDBs) and
+        // Swift names still need this.
+        if !self.with_params {
+            if let Some(stripped) = strip_params(&name) {
+                name = stripped;
+            }
+        }
+        name
     }
 
-    name.to_owned()
+    fn demangle(&self, name: &str, strip_macho_underscore: bool) -> String {
+        if name.starts_with('?') {
+            let mut flags = DemangleFlags::NO_ACCESS_SPECIFIERS
+                | DemangleFlags::NO_FUNCTION_RETURNS
+                | DemangleFlags::NO_MEMBER_TYPE
+                | DemangleFlags::NO_MS_KEYWORDS
+                | DemangleFlags::NO_THISTYPE
+                | DemangleFlags::NO_CLASS_TYPE
+                | DemangleFlags::SPACE_AFTER_COMMA
+                | DemangleFlags::HUG_TYPE;
+            if !self.with_params {
+                flags |= DemangleFlags::NAME_ONLY;
+            }
+            return msvc_demangler::demangle(name, flags).unwrap_or_else(|_| name.to_string());
+        }
+        if let Ok(demangled_symbol) = rustc_demangle::try_demangle(name) {
+            return if self.strip_rust_hash {
+                format!("{:#}", demangled_symbol)
+            } else {
+                demangled_symbol.to_string()
+            };
+        }
+
+        if let Some(symbol) = demangle_swift::demangle(name, SwiftDemangleStyle::Simplified) {
+            return symbol;
+        }
+
+        let mut options = cpp_demangle::DemangleOptions::default().no_return_type();
+        if !self.with_params {
+            options = options.no_params();
+        }
+        if let Ok(symbol) = cpp_demangle::Symbol::new(name) {
+            if let Ok(demangled_string) = symbol.demangle(&options) {
+                return demangled_string;
+            }
+        }
+
+        if let Some(symbol) = demangle_ocaml::demangle(name) {
+            return symbol;
+        }
+
+        match name.strip_prefix('_') {
+            Some(stripped) if strip_macho_underscore => stripped.to_owned(),
+            _ => name.to_owned(),
+        }
+    }
+}
+
+/// Removes all `<...>` groups which follow an identifier, so that
+/// `std::vector<int>::push_back` becomes `std::vector::push_back`. Comparison
+/// and shift operators are left alone, and so are the angle brackets around
+/// qualified paths in Rust names, e.g. `<Foo as Bar>::baz`.
+/// Returns None if the brackets are unbalanced.
+fn strip_template_args(name: &str) -> Option<String> {
+    let mut result = String::with_capacity(name.len());
+    let mut depth = 0usize;
+    for c in name.chars() {
+        if depth > 0 {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        let follows_identifier = matches!(
+            result.chars().last(),
+            Some(prev) if prev.is_alphanumeric() || prev == '_'
+        );
+        if c == '<' && follows_identifier && !result.ends_with("operator") {
+            depth = 1;
+        } else {
+            result.push(c);
+        }
+    }
+    if depth == 0 {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// Removes the parameter list at the end of the name, including any
+/// qualifiers after it, so that `Foo::bar(int) const` becomes `Foo::bar`.
+/// Returns None if the name doesn't end in a parameter list.
+fn strip_params(name: &str) -> Option<String> {
+    let without_qualifiers =
+        name.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '&' || c == ' ');
+    if !without_qualifiers.ends_with(')') {
+        return None;
+    }
+    let mut depth = 0usize;
+    for (index, c) in without_qualifiers.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    let function_name = &without_qualifiers[..index];
+                    if function_name.is_empty() || function_name.ends_with("operator") {
+                        return None;
+                    }
+                    return Some(function_name.to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_names() {
+        let default_options = DemangleOptions::default();
+        assert_eq!(
+            default_options.format_name("_ZN3foo3barIiEEvT_"),
+            "foo::bar<int>(int)"
+        );
+        assert_eq!(
+            default_options.format_name("_ZN4core3ptr13drop_in_place17h0123456789abcdefE"),
+            "core::ptr::drop_in_place"
+        );
+        assert_eq!(default_options.format_name("_main"), "_main");
+        assert_eq!(default_options.format_macho_symbol_name("_main"), "main");
+        assert_eq!(
+            default_options.format_name("__security_check_cookie"),
+            "__security_check_cookie"
+        );
+        assert_eq!(default_options.format_name("?foo@@YAHH@Z"), "foo(int)");
+        assert!(default_options.raw_name("_main").is_none());
+
+        let options = DemangleOptions {
+            with_params: false,
+            ..Default::default()
+        };
+        assert_eq!(options.format_name("_ZN3foo3barIiEEvT_"), "foo::bar<int>");
+        assert_eq!(options.format_name("?foo@@YAHH@Z"), "foo");
+        assert_eq!(
+            options.format_name("??$max@H@std@@YAAEBHAEBH0@Z"),
+            "std::max<int>"
+        );
+        assert_eq!(options.format_name("$s4main3fooyyF"), "foo");
+        assert_eq!(options.format_name("Foo::bar(int) const"), "Foo::bar");
+        assert_eq!(
+            options.format_name("Foo::operator()(int)"),
+            "Foo::operator()"
+        );
+
+        let options = DemangleOptions {
+            strip_template_args: true,
+            strip_rust_hash: false,
+            ..Default::default()
+        };
+        assert_eq!(options.format_name("_ZN3foo3barIiEEvT_"), "foo::bar(int)");
+        assert_eq!(
+            options.format_name("_ZN4core3ptr13drop_in_place17h0123456789abcdefE"),
+            "core::ptr::drop_in_place::h0123456789abcdef"
+        );
+
+        let options = DemangleOptions {
+            format: NameFormat::Raw,
+            max_length: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(options.format_name("_ZN3foo3barIiEEvT_"), "_ZN3foo…");
+        assert_eq!(options.format_name("_main"), "_main");
+        assert!(options.raw_name("_main").is_none());
+
+        let options = DemangleOptions {
+            format: NameFormat::Both,
+            ..Default::default()
+        };
+        assert_eq!(options.format_macho_symbol_name("_main"), "main");
+        assert_eq!(options.raw_name("_main"), Some("_main".to_string()));
+    }
+
+    #[test]
+    fn strip_template_and_params() {
+        assert_eq!(
+            strip_template_args("std::vector<std::pair<int, int> >::push_back"),
+            Some("std::vector::push_back".to_string())
+        );
+        assert_eq!(
+            strip_template_args("<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop"),
+            Some("<alloc::vec::Vec as core::ops::drop::Drop>::drop".to_string())
+        );
+        assert_eq!(
+            strip_template_args("Foo::operator<<(int)"),
+            Some("Foo::operator<<(int)".to_string())
+        );
+        assert!(strip_template_args("foo<int").is_none());
+        assert!(strip_params("foo").is_none());
+        assert_eq!(strip_params("foo(int (*)(int))"), Some("foo".to_string()));
+        assert!(strip_params("Foo::operator()").is_none());
+    }
 }
@@ -0,0 +1,3697 @@
+//! A demangler for Swift symbols, covering the Swift 4 (`_T0`) and Swift 5
+//! (`$s`, `$S`, `$e`) mangling schemes.
+//!
+//! The parser follows the structure of `swift/lib/Demangling/Demangler.cpp`:
+//! the mangled name is a sequence of postfix operators which push nodes onto
+//! a stack and pop their operands from it. The printer follows
+//! `NodePrinter.cpp`, with two styles: `Full` matches the default output of
+//! `swift-demangle`, and `Simplified` matches `swift-demangle --simplified`,
+//! which leaves out module names, parameter types and where clauses.
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+/// Selects how much detail to print for a demangled Swift symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwiftDemangleStyle {
+    /// Fully qualified names with parameter and result types, like
+    /// `main.Foo.bar(x: Swift.Int) -> Swift.String`.
+    Full,
+    /// Names with argument labels only, like `Foo.bar(x:)`.
+    Simplified,
+}
+
+/// Demangles a Swift symbol. Symbols with a leading underscore, as found in
+/// Mach-O symbol tables, are accepted. Returns `None` if the name is not a
+/// Swift symbol or cannot be parsed completely.
+pub fn demangle(name: &str, style: SwiftDemangleStyle) -> Option<String> {
+    let (mangled, old_mangling) = strip_mangling_prefix(name)?;
+    let mut demangler = Demangler::new(mangled, old_mangling);
+    let global = demangler.demangle_symbol()?;
+    Printer::new(&demangler, style).print_global(global)
+}
+
+fn strip_mangling_prefix(name: &str) -> Option<(&str, bool)> {
+    // Mach-O adds an underscore in front of every symbol name, so `_T0` becomes
+    // `__T0` and `$s` becomes `_$s`.
+    let name = match name.strip_prefix('_') {
+        Some(rest) if rest.starts_with("_T0") || rest.starts_with('$') => rest,
+        _ => name,
+    };
+    if let Some(rest) = name.strip_prefix("_T0") {
+        return Some((rest, true));
+    }
+    ["$s", "$S", "$e"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .map(|rest| (rest, false))
+}
+
+const MAX_NODE_COUNT: usize = 1 << 16;
+const MAX_REPEAT_COUNT: usize = 2048;
+const MAX_NUM_WORDS: usize = 26;
+const MAX_PRINT_DEPTH: usize = 256;
+const MAX_OUTPUT_LEN: usize = 16 * 1024;
+
+const STDLIB_NAME: &str = "Swift";
+
+type NodeId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Global,
+    Suffix,
+    Module,
+    Identifier,
+    LocalDeclName,
+    PrivateDeclName,
+    RelatedEntityDeclName,
+    InfixOperator,
+    PrefixOperator,
+    PostfixOperator,
+    Number,
+    Type,
+    TypeMangling,
+    Class,
+    Structure,
+    Enum,
+    Protocol,
+    TypeAlias,
+    BoundGenericClass,
+    BoundGenericStructure,
+    BoundGenericEnum,
+    BoundGenericOther,
+    TypeList,
+    Extension,
+    Function,
+    Allocator,
+    Constructor,
+    Destructor,
+    Deallocator,
+    IVarInitializer,
+    IVarDestroyer,
+    Initializer,
+    DefaultArgumentInitializer,
+    PropertyWrapperBackingInitializer,
+    PropertyWrapperInitFromProjectedValue,
+    ExplicitClosure,
+    ImplicitClosure,
+    Variable,
+    Subscript,
+    /// A getter, setter, etc. of a variable or subscript. The text holds the
+    /// accessor name.
+    Accessor,
+    Static,
+    LabelList,
+    FirstElementMarker,
+    EmptyList,
+    VariadicMarker,
+    ThrowsAnnotation,
+    TypedThrowsAnnotation,
+    AsyncAnnotation,
+    ConcurrentFunctionType,
+    GlobalActorFunctionType,
+    /// A function type; the text holds an attribute prefix such as
+    /// `@convention(c) `.
+    FunctionType,
+    ArgumentTuple,
+    ReturnType,
+    Tuple,
+    TupleElement,
+    TupleElementName,
+    BuiltinTypeName,
+    /// A generic parameter; `index` is the depth and `index2` the index.
+    DependentGenericParamType,
+    DependentMemberType,
+    DependentAssociatedTypeRef,
+    AssociatedTypeRef,
+    DependentGenericType,
+    DependentGenericSignature,
+    DependentGenericParamCount,
+    ConformanceRequirement,
+    SameTypeRequirement,
+    LayoutRequirement,
+    OpaqueReturnType,
+    Metatype,
+    ExistentialMetatype,
+    /// A type with a prefix such as `inout ` or `weak `, held in the text.
+    TypeAttribute,
+    DynamicSelf,
+    ErrorType,
+    ProtocolList,
+    ProtocolListWithClass,
+    ProtocolListWithAnyObject,
+    ImplFunctionType,
+    /// An attribute of an `ImplFunctionType`, printed from its text.
+    ImplAttribute,
+    ImplParameter,
+    ImplResult,
+    ImplYield,
+    ImplErrorResult,
+    /// A symbol which is described by a prefix, like `type metadata for `.
+    Described(&'static str),
+    /// A prefix which is applied to the rest of the symbol, like `merged `.
+    /// The second string is used in the simplified style.
+    FunctionAttribute(&'static str, &'static str),
+    PartialApplyForwarder,
+    PartialApplyObjCForwarder,
+    AsyncResumePartialFunction,
+    /// A specialization of the following function; the text holds its
+    /// description, such as `generic specialization`.
+    Specialization,
+    SpecializationParam,
+    /// A function signature specialization parameter; `index` holds the
+    /// `FuncSigSpecParamKind` bits.
+    FuncSigSpecParam,
+    FuncSigSpecReturn,
+    FuncSigSpecPayload,
+    IsSerialized,
+    ValueWitness,
+    /// Like `Described`, with a second string printed between the two
+    /// children, like `protocol witness for <entity> in conformance <conf>`.
+    DescribedPair(&'static str, &'static str),
+    ProtocolConformance,
+    ReabstractionThunk,
+    ReabstractionThunkHelper,
+    FieldOffset,
+}
+
+impl Kind {
+    fn is_context(self) -> bool {
+        matches!(
+            self,
+            Kind::Module
+                | Kind::Class
+                | Kind::Structure
+                | Kind::Enum
+                | Kind::Protocol
+                | Kind::TypeAlias
+                | Kind::Extension
+                | Kind::Function
+                | Kind::Allocator
+                | Kind::Constructor
+                | Kind::Destructor
+                | Kind::Deallocator
+                | Kind::IVarInitializer
+                | Kind::IVarDestroyer
+                | Kind::Initializer
+                | Kind::DefaultArgumentInitializer
+                | Kind::PropertyWrapperBackingInitializer
+                | Kind::PropertyWrapperInitFromProjectedValue
+                | Kind::ExplicitClosure
+                | Kind::ImplicitClosure
+                | Kind::Variable
+                | Kind::Subscript
+                | Kind::Accessor
+                | Kind::Static
+        )
+    }
+
+    fn is_entity(self) -> bool {
+        self == Kind::Type || self.is_context()
+    }
+
+    fn is_decl_name(self) -> bool {
+        matches!(
+            self,
+            Kind::Identifier
+                | Kind::LocalDeclName
+                | Kind::PrivateDeclName
+                | Kind::RelatedEntityDeclName
+                | Kind::InfixOperator
+                | Kind::PrefixOperator
+                | Kind::PostfixOperator
+        )
+    }
+
+    fn is_any_generic(self) -> bool {
+        matches!(
+            self,
+            Kind::Class | Kind::Structure | Kind::Enum | Kind::Protocol | Kind::TypeAlias
+        )
+    }
+
+    fn is_requirement(self) -> bool {
+        matches!(
+            self,
+            Kind::ConformanceRequirement | Kind::SameTypeRequirement | Kind::LayoutRequirement
+        )
+    }
+
+    fn is_function_attribute(self) -> bool {
+        matches!(
+            self,
+            Kind::FunctionAttribute(..)
+                | Kind::PartialApplyForwarder
+                | Kind::PartialApplyObjCForwarder
+                | Kind::AsyncResumePartialFunction
+                | Kind::Specialization
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct NodeData {
+    kind: Kind,
+    text: String,
+    index: u64,
+    index2: u64,
+    children: Vec<NodeId>,
+}
+
+struct Demangler<'a> {
+    text: &'a [u8],
+    pos: usize,
+    old_mangling: bool,
+    nodes: Vec<NodeData>,
+    stack: Vec<NodeId>,
+    substitutions: Vec<NodeId>,
+    words: Vec<String>,
+}
+
+impl<'a> Demangler<'a> {
+    fn new(mangled: &'a str, old_mangling: bool) -> Self {
+        Demangler {
+            text: mangled.as_bytes(),
+            pos: 0,
+            old_mangling,
+            nodes: Vec::new(),
+            stack: Vec::new(),
+            substitutions: Vec::new(),
+            words: Vec::new(),
+        }
+    }
+
+    fn demangle_symbol(&mut self) -> Option<NodeId> {
+        while self.pos < self.text.len() {
+            let node = self.demangle_operator()?;
+            self.push(node);
+        }
+
+        let suffix = self.pop_kind(Kind::Suffix);
+        let global = self.create(Kind::Global)?;
+        let mut parent = global;
+        while let Some(attribute) = self.pop_if(|kind| kind.is_function_attribute()) {
+            self.add_child(parent, attribute);
+            if matches!(
+                self.kind(attribute),
+                Kind::PartialApplyForwarder | Kind::PartialApplyObjCForwarder
+            ) {
+                parent = attribute;
+            }
+        }
+        for node in std::mem::take(&mut self.stack) {
+            let node = if self.kind(node) == Kind::Type {
+                self.child(node, 0)?
+            } else {
+                node
+            };
+            self.add_child(parent, node);
+        }
+        if let Some(suffix) = suffix {
+            self.add_child(global, suffix);
+        }
+        if self.nodes[global].children.is_empty() {
+            return None;
+        }
+        Some(global)
+    }
+
+    // Node creation and access
+
+    fn create(&mut self, kind: Kind) -> Option<NodeId> {
+        self.create_node(kind, String::new(), 0)
+    }
+
+    fn create_text(&mut self, kind: Kind, text: &str) -> Option<NodeId> {
+        self.create_node(kind, text.to_string(), 0)
+    }
+
+    fn create_index(&mut self, kind: Kind, index: u64) -> Option<NodeId> {
+        self.create_node(kind, String::new(), index)
+    }
+
+    fn create_node(&mut self, kind: Kind, text: String, index: u64) -> Option<NodeId> {
+        if self.nodes.len() >= MAX_NODE_COUNT {
+            return None;
+        }
+        self.nodes.push(NodeData {
+            kind,
+            text,
+            index,
+            index2: 0,
+            children: Vec::new(),
+        });
+        Some(self.nodes.len() - 1)
+    }
+
+    fn create_with_children(&mut self, kind: Kind, children: &[Option<NodeId>]) -> Option<NodeId> {
+        let children = children.iter().copied().collect::<Option<Vec<_>>>()?;
+        let node = self.create(kind)?;
+        self.nodes[node].children = children;
+        Some(node)
+    }
+
+    fn create_type(&mut self, child: Option<NodeId>) -> Option<NodeId> {
+        self.create_with_children(Kind::Type, &[child])
+    }
+
+    fn create_swift_type(&mut self, kind: Kind, name: &str) -> Option<NodeId> {
+        let module = self.create_text(Kind::Module, STDLIB_NAME);
+        let name = self.create_text(Kind::Identifier, name);
+        let nominal = self.create_with_children(kind, &[module, name]);
+        self.create_type(nominal)
+    }
+
+    fn add_child(&mut self, parent: NodeId, child: NodeId) {
+        self.nodes[parent].children.push(child);
+    }
+
+    /// Appends `child` to `parent` if both are present, and returns the
+    /// parent. Mirrors `Demangler::addChild`, which fails if either is null.
+    fn add_required(&mut self, parent: Option<NodeId>, child: Option<NodeId>) -> Option<NodeId> {
+        let (parent, child) = (parent?, child?);
+        self.add_child(parent, child);
+        Some(parent)
+    }
+
+    fn add_optional(&mut self, parent: NodeId, child: Option<NodeId>) {
+        if let Some(child) = child {
+            self.add_child(parent, child);
+        }
+    }
+
+    fn kind(&self, node: NodeId) -> Kind {
+        self.nodes[node].kind
+    }
+
+    fn child(&self, node: NodeId, index: usize) -> Option<NodeId> {
+        self.nodes[node].children.get(index).copied()
+    }
+
+    fn num_children(&self, node: NodeId) -> usize {
+        self.nodes[node].children.len()
+    }
+
+    fn change_kind(&mut self, node: Option<NodeId>, kind: Kind) -> Option<NodeId> {
+        let node = node?;
+        let mut data = self.nodes[node].clone();
+        data.kind = kind;
+        if self.nodes.len() >= MAX_NODE_COUNT {
+            return None;
+        }
+        self.nodes.push(data);
+        Some(self.nodes.len() - 1)
+    }
+
+    // The node stack
+
+    fn push(&mut self, node: NodeId) {
+        self.stack.push(node);
+    }
+
+    fn pop(&mut self) -> Option<NodeId> {
+        self.stack.pop()
+    }
+
+    fn pop_kind(&mut self, kind: Kind) -> Option<NodeId> {
+        self.pop_if(|k| k == kind)
+    }
+
+    fn pop_if(&mut self, predicate: impl Fn(Kind) -> bool) -> Option<NodeId> {
+        let top = *self.stack.last()?;
+        if predicate(self.kind(top)) {
+            self.stack.pop()
+        } else {
+            None
+        }
+    }
+
+    fn pop_type_and_get_child(&mut self) -> Option<NodeId> {
+        let ty = self.pop_kind(Kind::Type)?;
+        self.child(ty, 0)
+    }
+
+    fn pop_type_and_get_any_generic(&mut self) -> Option<NodeId> {
+        let child = self.pop_type_and_get_child()?;
+        if self.kind(child).is_any_generic() {
+            Some(child)
+        } else {
+            None
+        }
+    }
+
+    fn pop_module(&mut self) -> Option<NodeId> {
+        if let Some(identifier) = self.pop_kind(Kind::Identifier) {
+            return self.change_kind(Some(identifier), Kind::Module);
+        }
+        self.pop_kind(Kind::Module)
+    }
+
+    fn pop_context(&mut self) -> Option<NodeId> {
+        if let Some(module) = self.pop_module() {
+            return Some(module);
+        }
+        if let Some(ty) = self.pop_kind(Kind::Type) {
+            if self.num_children(ty) != 1 {
+                return None;
+            }
+            let child = self.child(ty, 0)?;
+            return if self.kind(child).is_context() {
+                Some(child)
+            } else {
+                None
+            };
+        }
+        self.pop_if(|kind| kind.is_context())
+    }
+
+    fn pop_protocol(&mut self) -> Option<NodeId> {
+        if let Some(ty) = self.pop_kind(Kind::Type) {
+            let child = self.child(ty, 0)?;
+            return if self.is_protocol_type(child) {
+                Some(ty)
+            } else {
+                None
+            };
+        }
+        let name = self.pop_if(|kind| kind.is_decl_name());
+        let context = self.pop_context();
+        let protocol = self.create_with_children(Kind::Protocol, &[context, name]);
+        self.create_type(protocol)
+    }
+
+    fn is_protocol_type(&self, node: NodeId) -> bool {
+        match self.kind(node) {
+            Kind::Type => {
+                matches!(self.child(node, 0), Some(child) if self.is_protocol_type(child))
+            }
+            Kind::Protocol => true,
+            _ => false,
+        }
+    }
+
+    fn pop_type_list(&mut self) -> Option<NodeId> {
+        let root = self.create(Kind::TypeList)?;
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                let ty = self.pop_kind(Kind::Type)?;
+                self.add_child(root, ty);
+                if first {
+                    break;
+                }
+            }
+            self.nodes[root].children.reverse();
+        }
+        Some(root)
+    }
+
+    fn add_substitution(&mut self, node: Option<NodeId>) -> Option<NodeId> {
+        let node = node?;
+        self.substitutions.push(node);
+        Some(node)
+    }
+
+    // Reading the mangled text
+
+    fn peek(&self) -> u8 {
+        self.text.get(self.pos).copied().unwrap_or(0)
+    }
+
+    /// Returns the next character, or 0 at the end of the text. Always
+    /// advances, so that a following `push_back` restores the position.
+    fn next(&mut self) -> u8 {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn next_if(&mut self, c: u8) -> bool {
+        if self.peek() == c {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn push_back(&mut self) {
+        self.pos -= 1;
+    }
+
+    fn demangle_natural(&mut self) -> Option<u64> {
+        if !self.peek().is_ascii_digit() {
+            return None;
+        }
+        let mut value: u64 = 0;
+        while self.peek().is_ascii_digit() {
+            let digit = u64::from(self.next() - b'0');
+            value = value.checked_mul(10)?.checked_add(digit)?;
+        }
+        Some(value)
+    }
+
+    fn demangle_index(&mut self) -> Option<u64> {
+        if self.next_if(b'_') {
+            return Some(0);
+        }
+        let number = self.demangle_natural()?;
+        if self.next_if(b'_') {
+            number.checked_add(1)
+        } else {
+            None
+        }
+    }
+
+    fn demangle_index_as_node(&mut self) -> Option<NodeId> {
+        let index = self.demangle_index()?;
+        self.create_index(Kind::Number, index)
+    }
+}
+
+// Function signature specialization parameter kinds, from
+// `FunctionSigSpecializationParamKind` in `swift/Demangling/Demangle.h`.
+const CONSTANT_PROP_FUNCTION: u64 = 0;
+const CONSTANT_PROP_GLOBAL: u64 = 1;
+const CONSTANT_PROP_INTEGER: u64 = 2;
+const CONSTANT_PROP_FLOAT: u64 = 3;
+const CONSTANT_PROP_STRING: u64 = 4;
+const CLOSURE_PROP: u64 = 5;
+const BOX_TO_VALUE: u64 = 6;
+const BOX_TO_STACK: u64 = 7;
+const IN_OUT_TO_OUT: u64 = 8;
+const CONSTANT_PROP_KEY_PATH: u64 = 9;
+const DEAD: u64 = 1 << 6;
+const OWNED_TO_GUARANTEED: u64 = 1 << 7;
+const SROA: u64 = 1 << 8;
+const GUARANTEED_TO_OWNED: u64 = 1 << 9;
+const EXISTENTIAL_TO_GENERIC: u64 = 1 << 10;
+
+impl<'a> Demangler<'a> {
+    fn demangle_operator(&mut self) -> Option<NodeId> {
+        match self.next() {
+            b'A' => self.demangle_multi_substitutions(),
+            b'B' => self.demangle_builtin_type(),
+            b'C' => self.demangle_any_generic_type(Kind::Class),
+            b'D' => {
+                let ty = self.pop_kind(Kind::Type);
+                self.create_with_children(Kind::TypeMangling, &[ty])
+            }
+            b'E' => self.demangle_extension_context(),
+            b'F' => self.demangle_plain_function(),
+            b'G' => self.demangle_bound_generic_type(),
+            b'I' => self.demangle_impl_function_type(),
+            b'K' => self.create(Kind::ThrowsAnnotation),
+            b'L' => self.demangle_local_identifier(),
+            b'M' => self.demangle_metatype(),
+            b'N' => self.create_with_popped_type("type metadata for "),
+            b'O' => self.demangle_any_generic_type(Kind::Enum),
+            b'P' => self.demangle_any_generic_type(Kind::Protocol),
+            b'Q' => self.demangle_archetype(),
+            b'R' => self.demangle_generic_requirement(),
+            b'S' => self.demangle_standard_substitution(),
+            b'T' => self.demangle_thunk_or_specialization(),
+            b'V' => self.demangle_any_generic_type(Kind::Structure),
+            b'W' => self.demangle_witness(),
+            b'X' => self.demangle_special_type(),
+            b'Y' => self.demangle_type_annotation(),
+            b'Z' => {
+                let entity = self.pop_if(Kind::is_entity);
+                self.create_with_children(Kind::Static, &[entity])
+            }
+            b'a' => self.demangle_any_generic_type(Kind::TypeAlias),
+            b'c' => self.pop_function_type(""),
+            b'd' => self.create(Kind::VariadicMarker),
+            b'f' => self.demangle_function_entity(),
+            b'h' => self.demangle_type_attribute("__shared "),
+            b'i' => self.demangle_subscript(),
+            b'l' => self.demangle_generic_signature(false),
+            b'm' => {
+                let ty = self.pop_kind(Kind::Type);
+                let metatype = self.create_with_children(Kind::Metatype, &[ty]);
+                self.create_type(metatype)
+            }
+            b'n' => self.demangle_type_attribute("__owned "),
+            b'o' => self.demangle_operator_identifier(),
+            b'p' => {
+                let list = self.demangle_protocol_list();
+                self.create_type(list)
+            }
+            b'q' => {
+                let param = self.demangle_generic_param_index();
+                self.create_type(param)
+            }
+            b'r' => self.demangle_generic_signature(true),
+            b's' => self.create_text(Kind::Module, STDLIB_NAME),
+            b't' => self.pop_tuple(),
+            b'u' => self.demangle_generic_type(),
+            b'v' => self.demangle_variable(),
+            b'w' => self.demangle_value_witness(),
+            b'x' => {
+                let param = self.dependent_generic_param_type(0, 0);
+                self.create_type(param)
+            }
+            b'y' => self.create(Kind::EmptyList),
+            b'z' => self.demangle_type_attribute("inout "),
+            b'_' => self.create(Kind::FirstElementMarker),
+            b'.' => {
+                self.push_back();
+                let suffix = String::from_utf8_lossy(&self.text[self.pos..]).into_owned();
+                self.pos = self.text.len();
+                self.create_node(Kind::Suffix, suffix, 0)
+            }
+            b'0'..=b'9' => {
+                self.push_back();
+                self.demangle_identifier()
+            }
+            _ => None,
+        }
+    }
+
+    fn create_with_popped_type(&mut self, description: &'static str) -> Option<NodeId> {
+        let ty = self.pop_kind(Kind::Type);
+        self.create_with_children(Kind::Described(description), &[ty])
+    }
+
+    fn create_with_popped_conformance(&mut self, description: &'static str) -> Option<NodeId> {
+        let conformance = self.pop_protocol_conformance();
+        self.create_with_children(Kind::Described(description), &[conformance])
+    }
+
+    fn demangle_type_attribute(&mut self, prefix: &str) -> Option<NodeId> {
+        let ty = self.pop_type_and_get_child();
+        let node = self.create_with_children(Kind::TypeAttribute, &[ty])?;
+        self.nodes[node].text = prefix.to_string();
+        self.create_type(Some(node))
+    }
+
+    fn dependent_generic_param_type(&mut self, depth: u64, index: u64) -> Option<NodeId> {
+        let node = self.create_index(Kind::DependentGenericParamType, depth)?;
+        self.nodes[node].index2 = index;
+        Some(node)
+    }
+
+    fn demangle_generic_param_index(&mut self) -> Option<NodeId> {
+        if self.next_if(b'd') {
+            let depth = self.demangle_index()?.checked_add(1)?;
+            let index = self.demangle_index()?;
+            return self.dependent_generic_param_type(depth, index);
+        }
+        if self.next_if(b'z') {
+            return self.dependent_generic_param_type(0, 0);
+        }
+        let index = self.demangle_index()?.checked_add(1)?;
+        self.dependent_generic_param_type(0, index)
+    }
+
+    // Identifiers and substitutions
+
+    fn demangle_identifier(&mut self) -> Option<NodeId> {
+        let mut has_word_substs = false;
+        let mut is_punycoded = false;
+        if self.next_if(b'0') {
+            if self.next_if(b'0') {
+                is_punycoded = true;
+            } else {
+                has_word_substs = true;
+            }
+        }
+        let text = self.text;
+        let mut identifier = String::new();
+        loop {
+            while has_word_substs && self.peek().is_ascii_alphabetic() {
+                let c = self.next();
+                let word_index = if c.is_ascii_lowercase() {
+                    usize::from(c - b'a')
+                } else {
+                    has_word_substs = false;
+                    usize::from(c - b'A')
+                };
+                identifier.push_str(self.words.get(word_index)?);
+            }
+            if self.next_if(b'0') {
+                break;
+            }
+            let num_chars = usize::try_from(self.demangle_natural()?).ok()?;
+            if num_chars == 0 {
+                return None;
+            }
+            if is_punycoded {
+                self.next_if(b'_');
+            }
+            let end = self.pos.checked_add(num_chars)?;
+            let slice = std::str::from_utf8(text.get(self.pos..end)?).ok()?;
+            if is_punycoded {
+                identifier.push_str(&decode_punycode(slice)?);
+            } else {
+                identifier.push_str(slice);
+                self.add_words(slice);
+            }
+            self.pos = end;
+            if !has_word_substs {
+                break;
+            }
+        }
+        if identifier.is_empty() {
+            return None;
+        }
+        let node = self.create_node(Kind::Identifier, identifier, 0);
+        self.add_substitution(node)
+    }
+
+    /// Records the words of an identifier, which later identifiers can refer
+    /// to by index.
+    fn add_words(&mut self, identifier: &str) {
+        let bytes = identifier.as_bytes();
+        let mut word_start = None;
+        for idx in 0..=bytes.len() {
+            let c = bytes.get(idx).copied().unwrap_or(0);
+            if let Some(start) = word_start {
+                if is_word_end(c, bytes[idx - 1]) {
+                    if idx - start >= 2 && self.words.len() < MAX_NUM_WORDS {
+                        let word = String::from_utf8_lossy(&bytes[start..idx]).into_owned();
+                        self.words.push(word);
+                    }
+                    word_start = None;
+                }
+            }
+            if word_start.is_none() && is_word_start(c) {
+                word_start = Some(idx);
+            }
+        }
+    }
+
+    fn demangle_multi_substitutions(&mut self) -> Option<NodeId> {
+        let mut repeat_count = None;
+        loop {
+            let c = self.next();
+            match c {
+                b'a'..=b'z' => {
+                    let node =
+                        self.push_multi_substitutions(repeat_count, usize::from(c - b'a'))?;
+                    self.push(node);
+                    repeat_count = None;
+                }
+                b'A'..=b'Z' => {
+                    return self.push_multi_substitutions(repeat_count, usize::from(c - b'A'));
+                }
+                b'_' => {
+                    let index = match repeat_count {
+                        Some(count) => count.checked_add(27)?,
+                        None => 26,
+                    };
+                    return self.substitutions.get(index).copied();
+                }
+                _ => {
+                    self.push_back();
+                    repeat_count = Some(usize::try_from(self.demangle_natural()?).ok()?);
+                }
+            }
+        }
+    }
+
+    fn push_multi_substitutions(
+        &mut self,
+        repeat_count: Option<usize>,
+        index: usize,
+    ) -> Option<NodeId> {
+        let node = *self.substitutions.get(index)?;
+        let count = repeat_count.unwrap_or(1);
+        if count > MAX_REPEAT_COUNT {
+            return None;
+        }
+        for _ in 1..count {
+            self.push(node);
+        }
+        Some(node)
+    }
+
+    fn demangle_standard_substitution(&mut self) -> Option<NodeId> {
+        match self.next() {
+            b'o' => self.create_text(Kind::Module, "__ObjC"),
+            b'C' => self.create_text(Kind::Module, "__C"),
+            b'g' => {
+                let wrapped = self.pop_kind(Kind::Type);
+                let optional = self.create_swift_type(Kind::Enum, "Optional");
+                let args = self.create_with_children(Kind::TypeList, &[wrapped]);
+                let bound = self.create_with_children(Kind::BoundGenericEnum, &[optional, args]);
+                let ty = self.create_type(bound);
+                self.add_substitution(ty)
+            }
+            _ => {
+                self.push_back();
+                let repeat_count = if self.peek().is_ascii_digit() {
+                    usize::try_from(self.demangle_natural()?).ok()?
+                } else {
+                    1
+                };
+                if repeat_count > MAX_REPEAT_COUNT {
+                    return None;
+                }
+                let concurrency = self.next_if(b'c');
+                let c = self.next();
+                let node = self.create_standard_substitution(c, concurrency)?;
+                for _ in 1..repeat_count {
+                    self.push(node);
+                }
+                Some(node)
+            }
+        }
+    }
+
+    fn create_standard_substitution(&mut self, c: u8, concurrency: bool) -> Option<NodeId> {
+        use Kind::{Class, Enum, Protocol, Structure};
+        let (kind, name) = if concurrency {
+            match c {
+                b'A' => (Protocol, "Actor"),
+                b'C' => (Structure, "CheckedContinuation"),
+                b'c' => (Structure, "UnsafeContinuation"),
+                b'E' => (Structure, "CancellationError"),
+                b'e' => (Structure, "UnownedSerialExecutor"),
+                b'F' => (Protocol, "Executor"),
+                b'f' => (Protocol, "SerialExecutor"),
+                b'G' => (Structure, "TaskGroup"),
+                b'g' => (Structure, "ThrowingTaskGroup"),
+                b'h' => (Protocol, "TaskExecutor"),
+                b'I' => (Protocol, "AsyncIteratorProtocol"),
+                b'i' => (Protocol, "AsyncSequence"),
+                b'J' => (Structure, "UnownedJob"),
+                b'M' => (Class, "MainActor"),
+                b'P' => (Structure, "TaskPriority"),
+                b'S' => (Structure, "AsyncStream"),
+                b's' => (Structure, "AsyncThrowingStream"),
+                b'T' => (Structure, "Task"),
+                b't' => (Structure, "UnsafeCurrentTask"),
+                _ => return None,
+            }
+        } else {
+            match c {
+                b'A' => (Structure, "AutoreleasingUnsafeMutablePointer"),
+                b'a' => (Structure, "Array"),
+                b'b' => (Structure, "Bool"),
+                b'D' => (Structure, "Dictionary"),
+                b'd' => (Structure, "Double"),
+                b'f' => (Structure, "Float"),
+                b'h' => (Structure, "Set"),
+                b'I' => (Structure, "DefaultIndices"),
+                b'i' => (Structure, "Int"),
+                b'J' => (Structure, "Character"),
+                b'N' => (Structure, "ClosedRange"),
+                b'n' => (Structure, "Range"),
+                b'O' => (Structure, "ObjectIdentifier"),
+                b'P' => (Structure, "UnsafePointer"),
+                b'p' => (Structure, "UnsafeMutablePointer"),
+                b'R' => (Structure, "UnsafeBufferPointer"),
+                b'r' => (Structure, "UnsafeMutableBufferPointer"),
+                b'S' => (Structure, "String"),
+                b's' => (Structure, "Substring"),
+                b'u' => (Structure, "UInt"),
+                b'V' => (Structure, "UnsafeRawPointer"),
+                b'v' => (Structure, "UnsafeMutableRawPointer"),
+                b'W' => (Structure, "UnsafeRawBufferPointer"),
+                b'w' => (Structure, "UnsafeMutableRawBufferPointer"),
+                b'q' => (Enum, "Optional"),
+                b'B' => (Protocol, "BinaryFloatingPoint"),
+                b'E' => (Protocol, "Encodable"),
+                b'e' => (Protocol, "Decodable"),
+                b'F' => (Protocol, "FloatingPoint"),
+                b'G' => (Protocol, "RandomNumberGenerator"),
+                b'H' => (Protocol, "Hashable"),
+                b'j' => (Protocol, "Numeric"),
+                b'K' => (Protocol, "BidirectionalCollection"),
+                b'k' => (Protocol, "RandomAccessCollection"),
+                b'L' => (Protocol, "Comparable"),
+                b'l' => (Protocol, "Collection"),
+                b'M' => (Protocol, "MutableCollection"),
+                b'm' => (Protocol, "RangeReplaceableCollection"),
+                b'Q' => (Protocol, "Equatable"),
+                b'T' => (Protocol, "Sequence"),
+                b't' => (Protocol, "IteratorProtocol"),
+                b'U' => (Protocol, "UnsignedInteger"),
+                b'X' => (Protocol, "RangeExpression"),
+                b'x' => (Protocol, "Strideable"),
+                b'Y' => (Protocol, "RawRepresentable"),
+                b'y' => (Protocol, "StringProtocol"),
+                b'Z' => (Protocol, "SignedInteger"),
+                b'z' => (Protocol, "BinaryInteger"),
+                _ => return None,
+            }
+        };
+        self.create_swift_type(kind, name)
+    }
+
+    fn demangle_builtin_type(&mut self) -> Option<NodeId> {
+        let name = match self.next() {
+            b'b' => "Builtin.BridgeObject".to_string(),
+            b'B' => "Builtin.UnsafeValueBuffer".to_string(),
+            b'c' => "Builtin.RawUnsafeContinuation".to_string(),
+            b'D' => "Builtin.DefaultActorStorage".to_string(),
+            b'd' => "Builtin.NonDefaultDistributedActorStorage".to_string(),
+            b'e' => "Builtin.Executor".to_string(),
+            b'f' => format!("Builtin.FPIEEE{}", self.demangle_builtin_size()?),
+            b'i' => format!("Builtin.Int{}", self.demangle_builtin_size()?),
+            b'I' => "Builtin.IntLiteral".to_string(),
+            b'j' => "Builtin.Job".to_string(),
+            b'O' => "Builtin.UnknownObject".to_string(),
+            b'o' => "Builtin.NativeObject".to_string(),
+            b'p' => "Builtin.RawPointer".to_string(),
+            b't' => "Builtin.SILToken".to_string(),
+            b'v' => {
+                let elements = self.demangle_builtin_size()?;
+                let element_type = self.pop_type_and_get_child()?;
+                if self.kind(element_type) != Kind::BuiltinTypeName {
+                    return None;
+                }
+                let element_name = self.nodes[element_type].text.strip_prefix("Builtin.")?;
+                format!("Builtin.Vec{}x{}", elements, element_name)
+            }
+            b'w' => "Builtin.Word".to_string(),
+            _ => return None,
+        };
+        let node = self.create_node(Kind::BuiltinTypeName, name, 0);
+        self.create_type(node)
+    }
+
+    fn demangle_builtin_size(&mut self) -> Option<u64> {
+        let size = self.demangle_index()?.checked_sub(1)?;
+        if size == 0 || size > 4096 {
+            None
+        } else {
+            Some(size)
+        }
+    }
+
+    // Nominal types and contexts
+
+    fn demangle_any_generic_type(&mut self, kind: Kind) -> Option<NodeId> {
+        let name = self.pop_if(Kind::is_decl_name);
+        let context = self.pop_context();
+        let nominal = self.create_with_children(kind, &[context, name]);
+        let ty = self.create_type(nominal);
+        self.add_substitution(ty)
+    }
+
+    fn demangle_extension_context(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let module = self.pop_module();
+        let ty = self.pop_type_and_get_any_generic();
+        let extension = self.create_with_children(Kind::Extension, &[module, ty])?;
+        self.add_optional(extension, signature);
+        Some(extension)
+    }
+
+    fn demangle_local_identifier(&mut self) -> Option<NodeId> {
+        if self.next_if(b'L') {
+            let discriminator = self.pop_kind(Kind::Identifier);
+            let name = self.pop_if(Kind::is_decl_name);
+            return self.create_with_children(Kind::PrivateDeclName, &[discriminator, name]);
+        }
+        if self.next_if(b'l') {
+            let discriminator = self.pop_kind(Kind::Identifier);
+            return self.create_with_children(Kind::PrivateDeclName, &[discriminator]);
+        }
+        if matches!(self.peek(), b'a'..=b'j' | b'A'..=b'J') {
+            let related_kind = char::from(self.next()).to_string();
+            let kind = self.create_text(Kind::Identifier, &related_kind);
+            let name = self.pop();
+            return self.create_with_children(Kind::RelatedEntityDeclName, &[kind, name]);
+        }
+        let discriminator = self.demangle_index_as_node();
+        let name = self.pop_if(Kind::is_decl_name);
+        self.create_with_children(Kind::LocalDeclName, &[discriminator, name])
+    }
+
+    fn demangle_operator_identifier(&mut self) -> Option<NodeId> {
+        const OPERATOR_CHARS: &[u8; 26] = b"& @/= >    <*!|+?%-~   ^ .";
+        let identifier = self.pop_kind(Kind::Identifier)?;
+        let mut operator = String::new();
+        for c in self.nodes[identifier].text.chars() {
+            if !c.is_ascii() {
+                operator.push(c);
+                continue;
+            }
+            if !c.is_ascii_lowercase() {
+                return None;
+            }
+            let o = OPERATOR_CHARS[usize::from(c as u8 - b'a')];
+            if o == b' ' {
+                return None;
+            }
+            operator.push(char::from(o));
+        }
+        let kind = match self.next() {
+            b'i' => Kind::InfixOperator,
+            b'p' => Kind::PrefixOperator,
+            b'P' => Kind::PostfixOperator,
+            _ => return None,
+        };
+        self.create_node(kind, operator, 0)
+    }
+
+    fn demangle_bound_generic_type(&mut self) -> Option<NodeId> {
+        let mut type_lists = Vec::new();
+        loop {
+            let list = self.create(Kind::TypeList)?;
+            while let Some(ty) = self.pop_kind(Kind::Type) {
+                self.add_child(list, ty);
+            }
+            self.nodes[list].children.reverse();
+            type_lists.push(list);
+            if self.pop_kind(Kind::EmptyList).is_some() {
+                break;
+            }
+            self.pop_kind(Kind::FirstElementMarker)?;
+        }
+        let nominal = self.pop_type_and_get_any_generic()?;
+        let bound = self.demangle_bound_generic_args(nominal, &type_lists, 0);
+        let ty = self.create_type(bound);
+        self.add_substitution(ty)
+    }
+
+    /// Applies the generic argument lists to a nominal type and its parent
+    /// contexts. The first list belongs to the innermost type.
+    fn demangle_bound_generic_args(
+        &mut self,
+        nominal: NodeId,
+        type_lists: &[NodeId],
+        mut index: usize,
+    ) -> Option<NodeId> {
+        let args = *type_lists.get(index)?;
+        let context = self.child(nominal, 0)?;
+        let nominal_kind = self.kind(nominal);
+        let consumes_generic_args = !matches!(
+            nominal_kind,
+            Kind::Variable
+                | Kind::Subscript
+                | Kind::ImplicitClosure
+                | Kind::ExplicitClosure
+                | Kind::DefaultArgumentInitializer
+                | Kind::Initializer
+                | Kind::PropertyWrapperBackingInitializer
+                | Kind::PropertyWrapperInitFromProjectedValue
+                | Kind::Static
+        );
+        if consumes_generic_args {
+            index += 1;
+        }
+        let mut nominal = nominal;
+        if index < type_lists.len() {
+            let bound_parent = if self.kind(context) == Kind::Extension {
+                let extended = self.child(context, 1)?;
+                let bound = self.demangle_bound_generic_args(extended, type_lists, index);
+                let module = self.child(context, 0);
+                let extension = self.create_with_children(Kind::Extension, &[module, bound])?;
+                let signature = self.child(context, 2);
+                self.add_optional(extension, signature);
+                extension
+            } else {
+                self.demangle_bound_generic_args(context, type_lists, index)?
+            };
+            let rebuilt = self.create(nominal_kind)?;
+            let mut children = vec![bound_parent];
+            children.extend_from_slice(&self.nodes[nominal].children[1..]);
+            self.nodes[rebuilt].children = children;
+            nominal = rebuilt;
+        }
+        if !consumes_generic_args || self.num_children(args) == 0 {
+            return Some(nominal);
+        }
+        let kind = match nominal_kind {
+            Kind::Class => Kind::BoundGenericClass,
+            Kind::Structure => Kind::BoundGenericStructure,
+            Kind::Enum => Kind::BoundGenericEnum,
+            Kind::Protocol | Kind::TypeAlias => Kind::BoundGenericOther,
+            _ => return None,
+        };
+        let ty = self.create_type(Some(nominal));
+        self.create_with_children(kind, &[ty, Some(args)])
+    }
+
+    // Functions, variables and other entities
+
+    fn demangle_plain_function(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let mut ty = self.pop_function_type("")?;
+        let labels = self.pop_function_param_labels(ty);
+        if let Some(signature) = signature {
+            let generic =
+                self.create_with_children(Kind::DependentGenericType, &[Some(signature), Some(ty)]);
+            ty = self.create_type(generic)?;
+        }
+        let name = self.pop_if(Kind::is_decl_name);
+        let context = self.pop_context();
+        match labels {
+            Some(labels) => {
+                self.create_with_children(Kind::Function, &[context, name, Some(labels), Some(ty)])
+            }
+            None => self.create_with_children(Kind::Function, &[context, name, Some(ty)]),
+        }
+    }
+
+    fn pop_function_type(&mut self, attribute: &str) -> Option<NodeId> {
+        let function = self.create_node(Kind::FunctionType, attribute.to_string(), 0)?;
+        let global_actor = self.pop_kind(Kind::GlobalActorFunctionType);
+        self.add_optional(function, global_actor);
+        let throws = self
+            .pop_if(|kind| matches!(kind, Kind::ThrowsAnnotation | Kind::TypedThrowsAnnotation));
+        self.add_optional(function, throws);
+        let concurrent = self.pop_kind(Kind::ConcurrentFunctionType);
+        self.add_optional(function, concurrent);
+        let asynchronous = self.pop_kind(Kind::AsyncAnnotation);
+        self.add_optional(function, asynchronous);
+        let params = self.pop_function_params(Kind::ArgumentTuple);
+        self.add_required(Some(function), params)?;
+        let result = self.pop_function_params(Kind::ReturnType);
+        self.add_required(Some(function), result)?;
+        self.create_type(Some(function))
+    }
+
+    fn pop_function_params(&mut self, kind: Kind) -> Option<NodeId> {
+        let params = if self.pop_kind(Kind::EmptyList).is_some() {
+            let tuple = self.create(Kind::Tuple);
+            self.create_type(tuple)
+        } else {
+            self.pop_kind(Kind::Type)
+        };
+        self.create_with_children(kind, &[params])
+    }
+
+    /// Pops the argument labels of a function, which are mangled as
+    /// identifiers (or `_` for unlabeled arguments) before the function type.
+    /// In the Swift 4 mangling, labels are part of the parameter tuple.
+    fn pop_function_param_labels(&mut self, ty: NodeId) -> Option<NodeId> {
+        if !self.old_mangling && self.pop_kind(Kind::EmptyList).is_some() {
+            return self.create(Kind::LabelList);
+        }
+        if self.kind(ty) != Kind::Type {
+            return None;
+        }
+        let mut function = self.child(ty, 0)?;
+        if self.kind(function) == Kind::DependentGenericType {
+            function = self.child(self.child(function, 1)?, 0)?;
+        }
+        if self.kind(function) != Kind::FunctionType {
+            return None;
+        }
+        let argument_tuple = self.nodes[function]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.kind(child) == Kind::ArgumentTuple)?;
+        let params = self.child(self.child(argument_tuple, 0)?, 0)?;
+        let is_tuple = self.kind(params) == Kind::Tuple;
+        let num_params = if is_tuple {
+            self.num_children(params)
+        } else {
+            1
+        };
+        if num_params == 0 {
+            return None;
+        }
+        if self.old_mangling && !is_tuple {
+            return self.create(Kind::LabelList);
+        }
+        let label_list = self.create(Kind::LabelList)?;
+        let mut has_labels = false;
+        for index in 0..num_params {
+            let label = if self.old_mangling {
+                self.take_tuple_element_label(params, index)?
+            } else {
+                self.pop_if(|kind| matches!(kind, Kind::Identifier | Kind::FirstElementMarker))?
+            };
+            has_labels |= self.kind(label) != Kind::FirstElementMarker;
+            self.add_child(label_list, label);
+        }
+        if !has_labels {
+            return self.create(Kind::LabelList);
+        }
+        if !self.old_mangling {
+            self.nodes[label_list].children.reverse();
+        }
+        Some(label_list)
+    }
+
+    fn take_tuple_element_label(&mut self, params: NodeId, index: usize) -> Option<NodeId> {
+        let param = self.child(params, index)?;
+        let position = self.nodes[param]
+            .children
+            .iter()
+            .position(|&child| self.kind(child) == Kind::TupleElementName);
+        match position {
+            Some(position) => {
+                let name = self.nodes[param].children.remove(position);
+                let text = self.nodes[name].text.clone();
+                self.create_node(Kind::Identifier, text, 0)
+            }
+            None => self.create(Kind::FirstElementMarker),
+        }
+    }
+
+    fn demangle_function_entity(&mut self) -> Option<NodeId> {
+        enum Args {
+            None,
+            TypeAndMaybePrivateName,
+            TypeAndIndex,
+            Index,
+        }
+        let (args, kind) = match self.next() {
+            b'D' => (Args::None, Kind::Deallocator),
+            b'd' => (Args::None, Kind::Destructor),
+            b'E' => (Args::None, Kind::IVarDestroyer),
+            b'e' => (Args::None, Kind::IVarInitializer),
+            b'i' => (Args::None, Kind::Initializer),
+            b'C' => (Args::TypeAndMaybePrivateName, Kind::Allocator),
+            b'c' => (Args::TypeAndMaybePrivateName, Kind::Constructor),
+            b'U' => (Args::TypeAndIndex, Kind::ExplicitClosure),
+            b'u' => (Args::TypeAndIndex, Kind::ImplicitClosure),
+            b'A' => (Args::Index, Kind::DefaultArgumentInitializer),
+            b'P' => (Args::None, Kind::PropertyWrapperBackingInitializer),
+            b'W' => (Args::None, Kind::PropertyWrapperInitFromProjectedValue),
+            _ => return None,
+        };
+        let mut name_or_index = None;
+        let mut param_type = None;
+        let mut labels = None;
+        match args {
+            Args::None => {}
+            Args::TypeAndMaybePrivateName => {
+                name_or_index = self.pop_kind(Kind::PrivateDeclName);
+                let ty = self.pop_kind(Kind::Type)?;
+                param_type = Some(ty);
+                labels = self.pop_function_param_labels(ty);
+            }
+            Args::TypeAndIndex => {
+                name_or_index = Some(self.demangle_index_as_node()?);
+                param_type = Some(self.pop_kind(Kind::Type)?);
+            }
+            Args::Index => {
+                name_or_index = Some(self.demangle_index_as_node()?);
+            }
+        }
+        let context = self.pop_context();
+        let entity = self.create_with_children(kind, &[context])?;
+        match args {
+            Args::None => {}
+            Args::Index => self.add_optional(entity, name_or_index),
+            Args::TypeAndMaybePrivateName => {
+                self.add_optional(entity, labels);
+                self.add_optional(entity, param_type);
+                self.add_optional(entity, name_or_index);
+            }
+            Args::TypeAndIndex => {
+                self.add_optional(entity, name_or_index);
+                self.add_optional(entity, param_type);
+            }
+        }
+        Some(entity)
+    }
+
+    fn demangle_entity(&mut self, kind: Kind) -> Option<NodeId> {
+        let ty = self.pop_kind(Kind::Type);
+        let labels = match ty {
+            Some(ty) => self.pop_function_param_labels(ty),
+            None => None,
+        };
+        let name = self.pop_if(Kind::is_decl_name);
+        let context = self.pop_context();
+        match labels {
+            Some(labels) => self.create_with_children(kind, &[context, name, Some(labels), ty]),
+            None => self.create_with_children(kind, &[context, name, ty]),
+        }
+    }
+
+    fn demangle_variable(&mut self) -> Option<NodeId> {
+        let variable = self.demangle_entity(Kind::Variable)?;
+        self.demangle_accessor(variable)
+    }
+
+    fn demangle_subscript(&mut self) -> Option<NodeId> {
+        let private_name = self.pop_kind(Kind::PrivateDeclName);
+        let ty = self.pop_kind(Kind::Type)?;
+        let labels = self.pop_function_param_labels(ty);
+        let context = self.pop_context();
+        let subscript = self.create_with_children(Kind::Subscript, &[context])?;
+        self.add_optional(subscript, labels);
+        self.add_child(subscript, ty);
+        self.add_optional(subscript, private_name);
+        self.demangle_accessor(subscript)
+    }
+
+    fn demangle_accessor(&mut self, storage: NodeId) -> Option<NodeId> {
+        let name = match self.next() {
+            b'm' => "materializeForSet",
+            b's' => "setter",
+            b'g' | b'G' => "getter",
+            b'w' => "willset",
+            b'W' => "didset",
+            b'r' => "read",
+            b'M' => "modify",
+            b'i' => "init",
+            b'a' => match self.next() {
+                b'O' => "owningMutableAddressor",
+                b'o' => "nativeOwningMutableAddressor",
+                b'P' => "nativePinningMutableAddressor",
+                b'u' => "unsafeMutableAddressor",
+                _ => return None,
+            },
+            b'l' => match self.next() {
+                b'O' => "owningAddressor",
+                b'o' => "nativeOwningAddressor",
+                b'p' => "nativePinningAddressor",
+                b'u' => "unsafeAddressor",
+                _ => return None,
+            },
+            // A pseudo-accessor which refers to the storage itself.
+            b'p' => return Some(storage),
+            _ => return None,
+        };
+        let accessor = self.create_text(Kind::Accessor, name)?;
+        self.add_child(accessor, storage);
+        Some(accessor)
+    }
+
+    // Types
+
+    fn pop_tuple(&mut self) -> Option<NodeId> {
+        let root = self.create(Kind::Tuple)?;
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                let element = self.create(Kind::TupleElement)?;
+                let variadic = self.pop_kind(Kind::VariadicMarker);
+                self.add_optional(element, variadic);
+                if let Some(identifier) = self.pop_kind(Kind::Identifier) {
+                    let text = self.nodes[identifier].text.clone();
+                    let name = self.create_node(Kind::TupleElementName, text, 0)?;
+                    self.add_child(element, name);
+                }
+                let ty = self.pop_kind(Kind::Type)?;
+                self.add_child(element, ty);
+                self.add_child(root, element);
+                if first {
+                    break;
+                }
+            }
+            self.nodes[root].children.reverse();
+        }
+        self.create_type(Some(root))
+    }
+
+    fn demangle_protocol_list(&mut self) -> Option<NodeId> {
+        let list = self.create(Kind::TypeList)?;
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                let protocol = self.pop_protocol()?;
+                self.add_child(list, protocol);
+                if first {
+                    break;
+                }
+            }
+            self.nodes[list].children.reverse();
+        }
+        self.create_with_children(Kind::ProtocolList, &[Some(list)])
+    }
+
+    fn demangle_generic_type(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let ty = self.pop_kind(Kind::Type);
+        let generic = self.create_with_children(Kind::DependentGenericType, &[signature, ty]);
+        self.create_type(generic)
+    }
+
+    fn demangle_archetype(&mut self) -> Option<NodeId> {
+        let ty = match self.next() {
+            b'a' => {
+                let name = self.pop_kind(Kind::Identifier);
+                let archetype = self.pop_type_and_get_child();
+                let associated =
+                    self.create_with_children(Kind::AssociatedTypeRef, &[archetype, name]);
+                self.create_type(associated)
+            }
+            b'r' => {
+                let opaque = self.create(Kind::OpaqueReturnType);
+                return self.create_type(opaque);
+            }
+            b'x' => self.demangle_associated_type_simple(None),
+            b'X' => self.demangle_associated_type_compound(None),
+            b'y' => {
+                let base = self.demangle_generic_param_index()?;
+                self.demangle_associated_type_simple(Some(base))
+            }
+            b'Y' => {
+                let base = self.demangle_generic_param_index()?;
+                self.demangle_associated_type_compound(Some(base))
+            }
+            b'z' => {
+                let base = self.dependent_generic_param_type(0, 0)?;
+                self.demangle_associated_type_simple(Some(base))
+            }
+            b'Z' => {
+                let base = self.dependent_generic_param_type(0, 0)?;
+                self.demangle_associated_type_compound(Some(base))
+            }
+            _ => return None,
+        };
+        self.add_substitution(ty)
+    }
+
+    fn pop_assoc_type_name(&mut self) -> Option<NodeId> {
+        let protocol = self.pop_kind(Kind::Type);
+        if let Some(protocol) = protocol {
+            if !self.is_protocol_type(protocol) {
+                return None;
+            }
+        }
+        let identifier = self.pop_kind(Kind::Identifier);
+        let associated = self.change_kind(identifier, Kind::DependentAssociatedTypeRef)?;
+        self.add_optional(associated, protocol);
+        Some(associated)
+    }
+
+    fn demangle_associated_type_simple(&mut self, base: Option<NodeId>) -> Option<NodeId> {
+        let name = self.pop_assoc_type_name();
+        let base = match base {
+            Some(param) => self.create_type(Some(param)),
+            None => self.pop_kind(Kind::Type),
+        };
+        let member = self.create_with_children(Kind::DependentMemberType, &[base, name]);
+        self.create_type(member)
+    }
+
+    fn demangle_associated_type_compound(&mut self, base: Option<NodeId>) -> Option<NodeId> {
+        let mut names = Vec::new();
+        loop {
+            let first = self.pop_kind(Kind::FirstElementMarker).is_some();
+            names.push(self.pop_assoc_type_name()?);
+            if first {
+                break;
+            }
+        }
+        let mut base = match base {
+            Some(param) => self.create_type(Some(param)),
+            None => self.pop_kind(Kind::Type),
+        };
+        while let Some(name) = names.pop() {
+            let member = self.create_with_children(Kind::DependentMemberType, &[base, Some(name)]);
+            base = self.create_type(member);
+        }
+        base
+    }
+
+    fn demangle_generic_signature(&mut self, has_param_counts: bool) -> Option<NodeId> {
+        let signature = self.create(Kind::DependentGenericSignature)?;
+        if has_param_counts {
+            while !self.next_if(b'l') {
+                let count = if self.next_if(b'z') {
+                    0
+                } else {
+                    self.demangle_index()?.checked_add(1)?
+                };
+                let node = self.create_index(Kind::DependentGenericParamCount, count)?;
+                self.add_child(signature, node);
+            }
+        } else {
+            let node = self.create_index(Kind::DependentGenericParamCount, 1)?;
+            self.add_child(signature, node);
+        }
+        let num_counts = self.num_children(signature);
+        while let Some(requirement) = self.pop_if(Kind::is_requirement) {
+            self.add_child(signature, requirement);
+        }
+        self.nodes[signature].children[num_counts..].reverse();
+        Some(signature)
+    }
+
+    fn demangle_generic_requirement(&mut self) -> Option<NodeId> {
+        #[derive(PartialEq)]
+        enum TypeKind {
+            Generic,
+            Assoc,
+            CompoundAssoc,
+            Substitution,
+        }
+        enum ConstraintKind {
+            Protocol,
+            BaseClass,
+            SameType,
+            Layout,
+        }
+        let (constraint, type_kind) = match self.next() {
+            b'c' => (ConstraintKind::BaseClass, TypeKind::Assoc),
+            b'C' => (ConstraintKind::BaseClass, TypeKind::CompoundAssoc),
+            b'b' => (ConstraintKind::BaseClass, TypeKind::Generic),
+            b'B' => (ConstraintKind::BaseClass, TypeKind::Substitution),
+            b't' => (ConstraintKind::SameType, TypeKind::Assoc),
+            b'T' => (ConstraintKind::SameType, TypeKind::CompoundAssoc),
+            b's' => (ConstraintKind::SameType, TypeKind::Generic),
+            b'S' => (ConstraintKind::SameType, TypeKind::Substitution),
+            b'm' => (ConstraintKind::Layout, TypeKind::Assoc),
+            b'M' => (ConstraintKind::Layout, TypeKind::CompoundAssoc),
+            b'l' => (ConstraintKind::Layout, TypeKind::Generic),
+            b'L' => (ConstraintKind::Layout, TypeKind::Substitution),
+            b'p' => (ConstraintKind::Protocol, TypeKind::Assoc),
+            b'P' => (ConstraintKind::Protocol, TypeKind::CompoundAssoc),
+            b'Q' => (ConstraintKind::Protocol, TypeKind::Substitution),
+            _ => {
+                self.push_back();
+                (ConstraintKind::Protocol, TypeKind::Generic)
+            }
+        };
+        let constrained = match type_kind {
+            TypeKind::Generic => {
+                let param = self.demangle_generic_param_index();
+                self.create_type(param)
+            }
+            TypeKind::Assoc | TypeKind::CompoundAssoc => {
+                let param = self.demangle_generic_param_index()?;
+                let ty = if type_kind == TypeKind::Assoc {
+                    self.demangle_associated_type_simple(Some(param))
+                } else {
+                    self.demangle_associated_type_compound(Some(param))
+                };
+                self.add_substitution(ty)
+            }
+            TypeKind::Substitution => self.pop_kind(Kind::Type),
+        };
+        match constraint {
+            ConstraintKind::Protocol => {
+                let protocol = self.pop_protocol();
+                self.create_with_children(Kind::ConformanceRequirement, &[constrained, protocol])
+            }
+            ConstraintKind::BaseClass => {
+                let class = self.pop_kind(Kind::Type);
+                self.create_with_children(Kind::ConformanceRequirement, &[constrained, class])
+            }
+            ConstraintKind::SameType => {
+                let ty = self.pop_kind(Kind::Type);
+                self.create_with_children(Kind::SameTypeRequirement, &[constrained, ty])
+            }
+            ConstraintKind::Layout => {
+                let (name, num_args) = match self.next() {
+                    b'U' => ("_UnknownLayout", 0),
+                    b'R' => ("_RefCountedObject", 0),
+                    b'N' => ("_NativeRefCountedObject", 0),
+                    b'C' => ("AnyObject", 0),
+                    b'D' => ("_NativeClass", 0),
+                    b'T' => ("_Trivial", 0),
+                    b'B' => ("_BridgeObject", 0),
+                    b'E' => ("_Trivial", 2),
+                    b'e' => ("_Trivial", 1),
+                    b'M' => ("_TrivialAtMost", 2),
+                    b'm' => ("_TrivialAtMost", 1),
+                    b'S' => ("_TrivialStride", 1),
+                    _ => return None,
+                };
+                let requirement =
+                    self.create_with_children(Kind::LayoutRequirement, &[constrained])?;
+                self.nodes[requirement].text = name.to_string();
+                for _ in 0..num_args {
+                    let arg = self.demangle_index_as_node()?;
+                    self.add_child(requirement, arg);
+                }
+                Some(requirement)
+            }
+        }
+    }
+
+    fn demangle_special_type(&mut self) -> Option<NodeId> {
+        match self.next() {
+            b'E' | b'U' => self.pop_function_type(""),
+            b'A' | b'K' => self.pop_function_type("@autoclosure "),
+            b'f' => self.pop_function_type("@convention(thin) "),
+            b'L' => self.pop_function_type("@escaping @convention(block) "),
+            b'B' => self.pop_function_type("@convention(block) "),
+            b'C' => self.pop_function_type("@convention(c) "),
+            b'o' => self.demangle_type_attribute("unowned "),
+            b'u' => self.demangle_type_attribute("unowned(unsafe) "),
+            b'w' => self.demangle_type_attribute("weak "),
+            b'b' => self.demangle_type_attribute("@box "),
+            b'D' => {
+                let ty = self.pop_kind(Kind::Type);
+                let dynamic_self = self.create_with_children(Kind::DynamicSelf, &[ty]);
+                self.create_type(dynamic_self)
+            }
+            c @ (b'M' | b'm') => {
+                let representation = match self.next() {
+                    b't' => "@thin ",
+                    b'T' => "@thick ",
+                    b'o' => "@objc_metatype ",
+                    _ => return None,
+                };
+                let ty = self.pop_kind(Kind::Type);
+                let kind = if c == b'M' {
+                    Kind::Metatype
+                } else {
+                    Kind::ExistentialMetatype
+                };
+                let metatype = self.create_with_children(kind, &[ty])?;
+                self.nodes[metatype].text = representation.to_string();
+                self.create_type(Some(metatype))
+            }
+            b'p' => {
+                let ty = self.pop_kind(Kind::Type);
+                let metatype = self.create_with_children(Kind::ExistentialMetatype, &[ty]);
+                self.create_type(metatype)
+            }
+            b'c' => {
+                let superclass = self.pop_kind(Kind::Type);
+                let protocols = self.demangle_protocol_list();
+                let list = self
+                    .create_with_children(Kind::ProtocolListWithClass, &[protocols, superclass]);
+                self.create_type(list)
+            }
+            b'l' => {
+                let protocols = self.demangle_protocol_list();
+                let list = self.create_with_children(Kind::ProtocolListWithAnyObject, &[protocols]);
+                self.create_type(list)
+            }
+            b'e' => {
+                let error = self.create(Kind::ErrorType);
+                self.create_type(error)
+            }
+            _ => None,
+        }
+    }
+
+    fn demangle_type_annotation(&mut self) -> Option<NodeId> {
+        match self.next() {
+            b'a' => self.create(Kind::AsyncAnnotation),
+            b'b' => self.create(Kind::ConcurrentFunctionType),
+            b'c' => {
+                let actor = self.pop_kind(Kind::Type);
+                self.create_with_children(Kind::GlobalActorFunctionType, &[actor])
+            }
+            b'K' => {
+                let error = self.pop_kind(Kind::Type);
+                self.create_with_children(Kind::TypedThrowsAnnotation, &[error])
+            }
+            b'i' => self.demangle_type_attribute("isolated "),
+            b't' => self.demangle_type_attribute("_const "),
+            b'u' => self.demangle_type_attribute("sending "),
+            _ => None,
+        }
+    }
+
+    /// Demangles a SIL function type, as found in reabstraction thunks.
+    fn demangle_impl_function_type(&mut self) -> Option<NodeId> {
+        let function = self.create(Kind::ImplFunctionType)?;
+        if self.peek() == b's' || self.peek() == b'I' {
+            // Pattern and invocation substitutions are not supported.
+            return None;
+        }
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        if signature.is_some() {
+            self.next_if(b'P');
+        }
+        if self.next_if(b'e') {
+            self.add_impl_attribute(function, "@escaping")?;
+        }
+        if self.next_if(b'A') {
+            self.add_impl_attribute(function, "@isolated(any)")?;
+        }
+        let callee = match self.next() {
+            b'y' => "@callee_unowned",
+            b'g' => "@callee_guaranteed",
+            b'x' => "@callee_owned",
+            b't' => "@convention(thin)",
+            _ => return None,
+        };
+        self.add_impl_attribute(function, callee)?;
+        let convention = match self.peek() {
+            b'B' => Some("@convention(block)"),
+            b'C' => Some("@convention(c)"),
+            b'M' => Some("@convention(method)"),
+            b'O' => Some("@convention(objc_method)"),
+            b'K' => Some("@convention(closure)"),
+            b'W' => Some("@convention(witness_method)"),
+            _ => None,
+        };
+        if let Some(convention) = convention {
+            self.next();
+            self.add_impl_attribute(function, convention)?;
+        }
+        if self.next_if(b'A') {
+            self.add_impl_attribute(function, "@yield_once")?;
+        } else if self.next_if(b'I') {
+            self.add_impl_attribute(function, "@yield_once_2")?;
+        } else if self.next_if(b'G') {
+            self.add_impl_attribute(function, "@yield_many")?;
+        }
+        if self.next_if(b'h') {
+            self.add_impl_attribute(function, "@Sendable")?;
+        }
+        if self.next_if(b'H') {
+            self.add_impl_attribute(function, "@async")?;
+        }
+        self.add_optional(function, signature);
+
+        let mut num_types = 0;
+        while let Some(param) = self.demangle_impl_param_convention(Kind::ImplParameter) {
+            self.add_child(function, param);
+            num_types += 1;
+        }
+        while let Some(result) = self.demangle_impl_result_convention(Kind::ImplResult) {
+            self.add_child(function, result);
+            num_types += 1;
+        }
+        while self.next_if(b'Y') {
+            let yield_result = self.demangle_impl_param_convention(Kind::ImplYield)?;
+            self.add_child(function, yield_result);
+            num_types += 1;
+        }
+        if self.next_if(b'z') {
+            let error = self.demangle_impl_result_convention(Kind::ImplErrorResult)?;
+            self.add_child(function, error);
+            num_types += 1;
+        }
+        if !self.next_if(b'_') {
+            return None;
+        }
+        let num_children = self.num_children(function);
+        for index in 0..num_types {
+            let ty = self.pop_kind(Kind::Type)?;
+            let target = self.nodes[function].children[num_children - index - 1];
+            self.add_child(target, ty);
+        }
+        self.create_type(Some(function))
+    }
+
+    fn add_impl_attribute(&mut self, function: NodeId, attribute: &str) -> Option<()> {
+        let node = self.create_text(Kind::ImplAttribute, attribute)?;
+        self.add_child(function, node);
+        Some(())
+    }
+
+    fn demangle_impl_param_convention(&mut self, kind: Kind) -> Option<NodeId> {
+        let convention = match self.next() {
+            b'i' => "@in",
+            b'c' => "@in_constant",
+            b'l' => "@inout",
+            b'b' => "@inout_aliasable",
+            b'n' => "@in_guaranteed",
+            b'X' => "@in_cxx",
+            b'x' => "@owned",
+            b'g' => "@guaranteed",
+            b'e' => "@deallocating",
+            b'y' => "@unowned",
+            b'v' => "@pack_owned",
+            b'p' => "@pack_guaranteed",
+            b'm' => "@pack_inout",
+            _ => {
+                self.push_back();
+                return None;
+            }
+        };
+        self.create_text(kind, convention)
+    }
+
+    fn demangle_impl_result_convention(&mut self, kind: Kind) -> Option<NodeId> {
+        let convention = match self.next() {
+            b'r' => "@out",
+            b'o' => "@owned",
+            b'd' => "@unowned",
+            b'u' => "@unowned_inner_pointer",
+            b'a' => "@autoreleased",
+            b'k' => "@pack_out",
+            _ => {
+                self.push_back();
+                return None;
+            }
+        };
+        self.create_text(kind, convention)
+    }
+
+    // Metadata, witnesses and thunks
+
+    fn demangle_metatype(&mut self) -> Option<NodeId> {
+        match self.next() {
+            b'a' => self.create_with_popped_type("type metadata accessor for "),
+            b'A' => self
+                .create_with_popped_conformance("reflection metadata associated type descriptor "),
+            b'B' => self.create_with_popped_type("reflection metadata builtin descriptor "),
+            b'c' => self.create_with_popped_conformance("protocol conformance descriptor for "),
+            b'C' => {
+                let ty = self.pop_type_and_get_any_generic();
+                self.create_with_children(
+                    Kind::Described("reflection metadata superclass descriptor "),
+                    &[ty],
+                )
+            }
+            b'D' => {
+                self.create_with_popped_type("demangling cache variable for type metadata for ")
+            }
+            b'f' => self.create_with_popped_type("full type metadata for "),
+            b'F' => self.create_with_popped_type("reflection metadata field descriptor "),
+            b'i' => self.create_with_popped_type("type metadata instantiation function for "),
+            b'I' => self.create_with_popped_type("type metadata instantiation cache for "),
+            b'l' => {
+                self.create_with_popped_type("type metadata singleton initialization cache for ")
+            }
+            b'L' => self.create_with_popped_type("lazy cache variable for type metadata for "),
+            b'm' => self.create_with_popped_type("metaclass for "),
+            b'n' => self.create_with_popped_type("nominal type descriptor for "),
+            b'o' => self.create_with_popped_type("class metadata base offset for "),
+            b'p' => {
+                let protocol = self.pop_protocol();
+                self.create_with_children(Kind::Described("protocol descriptor for "), &[protocol])
+            }
+            b'P' => self.create_with_popped_type("generic type metadata pattern for "),
+            b'r' => self.create_with_popped_type("type metadata completion function for "),
+            b's' => self.create_with_popped_type("ObjC resilient class stub for "),
+            b'S' => {
+                let protocol = self.pop_protocol();
+                self.create_with_children(
+                    Kind::Described("protocol self-conformance descriptor for "),
+                    &[protocol],
+                )
+            }
+            b't' => self.create_with_popped_type("full ObjC resilient class stub for "),
+            b'u' => self.create_with_popped_type("method lookup function for "),
+            b'U' => self.create_with_popped_type("ObjC metadata update function for "),
+            b'V' => {
+                let entity = self.pop_if(Kind::is_entity);
+                self.create_with_children(Kind::Described("property descriptor for "), &[entity])
+            }
+            b'X' => match self.next() {
+                b'E' => {
+                    let extension = self.pop_context();
+                    self.create_with_children(
+                        Kind::Described("extension descriptor "),
+                        &[extension],
+                    )
+                }
+                b'M' => {
+                    let module = self.pop_module();
+                    self.create_with_children(Kind::Described("module descriptor "), &[module])
+                }
+                b'X' => {
+                    let context = self.pop_context();
+                    self.create_with_children(Kind::Described("anonymous descriptor "), &[context])
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn pop_protocol_conformance(&mut self) -> Option<NodeId> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let module = self.pop_module();
+        let protocol = self.pop_protocol();
+        // A property behavior conformance, which has no type, is not
+        // supported.
+        let mut ty = Some(self.pop_kind(Kind::Type)?);
+        if let Some(signature) = signature {
+            let generic =
+                self.create_with_children(Kind::DependentGenericType, &[Some(signature), ty]);
+            ty = self.create_type(generic);
+        }
+        self.create_with_children(Kind::ProtocolConformance, &[ty, protocol, module])
+    }
+
+    fn demangle_witness(&mut self) -> Option<NodeId> {
+        match self.next() {
+            b'C' => {
+                let entity = self.pop_if(Kind::is_entity);
+                self.create_with_children(Kind::Described("enum case for "), &[entity])
+            }
+            b'V' => self.create_with_popped_type("value witness table for "),
+            b'v' => {
+                let directness = match self.next() {
+                    b'd' => "direct",
+                    b'i' => "indirect",
+                    _ => return None,
+                };
+                let entity = self.pop_if(Kind::is_entity);
+                let offset = self.create_with_children(Kind::FieldOffset, &[entity])?;
+                self.nodes[offset].text = directness.to_string();
+                Some(offset)
+            }
+            b'S' => {
+                let protocol = self.pop_protocol();
+                self.create_with_children(
+                    Kind::Described("protocol self-conformance witness table for "),
+                    &[protocol],
+                )
+            }
+            b'P' => self.create_with_popped_conformance("protocol witness table for "),
+            b'p' => self.create_with_popped_conformance("protocol witness table pattern for "),
+            b'G' => self.create_with_popped_conformance("generic protocol witness table for "),
+            b'I' => self.create_with_popped_conformance(
+                "instantiation function for generic protocol witness table for ",
+            ),
+            b'r' => self.create_with_popped_conformance("resilient protocol witness table for "),
+            b'a' => self.create_with_popped_conformance("protocol witness table accessor for "),
+            c @ (b'l' | b'L') => {
+                let conformance = self.pop_protocol_conformance();
+                let ty = self.pop_kind(Kind::Type);
+                let description = if c == b'l' {
+                    "lazy protocol witness table accessor for type "
+                } else {
+                    "lazy protocol witness table cache variable for type "
+                };
+                self.create_with_children(
+                    Kind::DescribedPair(description, " and conformance "),
+                    &[ty, conformance],
+                )
+            }
+            b't' => {
+                let name = self.pop_if(Kind::is_decl_name);
+                let conformance = self.pop_protocol_conformance();
+                self.create_with_children(
+                    Kind::DescribedPair("associated type metadata accessor for ", " in "),
+                    &[name, conformance],
+                )
+            }
+            b'b' => {
+                let protocol = self.pop_kind(Kind::Type);
+                let conformance = self.pop_protocol_conformance();
+                self.create_with_children(
+                    Kind::DescribedPair("base witness table accessor for ", " in "),
+                    &[protocol, conformance],
+                )
+            }
+            b'O' => {
+                let description = match self.next() {
+                    b'y' => "outlined copy of ",
+                    b'e' => "outlined consume of ",
+                    b'r' => "outlined retain of ",
+                    b's' => "outlined release of ",
+                    b'b' | b'B' => "outlined init with take of ",
+                    b'c' | b'C' => "outlined init with copy of ",
+                    b'd' | b'D' => "outlined assign with take of ",
+                    b'f' | b'F' => "outlined assign with copy of ",
+                    b'h' | b'H' => "outlined destroy of ",
+                    _ => return None,
+                };
+                let signature = self.pop_kind(Kind::DependentGenericSignature);
+                let ty = self.pop_kind(Kind::Type);
+                let outlined = self.create_with_children(Kind::Described(description), &[ty])?;
+                self.add_optional(outlined, signature);
+                Some(outlined)
+            }
+            _ => None,
+        }
+    }
+
+    fn demangle_value_witness(&mut self) -> Option<NodeId> {
+        let code = [self.next(), self.next()];
+        let name = match &code {
+            b"al" => "allocateBuffer",
+            b"ca" => "assignWithCopy",
+            b"ta" => "assignWithTake",
+            b"de" => "deallocateBuffer",
+            b"xx" => "destroy",
+            b"XX" => "destroyBuffer",
+            b"Xx" => "destroyArray",
+            b"CP" => "initializeBufferWithCopyOfBuffer",
+            b"Cp" => "initializeBufferWithCopy",
+            b"cp" => "initializeWithCopy",
+            b"Tk" => "initializeBufferWithTake",
+            b"tk" => "initializeWithTake",
+            b"pr" => "projectBuffer",
+            b"TK" => "initializeBufferWithTakeOfBuffer",
+            b"Cc" => "initializeArrayWithCopy",
+            b"Tt" => "initializeArrayWithTakeFrontToBack",
+            b"tT" => "initializeArrayWithTakeBackToFront",
+            b"xs" => "storeExtraInhabitant",
+            b"xg" => "getExtraInhabitantIndex",
+            b"ug" => "getEnumTag",
+            b"up" => "destructiveProjectEnumData",
+            b"ui" => "destructiveInjectEnumTag",
+            b"et" => "getEnumTagSinglePayload",
+            b"st" => "storeEnumTagSinglePayload",
+            _ => return None,
+        };
+        let ty = self.pop_kind(Kind::Type);
+        let witness = self.create_with_children(Kind::ValueWitness, &[ty])?;
+        self.nodes[witness].text = name.to_string();
+        Some(witness)
+    }
+
+    fn demangle_thunk_or_specialization(&mut self) -> Option<NodeId> {
+        let attribute = |full, short| Kind::FunctionAttribute(full, short);
+        match self.next() {
+            b'a' => self.create(Kind::PartialApplyObjCForwarder),
+            b'A' => self.create(Kind::PartialApplyForwarder),
+            b'm' => self.create(attribute("merged ", "")),
+            b'o' => self.create(attribute("@objc ", "@objc ")),
+            b'O' => self.create(attribute("@nonobjc ", "@nonobjc ")),
+            b'D' => self.create(attribute("dynamic ", "dynamic ")),
+            b'd' => self.create(attribute("super ", "super ")),
+            b'E' => self.create(attribute("distributed thunk ", "distributed thunk ")),
+            b'F' => self.create(attribute(
+                "distributed accessor for ",
+                "distributed accessor for ",
+            )),
+            b'u' => self.create(attribute(
+                "async function pointer to ",
+                "async function pointer to ",
+            )),
+            b'X' => self.create(attribute(
+                "dynamically replaceable variable for ",
+                "dynamically replaceable variable for ",
+            )),
+            b'x' => self.create(attribute(
+                "dynamically replaceable key for ",
+                "dynamically replaceable key for ",
+            )),
+            b'I' => self.create(attribute(
+                "dynamically replaceable thunk for ",
+                "dynamically replaceable thunk for ",
+            )),
+            c @ (b'Q' | b'Y') => {
+                let index = self.demangle_index_as_node();
+                let partial =
+                    self.create_with_children(Kind::AsyncResumePartialFunction, &[index])?;
+                self.nodes[partial].text = if c == b'Q' { "await" } else { "suspend" }.to_string();
+                Some(partial)
+            }
+            b'c' => self.create_with_popped_entity("curry thunk of "),
+            b'j' => self.create_with_popped_entity("dispatch thunk of "),
+            b'q' => self.create_with_popped_entity("method descriptor for "),
+            b'S' => self.create_with_popped_entity("protocol self-conformance witness for "),
+            b'V' => {
+                let base = self.pop_if(Kind::is_entity);
+                let derived = self.pop_if(Kind::is_entity);
+                self.create_with_children(
+                    Kind::DescribedPair("vtable thunk for ", " dispatching to "),
+                    &[base, derived],
+                )
+            }
+            b'W' => {
+                let entity = self.pop_if(Kind::is_entity);
+                let conformance = self.pop_protocol_conformance();
+                self.create_with_children(
+                    Kind::DescribedPair("protocol witness for ", " in conformance "),
+                    &[entity, conformance],
+                )
+            }
+            c @ (b'R' | b'r') => {
+                let kind = if c == b'R' {
+                    Kind::ReabstractionThunkHelper
+                } else {
+                    Kind::ReabstractionThunk
+                };
+                let thunk = self.create(kind)?;
+                let signature = self.pop_kind(Kind::DependentGenericSignature);
+                self.add_optional(thunk, signature);
+                let to = self.pop_kind(Kind::Type)?;
+                self.add_child(thunk, to);
+                let from = self.pop_kind(Kind::Type)?;
+                self.add_child(thunk, from);
+                Some(thunk)
+            }
+            b'g' => self.demangle_generic_specialization("generic specialization"),
+            b'G' => {
+                self.demangle_generic_specialization("generic not re-abstracted specialization")
+            }
+            b'B' => {
+                self.demangle_generic_specialization("generic specialization in resilience domain")
+            }
+            b'i' => self.demangle_generic_specialization("inlined generic function"),
+            c @ (b'p' | b'P') => {
+                let description = if c == b'p' {
+                    "partial generic specialization"
+                } else {
+                    "partial generic not re-abstracted specialization"
+                };
+                let specialization = self.demangle_spec_attributes(description);
+                let ty = self.pop_kind(Kind::Type);
+                let param = self.create_with_children(Kind::SpecializationParam, &[ty]);
+                self.add_required(specialization, param)
+            }
+            b'f' => self.demangle_function_specialization(),
+            _ => None,
+        }
+    }
+
+    fn create_with_popped_entity(&mut self, description: &'static str) -> Option<NodeId> {
+        let entity = self.pop_if(Kind::is_entity);
+        self.create_with_children(Kind::Described(description), &[entity])
+    }
+
+    fn demangle_spec_attributes(&mut self, description: &str) -> Option<NodeId> {
+        let _metatype_params_removed = self.next_if(b'm');
+        let is_serialized = self.next_if(b'q');
+        let _async_removed = self.next_if(b'a');
+        let pass_id = self.next().wrapping_sub(b'0');
+        if pass_id >= 10 {
+            return None;
+        }
+        let specialization = self.create_text(Kind::Specialization, description)?;
+        if is_serialized {
+            let serialized = self.create(Kind::IsSerialized)?;
+            self.add_child(specialization, serialized);
+        }
+        Some(specialization)
+    }
+
+    fn demangle_generic_specialization(&mut self, description: &str) -> Option<NodeId> {
+        let specialization = self.demangle_spec_attributes(description)?;
+        let types = self.pop_type_list()?;
+        for ty in self.nodes[types].children.clone() {
+            let param = self.create_with_children(Kind::SpecializationParam, &[Some(ty)])?;
+            self.add_child(specialization, param);
+        }
+        Some(specialization)
+    }
+
+    fn demangle_function_specialization(&mut self) -> Option<NodeId> {
+        let specialization = self.demangle_spec_attributes("function signature specialization")?;
+        while !self.next_if(b'_') {
+            let param = self.demangle_func_spec_param(Kind::FuncSigSpecParam)?;
+            self.add_child(specialization, param);
+        }
+        if !self.next_if(b'n') {
+            let result = self.demangle_func_spec_param(Kind::FuncSigSpecReturn)?;
+            self.add_child(specialization, result);
+        }
+
+        // Constant-propagated and closure arguments are followed by their
+        // payloads, which are mangled before the specialization. Pop them in
+        // reverse order.
+        let params = self.nodes[specialization].children.clone();
+        for &param in params.iter().rev() {
+            let data = &self.nodes[param];
+            if data.kind != Kind::FuncSigSpecParam || data.index2 == 0 {
+                continue;
+            }
+            let param_kind = data.index;
+            if !matches!(
+                param_kind,
+                CONSTANT_PROP_FUNCTION
+                    | CONSTANT_PROP_GLOBAL
+                    | CONSTANT_PROP_STRING
+                    | CONSTANT_PROP_KEY_PATH
+                    | CLOSURE_PROP
+            ) {
+                continue;
+            }
+            let fixed_children = self.num_children(param);
+            while let Some(ty) = self.pop_kind(Kind::Type) {
+                if param_kind != CLOSURE_PROP && param_kind != CONSTANT_PROP_KEY_PATH {
+                    return None;
+                }
+                self.add_child(param, ty);
+            }
+            let name = self.pop_kind(Kind::Identifier)?;
+            let mut text = self.nodes[name].text.clone();
+            if param_kind == CONSTANT_PROP_STRING && text.starts_with('_') {
+                // A '_' escapes a leading digit or '_' of a string constant.
+                text.remove(0);
+            }
+            let payload = self.create_node(Kind::FuncSigSpecPayload, text, 0)?;
+            self.add_child(param, payload);
+            self.nodes[param].children[fixed_children..].reverse();
+        }
+        Some(specialization)
+    }
+
+    fn demangle_func_spec_param(&mut self, kind: Kind) -> Option<NodeId> {
+        let param = self.create(kind)?;
+        let param_kind = match self.next() {
+            b'n' => return Some(param),
+            b'c' => CLOSURE_PROP,
+            b'p' => match self.next() {
+                b'f' => CONSTANT_PROP_FUNCTION,
+                b'g' => CONSTANT_PROP_GLOBAL,
+                c @ (b'i' | b'd') => {
+                    let start = self.pos;
+                    while self.peek().is_ascii_digit() {
+                        self.next();
+                    }
+                    if self.pos == start {
+                        return None;
+                    }
+                    let digits = String::from_utf8_lossy(&self.text[start..self.pos]).into_owned();
+                    let payload = self.create_node(Kind::FuncSigSpecPayload, digits, 0)?;
+                    self.add_child(param, payload);
+                    if c == b'i' {
+                        CONSTANT_PROP_INTEGER
+                    } else {
+                        CONSTANT_PROP_FLOAT
+                    }
+                }
+                b's' => {
+                    let encoding = match self.next() {
+                        b'b' => "u8",
+                        b'w' => "u16",
+                        b'c' => "objc",
+                        _ => return None,
+                    };
+                    let payload = self.create_text(Kind::FuncSigSpecPayload, encoding)?;
+                    self.add_child(param, payload);
+                    CONSTANT_PROP_STRING
+                }
+                b'k' => CONSTANT_PROP_KEY_PATH,
+                _ => return None,
+            },
+            b'e' => {
+                let mut value = EXISTENTIAL_TO_GENERIC;
+                if self.next_if(b'D') {
+                    value |= DEAD;
+                }
+                value | self.demangle_func_spec_param_options()
+            }
+            b'd' => DEAD | self.demangle_func_spec_param_options(),
+            b'g' => {
+                let mut value = OWNED_TO_GUARANTEED;
+                if self.next_if(b'X') {
+                    value |= SROA;
+                }
+                value
+            }
+            b'o' => {
+                let mut value = GUARANTEED_TO_OWNED;
+                if self.next_if(b'X') {
+                    value |= SROA;
+                }
+                value
+            }
+            b'x' => SROA,
+            b'i' => BOX_TO_VALUE,
+            b's' => BOX_TO_STACK,
+            b'r' => IN_OUT_TO_OUT,
+            _ => return None,
+        };
+        self.nodes[param].index = param_kind;
+        self.nodes[param].index2 = 1;
+        Some(param)
+    }
+
+    fn demangle_func_spec_param_options(&mut self) -> u64 {
+        let mut value = 0;
+        if self.next_if(b'G') {
+            value |= OWNED_TO_GUARANTEED;
+        }
+        if self.next_if(b'O') {
+            value |= GUARANTEED_TO_OWNED;
+        }
+        if self.next_if(b'X') {
+            value |= SROA;
+        }
+        value
+    }
+}
+
+fn is_word_start(c: u8) -> bool {
+    !c.is_ascii_digit() && c != b'_' && c != 0
+}
+
+fn is_word_end(c: u8, previous: u8) -> bool {
+    c == b'_' || c == 0 || (!previous.is_ascii_uppercase() && c.is_ascii_uppercase())
+}
+
+/// Decodes a Punycode identifier, which Swift uses for non-ASCII names. Swift
+/// uses `_` as the delimiter and `a-z`, `A-J` as digits.
+fn decode_punycode(input: &str) -> Option<String> {
+    const BASE: u32 = 36;
+    const TMIN: u32 = 1;
+    const TMAX: u32 = 26;
+
+    let bytes = input.as_bytes();
+    let (basic, encoded) = match bytes.iter().rposition(|&c| c == b'_') {
+        Some(position) => (&bytes[..position], &bytes[position + 1..]),
+        None => (&bytes[..0], bytes),
+    };
+    let mut output: Vec<u32> = Vec::with_capacity(bytes.len());
+    for &c in basic {
+        if !c.is_ascii() {
+            return None;
+        }
+        output.push(u32::from(c));
+    }
+
+    let mut n: u32 = 128;
+    let mut i: u32 = 0;
+    let mut bias: u32 = 72;
+    let mut position = 0;
+    while position < encoded.len() {
+        let old_i = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = punycode_digit_value(*encoded.get(position)?)?;
+            position += 1;
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = if k <= bias {
+                TMIN
+            } else if k >= bias + TMAX {
+                TMAX
+            } else {
+                k - bias
+            };
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = u32::try_from(output.len() + 1).ok()?;
+        bias = punycode_adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(usize::try_from(i).ok()?, n);
+        i += 1;
+    }
+    output.into_iter().map(char::from_u32).collect()
+}
+
+fn punycode_digit_value(c: u8) -> Option<u32> {
+    match c {
+        b'a'..=b'z' => Some(u32::from(c - b'a')),
+        b'A'..=b'J' => Some(u32::from(c - b'A') + 26),
+        _ => None,
+    }
+}
+
+fn punycode_adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    const BASE: u32 = 36;
+    const TMIN: u32 = 1;
+    const TMAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - TMIN) * TMAX) / 2 {
+        delta /= BASE - TMIN;
+        k += BASE;
+    }
+    k + (((BASE - TMIN + 1) * delta) / (delta + SKEW))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypePrinting {
+    NoType,
+    WithColon,
+    FunctionStyle,
+}
+
+/// The name parts of an entity, see `NodePrinter::printEntity`.
+#[derive(Default)]
+struct EntityName<'a> {
+    has_name: bool,
+    extra_name: &'a str,
+    extra_index: Option<u64>,
+    overwrite_name: &'a str,
+}
+
+/// A context which could not be printed as a prefix of an entity, and is
+/// printed after it instead, as in `closure #1 in foo()`.
+type PostfixContext = Option<NodeId>;
+
+struct Printer<'d> {
+    nodes: &'d [NodeData],
+    full: bool,
+    out: String,
+    specialization_prefix_printed: bool,
+}
+
+impl<'d> Printer<'d> {
+    fn new(demangler: &'d Demangler<'_>, style: SwiftDemangleStyle) -> Self {
+        Printer {
+            nodes: &demangler.nodes,
+            full: style == SwiftDemangleStyle::Full,
+            out: String::new(),
+            specialization_prefix_printed: false,
+        }
+    }
+
+    fn print_global(mut self, global: NodeId) -> Option<String> {
+        self.print(global, 0, false)?;
+        if self.out.len() > MAX_OUTPUT_LEN {
+            return None;
+        }
+        Some(self.out)
+    }
+
+    fn kind(&self, node: NodeId) -> Kind {
+        self.nodes[node].kind
+    }
+
+    fn child(&self, node: NodeId, index: usize) -> Option<NodeId> {
+        self.nodes[node].children.get(index).copied()
+    }
+
+    fn child_of_kind(&self, node: NodeId, kind: Kind) -> Option<NodeId> {
+        self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.kind(child) == kind)
+    }
+
+    fn print_child(&mut self, node: NodeId, index: usize, depth: usize) -> Option<()> {
+        let child = self.child(node, index)?;
+        self.print(child, depth, false)?;
+        Some(())
+    }
+
+    fn print_children(&mut self, node: NodeId, depth: usize, separator: &str) -> Option<()> {
+        let nodes = self.nodes;
+        for (index, &child) in nodes[node].children.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(separator);
+            }
+            self.print(child, depth, false)?;
+        }
+        Some(())
+    }
+
+    /// Prints a node. Returns `None` if the tree is malformed or too deep,
+    /// and otherwise the context of an entity which still has to be printed
+    /// if `as_prefix_context` is set.
+    fn print(
+        &mut self,
+        node: NodeId,
+        depth: usize,
+        as_prefix_context: bool,
+    ) -> Option<PostfixContext> {
+        if depth > MAX_PRINT_DEPTH || self.out.len() > MAX_OUTPUT_LEN {
+            return None;
+        }
+        let depth = depth + 1;
+        let nodes = self.nodes;
+        let data = &nodes[node];
+        match data.kind {
+            Kind::Global | Kind::TypeList => self.print_children(node, depth, "")?,
+            Kind::Suffix => {
+                if self.full {
+                    self.out.push_str(" with unmangled suffix ");
+                    self.print_quoted(&data.text);
+                }
+            }
+            Kind::Module => {
+                if self.full {
+                    self.out.push_str(&data.text);
+                }
+            }
+            Kind::Identifier
+            | Kind::BuiltinTypeName
+            | Kind::DependentAssociatedTypeRef
+            | Kind::ImplAttribute => self.out.push_str(&data.text),
+            Kind::Number => {
+                let _ = write!(self.out, "{}", data.index);
+            }
+            Kind::LocalDeclName => {
+                self.print_child(node, 1, depth)?;
+                let discriminator = self.child(node, 0)?;
+                let _ = write!(self.out, " #{}", nodes[discriminator].index + 1);
+            }
+            Kind::PrivateDeclName => {
+                let discriminator = &nodes[self.child(node, 0)?].text;
+                if data.children.len() > 1 {
+                    if self.full {
+                        self.out.push('(');
+                    }
+                    self.print_child(node, 1, depth)?;
+                    if self.full {
+                        let _ = write!(self.out, " in {})", discriminator);
+                    }
+                } else if self.full {
+                    let _ = write!(self.out, "(in {})", discriminator);
+                }
+            }
+            Kind::RelatedEntityDeclName => {
+                let related_kind = &nodes[self.child(node, 0)?].text;
+                let _ = write!(self.out, "related decl '{}' for ", related_kind);
+                self.print_child(node, 1, depth)?;
+            }
+            Kind::InfixOperator => {
+                let _ = write!(self.out, "{} infix", data.text);
+            }
+            Kind::PrefixOperator => {
+                let _ = write!(self.out, "{} prefix", data.text);
+            }
+            Kind::PostfixOperator => {
+                let _ = write!(self.out, "{} postfix", data.text);
+            }
+            Kind::Type | Kind::TypeMangling | Kind::ReturnType | Kind::SpecializationParam => {
+                self.print_child(node, 0, depth)?
+            }
+            Kind::Class | Kind::Structure | Kind::Enum | Kind::Protocol | Kind::TypeAlias => {
+                let name = EntityName {
+                    has_name: true,
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::NoType,
+                    name,
+                );
+            }
+            Kind::BoundGenericClass
+            | Kind::BoundGenericStructure
+            | Kind::BoundGenericEnum
+            | Kind::BoundGenericOther => self.print_bound_generic(node, depth)?,
+            Kind::Extension => {
+                if self.full {
+                    self.out.push_str("(extension in ");
+                    let module = self.child(node, 0)?;
+                    self.print(module, depth, true)?;
+                    self.out.push_str("):");
+                }
+                self.print_child(node, 1, depth)?;
+                if let Some(signature) = self.child(node, 2) {
+                    self.print(signature, depth, false)?;
+                }
+            }
+            Kind::Function => {
+                let name = EntityName {
+                    has_name: true,
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::FunctionStyle,
+                    name,
+                );
+            }
+            Kind::Subscript => {
+                let name = EntityName {
+                    overwrite_name: "subscript",
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::FunctionStyle,
+                    name,
+                );
+            }
+            Kind::Variable => {
+                let name = EntityName {
+                    has_name: true,
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::WithColon,
+                    name,
+                );
+            }
+            Kind::ExplicitClosure | Kind::ImplicitClosure => {
+                let index = self.child(node, 1)?;
+                let name = EntityName {
+                    extra_name: if data.kind == Kind::ExplicitClosure {
+                        "closure #"
+                    } else {
+                        "implicit closure #"
+                    },
+                    extra_index: Some(nodes[index].index + 1),
+                    ..Default::default()
+                };
+                let type_printing = if self.full {
+                    TypePrinting::FunctionStyle
+                } else {
+                    TypePrinting::NoType
+                };
+                return self.print_entity(node, depth, as_prefix_context, type_printing, name);
+            }
+            Kind::DefaultArgumentInitializer => {
+                let index = self.child(node, 1)?;
+                let name = EntityName {
+                    extra_name: "default argument ",
+                    extra_index: Some(nodes[index].index),
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::NoType,
+                    name,
+                );
+            }
+            Kind::Initializer
+            | Kind::PropertyWrapperBackingInitializer
+            | Kind::PropertyWrapperInitFromProjectedValue
+            | Kind::Destructor
+            | Kind::Deallocator
+            | Kind::IVarInitializer
+            | Kind::IVarDestroyer => {
+                let is_class = self.kind(self.child(node, 0)?) == Kind::Class;
+                let extra_name = match data.kind {
+                    Kind::Initializer => "variable initialization expression",
+                    Kind::PropertyWrapperBackingInitializer => {
+                        "property wrapper backing initializer"
+                    }
+                    Kind::PropertyWrapperInitFromProjectedValue => {
+                        "property wrapper init from projected value"
+                    }
+                    Kind::Deallocator if is_class => "__deallocating_deinit",
+                    Kind::IVarInitializer => "__ivar_initializer",
+                    Kind::IVarDestroyer => "__ivar_destroyer",
+                    _ => "deinit",
+                };
+                let name = EntityName {
+                    extra_name,
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::NoType,
+                    name,
+                );
+            }
+            Kind::Allocator | Kind::Constructor => {
+                let is_class = self.kind(self.child(node, 0)?) == Kind::Class;
+                let name = EntityName {
+                    extra_name: if data.kind == Kind::Allocator && is_class {
+                        "__allocating_init"
+                    } else {
+                        "init"
+                    },
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    node,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::FunctionStyle,
+                    name,
+                );
+            }
+            Kind::Accessor => {
+                let storage = self.child(node, 0)?;
+                let name = EntityName {
+                    has_name: self.kind(storage) == Kind::Variable,
+                    extra_name: &data.text,
+                    overwrite_name: if self.kind(storage) == Kind::Subscript {
+                        "subscript"
+                    } else {
+                        ""
+                    },
+                    ..Default::default()
+                };
+                return self.print_entity(
+                    storage,
+                    depth,
+                    as_prefix_context,
+                    TypePrinting::WithColon,
+                    name,
+                );
+            }
+            Kind::Static => {
+                self.out.push_str("static ");
+                self.print_child(node, 0, depth)?;
+            }
+            Kind::LabelList
+            | Kind::FirstElementMarker
+            | Kind::EmptyList
+            | Kind::VariadicMarker
+            | Kind::DependentGenericParamCount => {}
+            Kind::ThrowsAnnotation => self.out.push_str(" throws"),
+            Kind::TypedThrowsAnnotation => {
+                self.out.push_str(" throws(");
+                self.print_child(node, 0, depth)?;
+                self.out.push(')');
+            }
+            Kind::AsyncAnnotation => self.out.push_str(" async"),
+            Kind::ConcurrentFunctionType => self.out.push_str("@Sendable "),
+            Kind::GlobalActorFunctionType => {
+                self.out.push('@');
+                self.print_child(node, 0, depth)?;
+                self.out.push(' ');
+            }
+            Kind::FunctionType => self.print_function_type(None, node, depth)?,
+            Kind::ArgumentTuple => self.print_function_parameters(None, node, depth, self.full)?,
+            Kind::Tuple => {
+                self.out.push('(');
+                self.print_children(node, depth, ", ")?;
+                self.out.push(')');
+            }
+            Kind::TupleElement => {
+                if let Some(label) = self.child_of_kind(node, Kind::TupleElementName) {
+                    let _ = write!(self.out, "{}: ", nodes[label].text);
+                }
+                let ty = self.child_of_kind(node, Kind::Type)?;
+                self.print(ty, depth, false)?;
+                if self.child_of_kind(node, Kind::VariadicMarker).is_some() {
+                    self.out.push_str("...");
+                }
+            }
+            Kind::TupleElementName => {
+                let _ = write!(self.out, "{}: ", data.text);
+            }
+            Kind::DependentGenericParamType => {
+                self.out
+                    .push_str(&generic_parameter_name(data.index, data.index2));
+            }
+            Kind::DependentMemberType | Kind::AssociatedTypeRef => {
+                self.print_child(node, 0, depth)?;
+                self.out.push('.');
+                self.print_child(node, 1, depth)?;
+            }
+            Kind::DependentGenericType => {
+                self.print_child(node, 0, depth)?;
+                let ty = self.child(node, 1)?;
+                if self.need_space_before_type(ty) {
+                    self.out.push(' ');
+                }
+                self.print(ty, depth, false)?;
+            }
+            Kind::DependentGenericSignature => self.print_generic_signature(node, depth)?,
+            Kind::ConformanceRequirement | Kind::SameTypeRequirement => {
+                self.print_child(node, 0, depth)?;
+                self.out
+                    .push_str(if data.kind == Kind::SameTypeRequirement {
+                        " == "
+                    } else {
+                        ": "
+                    });
+                self.print_child(node, 1, depth)?;
+            }
+            Kind::LayoutRequirement => {
+                self.print_child(node, 0, depth)?;
+                let _ = write!(self.out, ": {}", data.text);
+                if data.children.len() > 1 {
+                    self.out.push('(');
+                    for (index, &arg) in data.children[1..].iter().enumerate() {
+                        if index > 0 {
+                            self.out.push_str(", ");
+                        }
+                        let _ = write!(self.out, "{}", nodes[arg].index);
+                    }
+                    self.out.push(')');
+                }
+            }
+            Kind::OpaqueReturnType => self.out.push_str("some"),
+            Kind::Metatype => {
+                self.out.push_str(&data.text);
+                let ty = self.child(self.child(node, 0)?, 0)?;
+                self.print_with_parens(ty, depth)?;
+                if self.is_existential_type(ty) {
+                    self.out.push_str(".Protocol");
+                } else {
+                    self.out.push_str(".Type");
+                }
+            }
+            Kind::ExistentialMetatype => {
+                self.out.push_str(&data.text);
+                self.print_child(node, 0, depth)?;
+                self.out.push_str(".Type");
+            }
+            Kind::TypeAttribute => {
+                self.out.push_str(&data.text);
+                self.print_child(node, 0, depth)?;
+            }
+            Kind::DynamicSelf => self.out.push_str("Self"),
+            Kind::ErrorType => self.out.push_str("<ERROR TYPE>"),
+            Kind::ProtocolList => {
+                let list = self.child(node, 0)?;
+                if nodes[list].children.is_empty() {
+                    self.out.push_str("Any");
+                } else {
+                    self.print_children(list, depth, " & ")?;
+                }
+            }
+            Kind::ProtocolListWithClass => {
+                self.print_child(node, 1, depth)?;
+                self.out.push_str(" & ");
+                let list = self.child(self.child(node, 0)?, 0)?;
+                self.print_children(list, depth, " & ")?;
+            }
+            Kind::ProtocolListWithAnyObject => {
+                let list = self.child(self.child(node, 0)?, 0)?;
+                if !nodes[list].children.is_empty() {
+                    self.print_children(list, depth, " & ")?;
+                    self.out.push_str(" & ");
+                }
+                if self.full {
+                    let _ = write!(self.out, "{}.", STDLIB_NAME);
+                }
+                self.out.push_str("AnyObject");
+            }
+            Kind::ImplFunctionType => self.print_impl_function_type(node, depth)?,
+            Kind::ImplParameter | Kind::ImplResult | Kind::ImplYield | Kind::ImplErrorResult => {
+                match data.kind {
+                    Kind::ImplYield => self.out.push_str("@yields "),
+                    Kind::ImplErrorResult => self.out.push_str("@error "),
+                    _ => {}
+                }
+                let _ = write!(self.out, "{} ", data.text);
+                self.print_child(node, 0, depth)?;
+            }
+            Kind::Described(description) => {
+                self.out.push_str(description);
+                self.print_child(node, 0, depth)?;
+            }
+            Kind::DescribedPair(first, second) => {
+                self.out.push_str(first);
+                self.print_child(node, 0, depth)?;
+                self.out.push_str(second);
+                self.print_child(node, 1, depth)?;
+            }
+            Kind::FunctionAttribute(full, simplified) => {
+                self.out.push_str(if self.full { full } else { simplified });
+            }
+            Kind::PartialApplyForwarder | Kind::PartialApplyObjCForwarder => {
+                self.out.push_str(if !self.full {
+                    "partial apply"
+                } else if data.kind == Kind::PartialApplyObjCForwarder {
+                    "partial apply ObjC forwarder"
+                } else {
+                    "partial apply forwarder"
+                });
+                if !data.children.is_empty() {
+                    self.out.push_str(" for ");
+                    self.print_children(node, depth, "")?;
+                }
+            }
+            Kind::AsyncResumePartialFunction => {
+                if self.full {
+                    self.out.push('(');
+                    self.print_child(node, 0, depth)?;
+                    let _ = write!(self.out, ") {} resume partial function for ", data.text);
+                }
+            }
+            Kind::Specialization => self.print_specialization_prefix(node, depth)?,
+            Kind::FuncSigSpecParam | Kind::FuncSigSpecReturn => {
+                self.print_func_sig_spec_param(node, depth)?
+            }
+            Kind::FuncSigSpecPayload => self.out.push_str(&data.text),
+            Kind::IsSerialized => self.out.push_str("serialized"),
+            Kind::ValueWitness => {
+                self.out.push_str(&data.text);
+                self.out.push_str(if self.full {
+                    " value witness for "
+                } else {
+                    " for "
+                });
+                self.print_child(node, 0, depth)?;
+            }
+            Kind::ProtocolConformance => {
+                self.print_child(node, 0, depth)?;
+                if self.full {
+                    self.out.push_str(" : ");
+                    self.print_child(node, 1, depth)?;
+                    self.out.push_str(" in ");
+                    self.print_child(node, 2, depth)?;
+                }
+            }
+            Kind::ReabstractionThunk | Kind::ReabstractionThunkHelper => {
+                let num_children = data.children.len();
+                if !self.full {
+                    self.out.push_str("thunk for ");
+                    self.print_child(node, num_children.checked_sub(1)?, depth)?;
+                    return Some(None);
+                }
+                self.out.push_str("reabstraction thunk ");
+                if data.kind == Kind::ReabstractionThunkHelper {
+                    self.out.push_str("helper ");
+                }
+                let mut index = 0;
+                if num_children == 3 {
+                    self.print_child(node, 0, depth)?;
+                    self.out.push(' ');
+                    index = 1;
+                }
+                self.out.push_str("from ");
+                self.print_child(node, index + 1, depth)?;
+                self.out.push_str(" to ");
+                self.print_child(node, index, depth)?;
+            }
+            Kind::FieldOffset => {
+                let _ = write!(self.out, "{} field offset for ", data.text);
+                self.print_child(node, 0, depth)?;
+            }
+        }
+        Some(None)
+    }
+}
+
+impl<'d> Printer<'d> {
+    /// Prints a named entity with its context, either as a prefix
+    /// (`Foo.bar`) or, for names with spaces, as a postfix
+    /// (`closure #1 in Foo.bar`).
+    fn print_entity(
+        &mut self,
+        entity: NodeId,
+        depth: usize,
+        as_prefix_context: bool,
+        mut type_printing: TypePrinting,
+        name: EntityName<'_>,
+    ) -> Option<PostfixContext> {
+        let mut extra_name = name.extra_name;
+        let mut extra_index = name.extra_index;
+        let is_local_name = name.has_name
+            && matches!(self.child(entity, 1), Some(child) if self.kind(child) == Kind::LocalDeclName);
+        let multi_word_name = extra_name.contains(' ') || is_local_name;
+        if as_prefix_context && (type_printing != TypePrinting::NoType || multi_word_name) {
+            return Some(Some(entity));
+        }
+
+        let context = self.child(entity, 0)?;
+        let mut postfix_context = if multi_word_name {
+            Some(context)
+        } else {
+            let len = self.out.len();
+            let postfix_context = self.print(context, depth, true)?;
+            if self.out.len() != len {
+                self.out.push('.');
+            }
+            postfix_context
+        };
+
+        if name.has_name || !name.overwrite_name.is_empty() {
+            if !extra_name.is_empty() && multi_word_name {
+                self.out.push_str(extra_name);
+                if let Some(index) = extra_index {
+                    let _ = write!(self.out, "{}", index);
+                }
+                self.out.push_str(" of ");
+                extra_name = "";
+                extra_index = None;
+            }
+            let len = self.out.len();
+            if !name.overwrite_name.is_empty() {
+                self.out.push_str(name.overwrite_name);
+            } else {
+                let decl_name = self.child(entity, 1)?;
+                if self.kind(decl_name) != Kind::PrivateDeclName {
+                    self.print(decl_name, depth, false)?;
+                }
+                if let Some(private_name) = self.child_of_kind(entity, Kind::PrivateDeclName) {
+                    self.print(private_name, depth, false)?;
+                }
+            }
+            if self.out.len() != len && !extra_name.is_empty() {
+                self.out.push('.');
+            }
+        }
+        if !extra_name.is_empty() {
+            self.out.push_str(extra_name);
+            if let Some(index) = extra_index {
+                let _ = write!(self.out, "{}", index);
+            }
+        }
+
+        if type_printing != TypePrinting::NoType {
+            let ty = self.child_of_kind(entity, Kind::Type)?;
+            let ty = self.child(ty, 0)?;
+            if type_printing == TypePrinting::FunctionStyle {
+                let mut function = ty;
+                while self.kind(function) == Kind::DependentGenericType {
+                    function = self.child(self.child(function, 1)?, 0)?;
+                }
+                if self.kind(function) != Kind::FunctionType {
+                    type_printing = TypePrinting::WithColon;
+                }
+            }
+            if type_printing == TypePrinting::WithColon {
+                if self.full {
+                    self.out.push_str(" : ");
+                    self.print_entity_type(entity, ty, depth)?;
+                }
+            } else {
+                if multi_word_name || self.need_space_before_type(ty) {
+                    self.out.push(' ');
+                }
+                self.print_entity_type(entity, ty, depth)?;
+            }
+        }
+
+        if !as_prefix_context {
+            if let Some(context) = postfix_context.take() {
+                self.out.push_str(match self.kind(entity) {
+                    Kind::DefaultArgumentInitializer
+                    | Kind::Initializer
+                    | Kind::PropertyWrapperBackingInitializer
+                    | Kind::PropertyWrapperInitFromProjectedValue => " of ",
+                    _ => " in ",
+                });
+                self.print(context, depth, false)?;
+            }
+        }
+        Some(postfix_context)
+    }
+
+    fn print_entity_type(&mut self, entity: NodeId, ty: NodeId, depth: usize) -> Option<()> {
+        let labels = match self.child_of_kind(entity, Kind::LabelList) {
+            Some(labels) => labels,
+            None => {
+                self.print(ty, depth, false)?;
+                return Some(());
+            }
+        };
+        let mut ty = ty;
+        if self.kind(ty) == Kind::DependentGenericType {
+            self.print_child(ty, 0, depth)?;
+            let dependent = self.child(ty, 1)?;
+            if self.need_space_before_type(dependent) {
+                self.out.push(' ');
+            }
+            ty = self.child(dependent, 0)?;
+        }
+        self.print_function_type(Some(labels), ty, depth)
+    }
+
+    fn need_space_before_type(&self, node: NodeId) -> bool {
+        match self.kind(node) {
+            Kind::Type => !matches!(
+                self.child(node, 0),
+                Some(child) if !self.need_space_before_type(child)
+            ),
+            Kind::FunctionType => !self.nodes[node].text.is_empty(),
+            Kind::DependentGenericType => false,
+            _ => true,
+        }
+    }
+
+    fn print_function_type(
+        &mut self,
+        labels: Option<NodeId>,
+        node: NodeId,
+        depth: usize,
+    ) -> Option<()> {
+        let nodes = self.nodes;
+        let data = &nodes[node];
+        if data.kind != Kind::FunctionType || data.children.len() < 2 {
+            return None;
+        }
+        self.out.push_str(&data.text);
+        let arg_index = data.children.len() - 2;
+        let mut attributes = data.children[..arg_index].iter().copied().peekable();
+        if let Some(actor) =
+            attributes.next_if(|&child| self.kind(child) == Kind::GlobalActorFunctionType)
+        {
+            self.print(actor, depth, false)?;
+        }
+        let thrown = attributes.next_if(|&child| {
+            matches!(
+                self.kind(child),
+                Kind::ThrowsAnnotation | Kind::TypedThrowsAnnotation
+            )
+        });
+        let is_sendable = attributes
+            .next_if(|&child| self.kind(child) == Kind::ConcurrentFunctionType)
+            .is_some();
+        let is_async = attributes
+            .next_if(|&child| self.kind(child) == Kind::AsyncAnnotation)
+            .is_some();
+
+        if is_sendable {
+            self.out.push_str("@Sendable ");
+        }
+        self.print_function_parameters(labels, data.children[arg_index], depth, self.full)?;
+        if !self.full {
+            return Some(());
+        }
+        if is_async {
+            self.out.push_str(" async");
+        }
+        if let Some(thrown) = thrown {
+            self.print(thrown, depth, false)?;
+        }
+        self.out.push_str(" -> ");
+        self.print(data.children[arg_index + 1], depth, false)?;
+        Some(())
+    }
+
+    fn print_function_parameters(
+        &mut self,
+        labels: Option<NodeId>,
+        argument_tuple: NodeId,
+        depth: usize,
+        show_types: bool,
+    ) -> Option<()> {
+        if self.kind(argument_tuple) != Kind::ArgumentTuple {
+            return None;
+        }
+        let params = self.child(self.child(argument_tuple, 0)?, 0)?;
+        if self.kind(params) != Kind::Tuple {
+            // A single unlabeled parameter.
+            if show_types {
+                self.out.push('(');
+                self.print(params, depth, false)?;
+                self.out.push(')');
+            } else {
+                self.out.push_str("(_:)");
+            }
+            return Some(());
+        }
+
+        let nodes = self.nodes;
+        let labels = labels.filter(|&labels| !nodes[labels].children.is_empty());
+        self.out.push('(');
+        for (index, &param) in nodes[params].children.iter().enumerate() {
+            if index > 0 && show_types {
+                self.out.push_str(", ");
+            }
+            if let Some(labels) = labels {
+                let label = self.child(labels, index)?;
+                if self.kind(label) == Kind::Identifier {
+                    self.out.push_str(&nodes[label].text);
+                } else {
+                    self.out.push('_');
+                }
+                self.out.push(':');
+                if show_types {
+                    self.out.push(' ');
+                }
+            } else if !show_types {
+                match self.child_of_kind(param, Kind::TupleElementName) {
+                    Some(name) => {
+                        let _ = write!(self.out, "{}:", nodes[name].text);
+                    }
+                    None => self.out.push_str("_:"),
+                }
+            }
+            if show_types {
+                self.print(param, depth, false)?;
+            }
+        }
+        self.out.push(')');
+        Some(())
+    }
+
+    fn print_generic_signature(&mut self, node: NodeId, depth: usize) -> Option<()> {
+        let nodes = self.nodes;
+        let children = &nodes[node].children;
+        let num_param_counts = children
+            .iter()
+            .take_while(|&&child| self.kind(child) == Kind::DependentGenericParamCount)
+            .count();
+        self.out.push('<');
+        for (param_depth, &count) in children[..num_param_counts].iter().enumerate() {
+            if param_depth > 0 {
+                self.out.push_str("><");
+            }
+            for index in 0..nodes[count].index {
+                if index > 0 {
+                    self.out.push_str(", ");
+                }
+                // Malformed symbols can have huge parameter counts.
+                if index >= 128 {
+                    self.out.push_str("...");
+                    break;
+                }
+                let name = generic_parameter_name(param_depth as u64, index);
+                self.out.push_str(&name);
+            }
+        }
+        if num_param_counts < children.len() && self.full {
+            self.out.push_str(" where ");
+            for (index, &requirement) in children[num_param_counts..].iter().enumerate() {
+                if index > 0 {
+                    self.out.push_str(", ");
+                }
+                self.print(requirement, depth, false)?;
+            }
+        }
+        self.out.push('>');
+        Some(())
+    }
+
+    fn print_bound_generic(&mut self, node: NodeId, depth: usize) -> Option<()> {
+        let args = self.child(node, 1)?;
+        let arg = |index| self.child(args, index);
+        match self.find_sugar(node) {
+            Some("Optional") => {
+                let wrapped = self.child(arg(0)?, 0)?;
+                self.print_with_parens(wrapped, depth)?;
+                self.out.push('?');
+            }
+            Some("Array") => {
+                let element = arg(0)?;
+                self.out.push('[');
+                self.print(element, depth, false)?;
+                self.out.push(']');
+            }
+            Some("Dictionary") => {
+                let (key, value) = (arg(0)?, arg(1)?);
+                self.out.push('[');
+                self.print(key, depth, false)?;
+                self.out.push_str(" : ");
+                self.print(value, depth, false)?;
+                self.out.push(']');
+            }
+            _ => {
+                self.print_child(node, 0, depth)?;
+                self.out.push('<');
+                self.print_children(args, depth, ", ")?;
+                self.out.push('>');
+            }
+        }
+        Some(())
+    }
+
+    /// Returns the name of the standard library type if a bound generic type
+    /// can be printed as `T?`, `[T]` or `[K : V]`.
+    fn find_sugar(&self, node: NodeId) -> Option<&'static str> {
+        let data = &self.nodes[node];
+        if data.children.len() != 2 {
+            return None;
+        }
+        let nominal = self.child(data.children[0], 0)?;
+        let num_args = self.nodes[data.children[1]].children.len();
+        let module = self.child(nominal, 0)?;
+        let name = self.child(nominal, 1)?;
+        if self.kind(module) != Kind::Module
+            || self.nodes[module].text != STDLIB_NAME
+            || self.kind(name) != Kind::Identifier
+        {
+            return None;
+        }
+        match (data.kind, self.nodes[name].text.as_str(), num_args) {
+            (Kind::BoundGenericEnum, "Optional", 1) => Some("Optional"),
+            (Kind::BoundGenericStructure, "Array", 1) => Some("Array"),
+            (Kind::BoundGenericStructure, "Dictionary", 2) => Some("Dictionary"),
+            _ => None,
+        }
+    }
+
+    fn print_with_parens(&mut self, ty: NodeId, depth: usize) -> Option<()> {
+        let needs_parens = !self.is_simple_type(ty);
+        if needs_parens {
+            self.out.push('(');
+        }
+        self.print(ty, depth, false)?;
+        if needs_parens {
+            self.out.push(')');
+        }
+        Some(())
+    }
+
+    fn is_simple_type(&self, node: NodeId) -> bool {
+        let first_child_len = |node| {
+            self.child(node, 0)
+                .map_or(0, |child| self.nodes[child].children.len())
+        };
+        match self.kind(node) {
+            Kind::Type => matches!(self.child(node, 0), Some(child) if self.is_simple_type(child)),
+            Kind::ProtocolList => first_child_len(node) <= 1,
+            Kind::ProtocolListWithAnyObject => {
+                matches!(self.child(node, 0), Some(protocols) if first_child_len(protocols) == 0)
+            }
+            Kind::FunctionType
+            | Kind::ImplFunctionType
+            | Kind::ProtocolListWithClass
+            | Kind::TypeAttribute => false,
+            _ => true,
+        }
+    }
+
+    fn is_existential_type(&self, node: NodeId) -> bool {
+        matches!(
+            self.kind(node),
+            Kind::ExistentialMetatype
+                | Kind::ProtocolList
+                | Kind::ProtocolListWithClass
+                | Kind::ProtocolListWithAnyObject
+        )
+    }
+
+    fn print_impl_function_type(&mut self, node: NodeId, depth: usize) -> Option<()> {
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+        enum State {
+            Attributes,
+            Parameters,
+            Results,
+        }
+        fn transition(out: &mut String, state: &mut State, new_state: State) {
+            while *state < new_state {
+                if *state == State::Attributes {
+                    out.push('(');
+                    *state = State::Parameters;
+                } else {
+                    out.push_str(") -> (");
+                    *state = State::Results;
+                }
+            }
+        }
+
+        let nodes = self.nodes;
+        let mut state = State::Attributes;
+        for &child in &nodes[node].children {
+            match self.kind(child) {
+                Kind::ImplParameter => {
+                    if state == State::Parameters {
+                        self.out.push_str(", ");
+                    }
+                    transition(&mut self.out, &mut state, State::Parameters);
+                    self.print(child, depth, false)?;
+                }
+                Kind::ImplResult | Kind::ImplYield | Kind::ImplErrorResult => {
+                    if state == State::Results {
+                        self.out.push_str(", ");
+                    }
+                    transition(&mut self.out, &mut state, State::Results);
+                    self.print(child, depth, false)?;
+                }
+                _ => {
+                    self.print(child, depth, false)?;
+                    self.out.push(' ');
+                }
+            }
+        }
+        transition(&mut self.out, &mut state, State::Results);
+        self.out.push(')');
+        Some(())
+    }
+
+    fn print_specialization_prefix(&mut self, node: NodeId, depth: usize) -> Option<()> {
+        if !self.full {
+            if !self.specialization_prefix_printed {
+                self.out.push_str("specialized ");
+                self.specialization_prefix_printed = true;
+            }
+            return Some(());
+        }
+        let nodes = self.nodes;
+        let _ = write!(self.out, "{} <", nodes[node].text);
+        let mut separator = "";
+        let mut arg_index = 0;
+        for &child in &nodes[node].children {
+            let data = &nodes[child];
+            if data.kind == Kind::IsSerialized {
+                self.out.push_str(separator);
+                separator = ", ";
+                self.print(child, depth, false)?;
+                continue;
+            }
+            // Parameters which are not changed by the specialization are
+            // left out.
+            if !data.children.is_empty() || data.index2 != 0 {
+                self.out.push_str(separator);
+                separator = ", ";
+                match data.kind {
+                    Kind::FuncSigSpecParam => {
+                        let _ = write!(self.out, "Arg[{}] = ", arg_index);
+                    }
+                    Kind::FuncSigSpecReturn => self.out.push_str("Return = "),
+                    _ => {}
+                }
+                self.print(child, depth, false)?;
+            }
+            arg_index += 1;
+        }
+        self.out.push_str("> of ");
+        Some(())
+    }
+
+    fn print_func_sig_spec_param(&mut self, node: NodeId, depth: usize) -> Option<()> {
+        let nodes = self.nodes;
+        let data = &nodes[node];
+        let payload = |index: usize| data.children.get(index).map(|&child| &nodes[child]);
+        match data.index {
+            CONSTANT_PROP_FUNCTION | CONSTANT_PROP_GLOBAL | CLOSURE_PROP => {
+                self.out.push('[');
+                self.write_func_sig_spec_kind(data.index);
+                self.out.push_str(" : ");
+                self.write_mangled_name(&payload(0)?.text);
+                if data.index == CLOSURE_PROP {
+                    self.out.push_str(", Argument Types : [");
+                    for (index, &ty) in data.children[1..].iter().enumerate() {
+                        if index > 0 {
+                            self.out.push_str(", ");
+                        }
+                        self.print(ty, depth, false)?;
+                    }
+                    self.out.push(']');
+                }
+                self.out.push(']');
+            }
+            CONSTANT_PROP_INTEGER | CONSTANT_PROP_FLOAT => {
+                self.out.push('[');
+                self.write_func_sig_spec_kind(data.index);
+                let _ = write!(self.out, " : {}]", payload(0)?.text);
+            }
+            CONSTANT_PROP_STRING => {
+                self.out.push('[');
+                self.write_func_sig_spec_kind(data.index);
+                let (encoding, value) = (&payload(0)?.text, &payload(1)?.text);
+                let _ = write!(self.out, " : {}'{}']", encoding, value);
+            }
+            CONSTANT_PROP_KEY_PATH => {
+                self.out.push('[');
+                self.write_func_sig_spec_kind(data.index);
+                let _ = write!(self.out, " : {}<", payload(0)?.text);
+                for (index, &ty) in data.children[1..].iter().enumerate() {
+                    if index > 0 {
+                        self.out.push(',');
+                    }
+                    self.print(ty, depth, false)?;
+                }
+                self.out.push_str(">]");
+            }
+            kind => self.write_func_sig_spec_kind(kind),
+        }
+        Some(())
+    }
+
+    fn write_func_sig_spec_kind(&mut self, kind: u64) {
+        const OPTIONS: [(u64, &str); 5] = [
+            (
+                EXISTENTIAL_TO_GENERIC,
+                "Existential To Protocol Constrained Generic",
+            ),
+            (DEAD, "Dead"),
+            (OWNED_TO_GUARANTEED, "Owned To Guaranteed"),
+            (GUARANTEED_TO_OWNED, "Guaranteed To Owned"),
+            (SROA, "Exploded"),
+        ];
+        let options: Vec<&str> = OPTIONS
+            .iter()
+            .filter(|(flag, _)| kind & flag != 0)
+            .map(|&(_, name)| name)
+            .collect();
+        if !options.is_empty() {
+            self.out.push_str(&options.join(" and "));
+            return;
+        }
+        self.out.push_str(match kind {
+            CONSTANT_PROP_FUNCTION => "Constant Propagated Function",
+            CONSTANT_PROP_GLOBAL => "Constant Propagated Global",
+            CONSTANT_PROP_INTEGER => "Constant Propagated Integer",
+            CONSTANT_PROP_FLOAT => "Constant Propagated Float",
+            CONSTANT_PROP_STRING => "Constant Propagated String",
+            CONSTANT_PROP_KEY_PATH => "Constant Propagated KeyPath",
+            CLOSURE_PROP => "Closure Propagated",
+            BOX_TO_VALUE => "Value Promoted from Box",
+            BOX_TO_STACK => "Stack Promoted from Box",
+            IN_OUT_TO_OUT => "InOut Converted to Out",
+            _ => "",
+        });
+    }
+
+    /// Writes a symbol name from a specialization payload, demangled if
+    /// possible.
+    fn write_mangled_name(&mut self, name: &str) {
+        match demangle(name, SwiftDemangleStyle::Full) {
+            Some(demangled) => self.out.push_str(&demangled),
+            None => self.out.push_str(name),
+        }
+    }
+
+    fn print_quoted(&mut self, text: &str) {
+        self.out.push('"');
+        for c in text.chars() {
+            match c {
+                '\\' => self.out.push_str("\\\\"),
+                '\t' => self.out.push_str("\\t"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '"' => self.out.push_str("\\\""),
+                '\0' => self.out.push_str("\\0"),
+                c if c.is_ascii_control() => {
+                    let _ = write!(self.out, "\\x{:02X}", c as u32);
+                }
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+}
+
+/// Returns the name which `swift-demangle` uses for a generic parameter:
+/// `A`, `B`, ... for the outermost parameters and `A1`, `B1`, ... for the
+/// next level.
+fn generic_parameter_name(depth: u64, index: u64) -> String {
+    let mut name = String::new();
+    let mut index = index;
+    loop {
+        name.push(char::from(b'A' + (index % 26) as u8));
+        index /= 26;
+        if index == 0 {
+            break;
+        }
+    }
+    if depth != 0 {
+        name.push_str(&depth.to_string());
+    }
+    name
+}
+
+#[cfg(test)]
+mod test {
+    use super::{demangle, SwiftDemangleStyle};
+
+    fn full(name: &str) -> Option<String> {
+        demangle(name, SwiftDemangleStyle::Full)
+    }
+
+    fn simplified(name: &str) -> Option<String> {
+        demangle(name, SwiftDemangleStyle::Simplified)
+    }
+
+    #[test]
+    fn demangle_swift_full() {
+        assert!(full("main").is_none());
+        assert!(full("_ZN3foo3barE").is_none());
+        assert_eq!(full("$s4main3fooyyF"), Some("main.foo() -> ()".to_string()));
+        assert_eq!(
+            full("_$s4main3fooyyF"),
+            Some("main.foo() -> ()".to_string())
+        );
+        assert_eq!(
+            full("_T04main3fooyyF"),
+            Some("main.foo() -> ()".to_string())
+        );
+        assert_eq!(
+            full("__T04main3fooyyF"),
+            Some("main.foo() -> ()".to_string())
+        );
+        assert_eq!(
+            full("_$S4main3fooyyF"),
+            Some("main.foo() -> ()".to_string())
+        );
+        assert!(full("__$s4main3fooyyF").is_none());
+        assert_eq!(
+            full("$sSS7cStringSSSPys4Int8VG_tcfC"),
+            Some(
+                "Swift.String.init(cString: Swift.UnsafePointer<Swift.Int8>) -> Swift.String"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            full("$sSa6appendyyxnF"),
+            Some("Swift.Array.append(__owned A) -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3foo1x1yySi_SStF"),
+            Some("main.foo(x: Swift.Int, y: Swift.String) -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3FooV3barSivg"),
+            Some("main.Foo.bar.getter : Swift.Int".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyyFyycfU_"),
+            Some("closure #1 () -> () in main.foo() -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyySDySSSiGF"),
+            Some("main.foo([Swift.String : Swift.Int]) -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyySiSgF"),
+            Some("main.foo(Swift.Int?) -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyyYaKF"),
+            Some("main.foo() async throws -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyyxAA1PRzlF"),
+            Some("main.foo<A where A: main.P>(A) -> ()".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyyxlFSi_Tg5"),
+            Some("generic specialization <Swift.Int> of main.foo<A>(A) -> ()".to_string())
+        );
+        assert_eq!(
+            full("$sSa4mainSQRzlE3fooyyF"),
+            Some(
+                "(extension in main):Swift.Array<A where A: Swift.Equatable>.foo() -> ()"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            full("$s4main3FooVAA1PA2aDP3baryyFTW"),
+            Some(
+                "protocol witness for main.P.bar() -> () in conformance main.Foo : main.P in main"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            full("$sSo8NSObjectCN"),
+            Some("type metadata for __ObjC.NSObject".to_string())
+        );
+        assert_eq!(
+            full("$s4main3fooyyF.cold.1"),
+            Some("main.foo() -> () with unmangled suffix \".cold.1\"".to_string())
+        );
+    }
+
+    #[test]
+    fn demangle_swift_simplified() {
+        assert_eq!(simplified("$s4main3fooyyF"), Some("foo()".to_string()));
+        assert_eq!(simplified("_$s4main3fooyyF"), Some("foo()".to_string()));
+        assert_eq!(simplified("__T04main3fooyyF"), Some("foo()".to_string()));
+        assert_eq!(
+            simplified("$sSS7cStringSSSPys4Int8VG_tcfC"),
+            Some("String.init(cString:)".to_string())
+        );
+        assert_eq!(
+            simplified("$sSa6appendyyxnF"),
+            Some("Array.append(_:)".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3foo1x1yySi_SStF"),
+            Some("foo(x:y:)".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3fooyySi_SStF"),
+            Some("foo(_:_:)".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3FooV3barSivg"),
+            Some("Foo.bar.getter".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3fooyyFyycfU_"),
+            Some("closure #1 in foo()".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3FooCfD"),
+            Some("Foo.__deallocating_deinit".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main1AC3fooyyFZ"),
+            Some("static A.foo()".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3fooyyxlFSi_Tg5"),
+            Some("specialized foo<A>(_:)".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3fooyyFTA"),
+            Some("partial apply for foo()".to_string())
+        );
+        assert_eq!(simplified("$s4main3fooyyFTm"), Some("foo()".to_string()));
+        assert_eq!(
+            simplified("$s4main3foo1xySi_tFfA_"),
+            Some("default argument 0 of foo(x:)".to_string())
+        );
+        assert_eq!(
+            simplified("$s4main3fooyyF.cold.1"),
+            Some("foo()".to_string())
+        );
+    }
+}
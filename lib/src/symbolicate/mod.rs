@@ -1,4 +1,5 @@
 pub mod demangle;
 pub mod demangle_ocaml;
+pub mod demangle_swift;
 pub mod request_json;
 pub mod v5;
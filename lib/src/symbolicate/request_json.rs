@@ -1,21 +1,88 @@
+use super::demangle::DemangleOptions;
+use crate::path_mapper::PathMappingRule;
 use serde::Deserialize;
 
+#[derive(Deserialize, Debug)]
+pub struct Request {
+    #[serde(flatten)]
+    pub options: RequestOptions,
+
+    #[serde(flatten)]
+    jobs: Jobs,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
-pub enum Request {
+enum Jobs {
     WithJobsList { jobs: Vec<Job> },
     JustOneJob(Job),
 }
 
 impl Request {
     pub fn jobs(&self) -> JobIterator {
-        match self {
-            Request::WithJobsList { jobs } => JobIterator::WithJobsList(jobs.iter()),
-            Request::JustOneJob(job) => JobIterator::JustOneJob(std::iter::once(job)),
+        match &self.jobs {
+            Jobs::WithJobsList { jobs } => JobIterator::WithJobsList(jobs.iter()),
+            Jobs::JustOneJob(job) => JobIterator::JustOneJob(std::iter::once(job)),
         }
     }
 }
 
+/// Options which are not part of the Tecken API. They can be specified next to
+/// "jobs" (or next to "memoryMap" and "stacks"), and all of them are off by default.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    /// Whether to return the names of other symbols at the same address as the
+    /// function symbol, in "function_aliases".
+    #[serde(default)]
+    pub with_aliases: bool,
+
+    /// Whether to report parts of functions which were split off by the compiler,
+    /// such as `foo.cold.1`, under the name of the parent function. Frames in such
+    /// parts get "function_split_part": true.
+    #[serde(default)]
+    pub attribute_split_functions_to_parent: bool,
+
+    /// Whether to return where each symbol came from ("symbol_source"), the
+    /// section which contains the address ("section"), and whether the address
+    /// is outside of all executable sections ("outside_text").
+    #[serde(default)]
+    pub with_symbol_provenance: bool,
+
+    /// Whether the addresses are pointers into data sections, to be looked up in
+    /// the symbols for global, static and thread-local variables rather than in
+    /// the function symbols. No debug info is returned in this mode.
+    #[serde(default)]
+    pub data_symbols: bool,
+
+    /// Whether to return the column number next to the line number ("column"),
+    /// for the frame and for its inline frames, if the debug info has it.
+    #[serde(default)]
+    pub with_columns: bool,
+
+    /// Whether to return where the function of each frame starts in the source
+    /// ("function_start_file" and "function_start_line"), and for inline frames,
+    /// the address where the inlined code starts ("inline_range_start").
+    #[serde(default)]
+    pub with_function_start: bool,
+
+    /// Whether to return the checksum which the debug info records for the
+    /// source file of each frame and inline frame ("file_checksum").
+    #[serde(default)]
+    pub with_file_checksums: bool,
+
+    /// How function names are demangled and simplified. By default, names are
+    /// demangled, with parameters, and Rust hashes are removed.
+    #[serde(default)]
+    pub demangling: DemangleOptions,
+
+    /// Rules which rewrite the source file paths from the debug info, e.g. from
+    /// the paths on the build machine to special paths or to a local checkout.
+    /// They are tried in order, and the first matching rule wins.
+    #[serde(default)]
+    pub path_mappings: Vec<PathMappingRule>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Job {
@@ -59,6 +126,7 @@ impl<'a> Iterator for JobIterator<'a> {
 #[cfg(test)]
 mod test {
 
+    use super::super::demangle::{DemangleOptions, NameFormat};
     use super::super::request_json::Request;
     use serde_json::Result;
 
@@ -120,4 +188,87 @@ mod test {
         assert_eq!(r.jobs().count(), 1);
         Ok(())
     }
+
+    #[test]
+    fn parse_options() -> Result<()> {
+        let data = r#"
+        {
+            "jobs": [
+                {
+                  "memoryMap": [
+                    [
+                      "xul.pdb",
+                      "44E4EC8C2F41492B9369D6B9A059577C2"
+                    ]
+                  ],
+                  "stacks": [
+                    [
+                      [0, 11723767]
+                    ]
+                  ]
+                }
+            ],
+            "withAliases": true,
+            "attributeSplitFunctionsToParent": true,
+            "withSymbolProvenance": true,
+            "dataSymbols": true,
+            "withColumns": true,
+            "withFunctionStart": true,
+            "withFileChecksums": true,
+            "demangling": {
+                "format": "both",
+                "withParams": false,
+                "maxLength": 100
+            },
+            "pathMappings": [
+                { "prefix": "/builds/worker/checkouts/gecko/", "mapped": "hg:hg.mozilla.org/mozilla-central:${path}:1706d4d54ec6" },
+                { "regex": "^/home/ci/src/(?P<path>.*)$", "local": "/home/me/src/${path}" }
+            ]
+        }"#;
+
+        let r: Request = serde_json::from_str(data)?;
+        assert_eq!(r.jobs().count(), 1);
+        assert!(r.options.with_aliases);
+        assert!(r.options.attribute_split_functions_to_parent);
+        assert!(r.options.with_symbol_provenance);
+        assert!(r.options.data_symbols);
+        assert!(r.options.with_columns);
+        assert!(r.options.with_function_start);
+        assert!(r.options.with_file_checksums);
+        assert!(r.options.demangling.format == NameFormat::Both);
+        assert!(!r.options.demangling.with_params);
+        assert!(!r.options.demangling.strip_template_args);
+        assert!(r.options.demangling.strip_rust_hash);
+        assert!(r.options.demangling.max_length == Some(100));
+        assert_eq!(r.options.path_mappings.len(), 2);
+        assert!(!r.options.path_mappings[0].maps_to_local_path());
+        assert!(r.options.path_mappings[1].maps_to_local_path());
+
+        let data = r#"
+        {
+            "memoryMap": [
+              [
+                "xul.pdb",
+                "44E4EC8C2F41492B9369D6B9A059577C2"
+              ]
+            ],
+            "stacks": [
+              [
+                [0, 11723767]
+              ]
+            ]
+          }
+          "#;
+
+        let r: Request = serde_json::from_str(data)?;
+        assert_eq!(r.jobs().count(), 1);
+        assert!(!r.options.with_aliases);
+        assert!(!r.options.with_symbol_provenance);
+        assert!(!r.options.with_columns);
+        assert!(!r.options.with_function_start);
+        assert!(!r.options.with_file_checksums);
+        assert!(r.options.demangling == DemangleOptions::default());
+        assert!(r.options.path_mappings.is_empty());
+        Ok(())
+    }
 }
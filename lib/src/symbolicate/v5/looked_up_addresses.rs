@@ -1,5 +1,7 @@
-use super::super::demangle;
-use crate::shared::{AddressDebugInfo, InlineStackFrame, SymbolicationResult};
+use crate::error::GetSymbolsError;
+use crate::shared::{
+    AddressDebugInfo, AddressSymbolInfo, InlineStackFrame, SymbolSource, SymbolicationResult,
+};
 use std::collections::BTreeMap;
 use std::ops::Deref;
 
@@ -7,6 +9,11 @@ pub struct AddressResult {
     pub symbol_address: u32,
     pub symbol_name: String,
     pub function_size: Option<u32>,
+    pub symbol_aliases: Vec<String>,
+    pub is_split_function_part: bool,
+    pub symbol_source: SymbolSource,
+    pub section_name: Option<String>,
+    pub is_outside_text: bool,
     pub inline_frames: Option<Vec<InlineStackFrame>>,
 }
 
@@ -15,6 +22,10 @@ pub type AddressResults = BTreeMap<u32, Option<AddressResult>>;
 pub struct LookedUpAddresses {
     pub address_results: AddressResults,
     pub symbol_count: u32,
+    pub debug_info_errors: Vec<GetSymbolsError>,
+    /// Whether the names from the symbol table have an extra leading underscore,
+    /// as they do in mach-O binaries.
+    pub symbol_names_have_leading_underscore: bool,
 }
 
 impl SymbolicationResult for LookedUpAddresses {
@@ -26,24 +37,31 @@ impl SymbolicationResult for LookedUpAddresses {
         LookedUpAddresses {
             address_results: addresses.iter().map(|&addr| (addr, None)).collect(),
             symbol_count: 0,
+            debug_info_errors: Vec::new(),
+            symbol_names_have_leading_underscore: false,
         }
     }
 
-    fn add_address_symbol(
-        &mut self,
-        address: u32,
-        symbol_address: u32,
-        symbol_name: &str,
-        function_size: Option<u32>,
-    ) {
+    fn add_address_symbol(&mut self, address: u32, symbol: AddressSymbolInfo) {
         *self.address_results.get_mut(&address).unwrap() = Some(AddressResult {
-            symbol_address,
-            symbol_name: demangle::demangle_any(symbol_name),
-            function_size,
+            symbol_address: symbol.symbol_address,
+            symbol_name: symbol.symbol_name.to_string(),
+            function_size: symbol.function_size,
+            symbol_aliases: Vec::new(),
+            is_split_function_part: symbol.is_split_function_part,
+            symbol_source: symbol.source,
+            section_name: symbol.section_name.map(str::to_string),
+            is_outside_text: symbol.is_outside_text,
             inline_frames: None,
         });
     }
 
+    fn add_address_symbol_aliases(&mut self, address: u32, aliases: &[&str]) {
+        if let Some(address_result) = self.address_results.get_mut(&address).unwrap() {
+            address_result.symbol_aliases = aliases.iter().map(|alias| alias.to_string()).collect();
+        }
+    }
+
     fn add_address_debug_info(&mut self, address: u32, info: AddressDebugInfo) {
         let outer_function_name = info.frames.last().and_then(|f| f.function.as_deref());
         let entry = self.address_results.get_mut(&address).unwrap();
@@ -51,8 +69,16 @@ impl SymbolicationResult for LookedUpAddresses {
         match entry {
             Some(address_result) => {
                 // Overwrite the symbol name with the function name from the debug info.
+                // PDB files and Go function tables supply both the symbol and the debug
+                // info, so the source only changes if the symbol came from somewhere else.
                 if let Some(name) = outer_function_name {
                     address_result.symbol_name = name.to_string();
+                    if !matches!(
+                        address_result.symbol_source,
+                        SymbolSource::Pdb | SymbolSource::GoPclntab
+                    ) {
+                        address_result.symbol_source = SymbolSource::Dwarf;
+                    }
                 }
                 // Add the inline frame info.``
                 address_result.inline_frames = Some(info.frames);
@@ -66,6 +92,11 @@ impl SymbolicationResult for LookedUpAddresses {
                     symbol_name: outer_function_name
                         .map_or_else(|| format!("0x{:x}", address), str::to_string),
                     function_size: None,
+                    symbol_aliases: Vec::new(),
+                    is_split_function_part: false,
+                    symbol_source: SymbolSource::Dwarf,
+                    section_name: None,
+                    is_outside_text: false,
                     inline_frames: Some(info.frames),
                 });
             }
@@ -75,4 +106,12 @@ impl SymbolicationResult for LookedUpAddresses {
     fn set_total_symbol_count(&mut self, total_symbol_count: u32) {
         self.symbol_count = total_symbol_count;
     }
+
+    fn add_debug_info_error(&mut self, error: GetSymbolsError) {
+        self.debug_info_errors.push(error);
+    }
+
+    fn set_symbol_names_have_leading_underscore(&mut self) {
+        self.symbol_names_have_leading_underscore = true;
+    }
 }
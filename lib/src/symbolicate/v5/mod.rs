@@ -1,13 +1,16 @@
 use crate::error::{GetSymbolsError, Result};
-use crate::shared::{FileAndPathHelper, SymbolicationQuery, SymbolicationResultKind};
+use crate::shared::{
+    FileAndPathHelper, QuerySymbolKind, SymbolicationQuery, SymbolicationResultKind,
+};
 use crate::to_debug_id;
 use std::collections::HashMap;
 
 pub mod looked_up_addresses;
 pub mod response_json;
 
-use super::request_json::{self, Lib};
-use looked_up_addresses::{AddressResults, LookedUpAddresses};
+use super::request_json::{self, Lib, RequestOptions};
+use crate::shared::SymbolSource;
+use looked_up_addresses::LookedUpAddresses;
 use serde_json::json;
 
 pub async fn query_api_json<'h>(
@@ -37,8 +40,13 @@ pub async fn query_api<'h>(
     with_debug_info: bool,
 ) -> Result<response_json::Response> {
     let requested_addresses = gather_requested_addresses(request)?;
-    let symbolicated_addresses =
-        symbolicate_requested_addresses(requested_addresses, helper, with_debug_info).await;
+    let symbolicated_addresses = symbolicate_requested_addresses(
+        requested_addresses,
+        &request.options,
+        helper,
+        with_debug_info,
+    )
+    .await;
     Ok(create_response(request, symbolicated_addresses))
 }
 
@@ -71,6 +79,7 @@ fn gather_requested_addresses(request: &request_json::Request) -> Result<HashMap
 
 async fn symbolicate_requested_addresses<'h>(
     requested_addresses: HashMap<Lib, Vec<u32>>,
+    options: &RequestOptions,
     helper: &'h impl FileAndPathHelper<'h>,
     with_debug_info: bool,
 ) -> HashMap<Lib, Result<LookedUpAddresses>> {
@@ -88,6 +97,14 @@ async fn symbolicate_requested_addresses<'h>(
                             addresses: &addresses,
                             with_debug_info,
                         },
+                        attribute_split_functions_to_parent: options
+                            .attribute_split_functions_to_parent,
+                        symbol_kind: if options.data_symbols {
+                            QuerySymbolKind::Data
+                        } else {
+                            QuerySymbolKind::Functions
+                        },
+                        path_mapping_rules: &options.path_mappings,
                     },
                     helper,
                 )
@@ -108,6 +125,7 @@ fn create_response(
 
     fn result_for_job(
         job: &request_json::Job,
+        options: &RequestOptions,
         symbolicated_addresses: &HashMap<Lib, crate::Result<LookedUpAddresses>>,
     ) -> Result {
         let mut found_modules = HashMap::new();
@@ -118,8 +136,13 @@ fn create_response(
                 let module_key = format!("{}/{}", lib.debug_name, lib.breakpad_id);
                 match symbol_result {
                     Ok(symbols) => {
-                        symbols_by_module_index
-                            .insert(module_index as u32, &symbols.address_results);
+                        symbols_by_module_index.insert(module_index as u32, symbols);
+                        if !symbols.debug_info_errors.is_empty() {
+                            module_errors.insert(
+                                module_key.clone(),
+                                symbols.debug_info_errors.iter().map(Into::into).collect(),
+                            );
+                        }
                     }
                     Err(err) => {
                         module_errors.insert(module_key.clone(), vec![err.into()]);
@@ -130,7 +153,12 @@ fn create_response(
         }
 
         let stacks = job.stacks.iter().map(|stack| {
-            response_stack_for_request_stack(stack, &job.memory_map, &symbols_by_module_index)
+            response_stack_for_request_stack(
+                stack,
+                &job.memory_map,
+                options,
+                &symbols_by_module_index,
+            )
         });
 
         Result {
@@ -143,13 +171,15 @@ fn create_response(
     fn response_stack_for_request_stack<'a>(
         stack: &request_json::Stack,
         memory_map: &[Lib],
-        symbols_by_module_index: &HashMap<u32, &'a AddressResults>,
+        options: &RequestOptions,
+        symbols_by_module_index: &HashMap<u32, &'a LookedUpAddresses>,
     ) -> Stack {
         let frames = stack.0.iter().enumerate().map(|(frame_index, frame)| {
             response_frame_for_request_frame(
                 frame,
                 frame_index as u32,
                 memory_map,
+                options,
                 symbols_by_module_index,
             )
         });
@@ -160,37 +190,119 @@ fn create_response(
         frame: &request_json::StackFrame,
         frame_index: u32,
         memory_map: &[Lib],
-        symbols_by_module_index: &HashMap<u32, &'a AddressResults>,
+        options: &RequestOptions,
+        symbols_by_module_index: &HashMap<u32, &'a LookedUpAddresses>,
     ) -> StackFrame {
         let symbol = symbols_by_module_index
             .get(&frame.module_index)
-            .and_then(|symbol_map| {
+            .and_then(|symbols| {
+                // Mach-O symbol tables prefix C names with an underscore. Names which
+                // were replaced by the function name from the debug info don't have it.
+                let format_symbol_name = |name: &str, from_symbol_table: bool| {
+                    if symbols.symbol_names_have_leading_underscore && from_symbol_table {
+                        options.demangling.format_macho_symbol_name(name)
+                    } else {
+                        options.demangling.format_name(name)
+                    }
+                };
                 // If we have a symbol table for this library, then we know that
                 // this address is present in it.
-                symbol_map
+                symbols
+                    .address_results
                     .get(&frame.address)
                     .unwrap()
                     .as_ref()
                     .map(|address_result| Symbol {
-                        function: address_result.symbol_name.clone(),
+                        function: format_symbol_name(
+                            &address_result.symbol_name,
+                            matches!(
+                                address_result.symbol_source,
+                                SymbolSource::SymbolTable
+                                    | SymbolSource::DynamicSymbolTable
+                                    | SymbolSource::Export
+                            ),
+                        ),
+                        function_raw: options.demangling.raw_name(&address_result.symbol_name),
                         function_offset: frame.address - address_result.symbol_address,
                         function_size: address_result.function_size,
+                        function_aliases: if options.with_aliases {
+                            address_result
+                                .symbol_aliases
+                                .iter()
+                                .map(|alias| format_symbol_name(alias, true))
+                                .collect()
+                        } else {
+                            Vec::new()
+                        },
+                        function_split_part: address_result.is_split_function_part,
+                        symbol_source: if options.with_symbol_provenance {
+                            Some(address_result.symbol_source.as_str())
+                        } else {
+                            None
+                        },
+                        section: if options.with_symbol_provenance {
+                            address_result.section_name.clone()
+                        } else {
+                            None
+                        },
+                        outside_text: options.with_symbol_provenance
+                            && address_result.is_outside_text,
                         debug_info: address_result.inline_frames.as_ref().map(|frames| {
                             let (outer, inlines) = frames
                                 .split_last()
                                 .expect("inline_frames should always have at least one element");
                             DebugInfo {
                                 file: outer.file_path.as_ref().map(|p| p.mapped_path().into()),
+                                file_checksum: outer
+                                    .file_checksum
+                                    .as_ref()
+                                    .filter(|_| options.with_file_checksums)
+                                    .map(|checksum| checksum.to_string()),
                                 line: outer.line_number,
+                                column: outer.column_number.filter(|_| options.with_columns),
+                                function_start_file: outer
+                                    .function_decl_file_path
+                                    .as_ref()
+                                    .filter(|_| options.with_function_start)
+                                    .map(|p| p.mapped_path().into()),
+                                function_start_line: outer
+                                    .function_decl_line_number
+                                    .filter(|_| options.with_function_start),
                                 inlines: inlines
                                     .iter()
                                     .map(|inline_frame| InlineStackFrame {
-                                        function: inline_frame.function.clone(),
+                                        function: inline_frame
+                                            .function
+                                            .as_deref()
+                                            .map(|name| options.demangling.format_name(name)),
+                                        function_raw: inline_frame
+                                            .function
+                                            .as_deref()
+                                            .and_then(|name| options.demangling.raw_name(name)),
                                         file: inline_frame
                                             .file_path
                                             .as_ref()
                                             .map(|p| p.mapped_path().into()),
+                                        file_checksum: inline_frame
+                                            .file_checksum
+                                            .as_ref()
+                                            .filter(|_| options.with_file_checksums)
+                                            .map(|checksum| checksum.to_string()),
                                         line: inline_frame.line_number,
+                                        column: inline_frame
+                                            .column_number
+                                            .filter(|_| options.with_columns),
+                                        function_start_file: inline_frame
+                                            .function_decl_file_path
+                                            .as_ref()
+                                            .filter(|_| options.with_function_start)
+                                            .map(|p| p.mapped_path().into()),
+                                        function_start_line: inline_frame
+                                            .function_decl_line_number
+                                            .filter(|_| options.with_function_start),
+                                        inline_range_start: inline_frame
+                                            .inline_range_start
+                                            .filter(|_| options.with_function_start),
                                     })
                                     .collect(),
                             }
@@ -208,7 +320,182 @@ fn create_response(
     Response {
         results: request
             .jobs()
-            .map(|job| result_for_job(job, &symbolicated_addresses))
+            .map(|job| result_for_job(job, &request.options, &symbolicated_addresses))
             .collect(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::create_response;
+    use super::looked_up_addresses::{AddressResult, LookedUpAddresses};
+    use crate::shared::{FilePath, InlineStackFrame, SymbolSource};
+    use crate::symbolicate::request_json::{Lib, Request};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    /// A symbol for the function at 0x1000, which contains the address 0x1010.
+    fn address_result(symbol_name: &str, symbol_source: SymbolSource) -> AddressResult {
+        AddressResult {
+            symbol_address: 0x1000,
+            symbol_name: symbol_name.to_string(),
+            function_size: Some(0x20),
+            symbol_aliases: Vec::new(),
+            is_split_function_part: false,
+            symbol_source,
+            section_name: Some(".text".to_string()),
+            is_outside_text: false,
+            inline_frames: None,
+        }
+    }
+
+    /// Debug info for the address 0x1010, where `inlined` was inlined into `foo`.
+    fn inline_frames() -> Vec<InlineStackFrame> {
+        let frame =
+            |function: &str, line, column, decl_line, inline_range_start| InlineStackFrame {
+                function: Some(function.to_string()),
+                file_path: Some(FilePath::NonLocal("foo.c".to_string())),
+                file_checksum: None,
+                line_number: Some(line),
+                column_number: Some(column),
+                function_decl_file_path: Some(FilePath::NonLocal("foo.c".to_string())),
+                function_decl_line_number: Some(decl_line),
+                inline_range_start,
+            };
+        vec![
+            frame("inlined", 5, 3, 4, Some(0x1008)),
+            frame("foo", 12, 7, 10, None),
+        ]
+    }
+
+    /// Returns the response frame for the address 0x1010 in libfoo.so, for a request
+    /// with the options in `options`, if `address_result` is the result of the lookup.
+    fn response_frame(
+        options: Value,
+        address_result: AddressResult,
+        symbol_names_have_leading_underscore: bool,
+    ) -> Value {
+        let lib = Lib {
+            debug_name: "libfoo.so".to_string(),
+            breakpad_id: "B3CC644ECC086E044C4C44205044422E1".to_string(),
+        };
+        let mut request = json!({
+            "memoryMap": [[lib.debug_name, lib.breakpad_id]],
+            "stacks": [[[0, 0x1010]]],
+        });
+        for (key, value) in options.as_object().unwrap() {
+            request[key] = value.clone();
+        }
+        let request: Request = serde_json::from_value(request).unwrap();
+        let looked_up_addresses = LookedUpAddresses {
+            address_results: std::iter::once((0x1010, Some(address_result))).collect(),
+            symbol_count: 1,
+            debug_info_errors: Vec::new(),
+            symbol_names_have_leading_underscore,
+        };
+        let mut symbolicated_addresses = HashMap::new();
+        symbolicated_addresses.insert(lib, Ok(looked_up_addresses));
+        let response = create_response(&request, symbolicated_addresses);
+        serde_json::to_value(response).unwrap()["results"][0]["stacks"][0][0].clone()
+    }
+
+    #[test]
+    fn default_options() {
+        // Mach-O symbol table and export names lose their leading underscore.
+        let result = address_result("_main", SymbolSource::SymbolTable);
+        let frame = response_frame(json!({}), result, true);
+        assert_eq!(frame["function"], "main");
+        assert!(frame.get("function_raw").is_none());
+        let result = address_result("_main", SymbolSource::Export);
+        assert_eq!(response_frame(json!({}), result, true)["function"], "main");
+
+        // Names from the debug info, and from binaries without the prefix, keep it.
+        let result = address_result("_main", SymbolSource::Dwarf);
+        assert_eq!(response_frame(json!({}), result, true)["function"], "_main");
+        let result = address_result("_main", SymbolSource::SymbolTable);
+        assert_eq!(
+            response_frame(json!({}), result, false)["function"],
+            "_main"
+        );
+
+        // Names are demangled, with their parameters.
+        let result = address_result("_ZN3foo3barEi", SymbolSource::SymbolTable);
+        let frame = response_frame(json!({}), result, false);
+        assert_eq!(frame["function"], "foo::bar(int)");
+        assert!(frame.get("function_raw").is_none());
+        let result = address_result("__ZN3foo3barEi", SymbolSource::SymbolTable);
+        let frame = response_frame(json!({}), result, true);
+        assert_eq!(frame["function"], "foo::bar(int)");
+    }
+
+    #[test]
+    fn symbol_provenance() {
+        let data_result = || {
+            let mut result = address_result("some_table", SymbolSource::DynamicSymbolTable);
+            result.section_name = Some(".rodata".to_string());
+            result.is_outside_text = true;
+            result
+        };
+        let frame = response_frame(json!({}), data_result(), false);
+        assert_eq!(frame["function"], "some_table");
+        assert!(frame.get("symbol_source").is_none());
+        assert!(frame.get("section").is_none());
+        assert!(frame.get("outside_text").is_none());
+
+        let frame = response_frame(
+            json!({ "withSymbolProvenance": true }),
+            data_result(),
+            false,
+        );
+        assert_eq!(frame["symbol_source"], "dynsym");
+        assert_eq!(frame["section"], ".rodata");
+        assert_eq!(frame["outside_text"], true);
+
+        // Addresses in code sections don't get "outside_text".
+        let result = address_result("main", SymbolSource::Dwarf);
+        let frame = response_frame(json!({ "withSymbolProvenance": true }), result, false);
+        assert_eq!(frame["symbol_source"], "dwarf");
+        assert_eq!(frame["section"], ".text");
+        assert!(frame.get("outside_text").is_none());
+    }
+
+    #[test]
+    fn columns() {
+        let mut result = address_result("foo", SymbolSource::Dwarf);
+        result.inline_frames = Some(inline_frames());
+        let frame = response_frame(json!({}), result, false);
+        assert_eq!(frame["line"], 12);
+        assert!(frame.get("column").is_none());
+        assert_eq!(frame["inlines"][0]["line"], 5);
+        assert!(frame["inlines"][0].get("column").is_none());
+
+        let mut result = address_result("foo", SymbolSource::Dwarf);
+        result.inline_frames = Some(inline_frames());
+        let frame = response_frame(json!({ "withColumns": true }), result, false);
+        assert_eq!(frame["column"], 7);
+        assert_eq!(frame["inlines"][0]["column"], 3);
+    }
+
+    #[test]
+    fn function_start() {
+        let mut result = address_result("foo", SymbolSource::Dwarf);
+        result.inline_frames = Some(inline_frames());
+        let frame = response_frame(json!({}), result, false);
+        assert!(frame.get("function_start_file").is_none());
+        assert!(frame.get("function_start_line").is_none());
+        let inline = &frame["inlines"][0];
+        assert!(inline.get("function_start_file").is_none());
+        assert!(inline.get("function_start_line").is_none());
+        assert!(inline.get("inline_range_start").is_none());
+
+        let mut result = address_result("foo", SymbolSource::Dwarf);
+        result.inline_frames = Some(inline_frames());
+        let frame = response_frame(json!({ "withFunctionStart": true }), result, false);
+        assert_eq!(frame["function_start_file"], "foo.c");
+        assert_eq!(frame["function_start_line"], 10);
+        let inline = &frame["inlines"][0];
+        assert_eq!(inline["function_start_file"], "foo.c");
+        assert_eq!(inline["function_start_line"], 4);
+        assert_eq!(inline["inline_range_start"], "0x1008");
+    }
+}
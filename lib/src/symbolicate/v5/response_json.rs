@@ -37,6 +37,9 @@ pub struct StackFrame {
 pub struct Symbol {
     pub function: String,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_raw: Option<String>,
+
     #[serde(serialize_with = "as_hex_string")]
     pub function_offset: u32,
 
@@ -46,6 +49,21 @@ pub struct Symbol {
     )]
     pub function_size: Option<u32>,
 
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub function_aliases: Vec<String>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub function_split_part: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_source: Option<&'static str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub outside_text: bool,
+
     #[serde(flatten)]
     pub debug_info: Option<DebugInfo>,
 }
@@ -55,9 +73,21 @@ pub struct DebugInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_checksum: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line: Option<u32>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_start_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_start_line: Option<u32>,
+
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub inlines: Vec<InlineStackFrame>,
 }
@@ -67,11 +97,32 @@ pub struct InlineStackFrame {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_raw: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_checksum: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_start_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_start_line: Option<u32>,
+
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "as_optional_hex_string"
+    )]
+    pub inline_range_start: Option<u32>,
 }
 
 #[derive(Serialize, Debug)]
@@ -137,8 +188,14 @@ mod test {
                         module: String::from("xul.pdb"),
                         symbol: Some(response_json::Symbol {
                             function: String::from("sctp_send_initiate"),
+                            function_raw: None,
                             function_offset: 0x4ca,
                             function_size: None,
+                            function_aliases: vec![],
+                            function_split_part: false,
+                            symbol_source: None,
+                            section: None,
+                            outside_text: false,
                             debug_info: None,
                         }),
                     },
@@ -0,0 +1,93 @@
+//! A `FileAndPathHelper` for tests, which serves files from memory.
+
+use crate::shared::{
+    CandidatePathInfo, FileAndPathHelper, FileAndPathHelperResult, FileLocation,
+    ResolvedSpecialPath, SpecialPath,
+};
+use debugid::DebugId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+#[derive(Default)]
+pub struct TestHelper {
+    /// The files which can be opened, by path.
+    pub files: HashMap<PathBuf, Vec<u8>>,
+    /// The new locations of object files which were moved after linking, by the
+    /// path which is stored in the debug map of the mach-O binary. Object files
+    /// which are not in this map have no candidate paths.
+    pub moved_object_files: HashMap<PathBuf, PathBuf>,
+    /// What `resolve_special_path` returns for every special path.
+    pub special_path_contents: Option<Vec<u8>>,
+    /// The special paths which `resolve_special_path` was called with.
+    pub resolved_special_paths: RefCell<Vec<SpecialPath>>,
+}
+
+impl<'h> FileAndPathHelper<'h> for TestHelper {
+    type F = Vec<u8>;
+    type OpenFileFuture = std::future::Ready<FileAndPathHelperResult<Vec<u8>>>;
+
+    fn get_candidate_paths_for_binary_or_pdb(
+        &self,
+        _debug_name: &str,
+        _debug_id: &DebugId,
+    ) -> FileAndPathHelperResult<Vec<CandidatePathInfo>> {
+        Ok(Vec::new())
+    }
+
+    fn get_candidate_paths_for_object_file(
+        &self,
+        object_path: &Path,
+        _archive_member_name: Option<&str>,
+    ) -> FileAndPathHelperResult<Vec<FileLocation>> {
+        Ok(self
+            .moved_object_files
+            .get(object_path)
+            .map(|path| FileLocation::Path(path.clone()))
+            .into_iter()
+            .collect())
+    }
+
+    fn open_file(&'h self, location: &FileLocation) -> Self::OpenFileFuture {
+        let contents = match location {
+            FileLocation::Path(path) => self.files.get(path).cloned(),
+            _ => None,
+        };
+        std::future::ready(contents.ok_or_else(|| "No such file".into()))
+    }
+
+    fn resolve_special_path(
+        &self,
+        special_path: &SpecialPath,
+    ) -> FileAndPathHelperResult<Option<ResolvedSpecialPath>> {
+        self.resolved_special_paths
+            .borrow_mut()
+            .push(special_path.clone());
+        Ok(self
+            .special_path_contents
+            .clone()
+            .map(ResolvedSpecialPath::Contents))
+    }
+}
+
+/// Runs `future` to completion. The futures of `TestHelper` are always ready, so
+/// this never needs to wait.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    fn noop_raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            noop_raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("The future should not need to wait"),
+    }
+}
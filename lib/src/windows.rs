@@ -2,16 +2,22 @@ use crate::debugid_util::debug_id_for_object;
 use crate::error::{Context, GetSymbolsError, Result};
 use crate::path_mapper::{ExtraPathMapper, PathMapper};
 use crate::shared::{
-    get_symbolication_result_for_addresses_from_object, object_to_map, AddressDebugInfo, BasePath,
-    FileAndPathHelper, FileContents, FileContentsWrapper, FileLocation, InlineStackFrame,
-    SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
+    get_data_symbolication_result, get_data_symbolication_result_from_object,
+    get_symbolication_result_for_addresses_from_object, object_to_map, AddressDebugInfo,
+    AddressSymbolInfo, BasePath, DataSymbol, FileAndPathHelper, FileContents, FileContentsWrapper,
+    FileLocation, InlineStackFrame, QuerySymbolKind, SectionInfo, SourceFileChecksum, StubSymbol,
+    SymbolSource, SymbolicationQuery, SymbolicationResult, SymbolicationResultKind,
 };
+use crate::sourcelink::SourceLinkPathMapper;
 use debugid::DebugId;
+use object::read::pe::{ImageNtHeaders, Import, PeFile, PeFile32, PeFile64};
+use object::{LittleEndian as LE, ReadRef};
 use pdb::PDB;
 use pdb_addr2line::pdb;
 use regex::Regex;
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
 use uuid::Uuid;
 
 pub async fn get_symbolication_result_via_binary<'h, R>(
@@ -80,6 +86,14 @@ where
         return Err(GetSymbolsError::UnmatchedDebugId(file_debug_id, debug_id));
     }
 
+    if query.symbol_kind == QuerySymbolKind::Data {
+        return Ok(get_data_symbolication_result_from_object(
+            &pe,
+            query.result_kind,
+            Vec::new(),
+        ));
+    }
+
     // Get function start and end addresses from the function list in .pdata.
     let mut function_starts = None;
     let mut function_ends = None;
@@ -92,9 +106,28 @@ where
         function_ends = Some(e);
     }
 
+    // Synthesize symbols for import thunks. This is only needed when we have no PDB:
+    // PDB files have public symbols for the thunks, named after the imported function,
+    // and the PDB path doesn't have the binary whose code we'd need to scan.
+    let import_thunks = match file_kind {
+        object::FileKind::Pe32 => PeFile32::parse(&file_contents)
+            .map(|pe| import_thunk_symbols(&pe))
+            .unwrap_or_default(),
+        object::FileKind::Pe64 => PeFile64::parse(&file_contents)
+            .map(|pe| import_thunk_symbols(&pe))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
     let r = match query.result_kind {
         SymbolicationResultKind::AllSymbols => {
-            let map = object_to_map(&pe, function_starts.as_deref());
+            let map = object_to_map(
+                &pe,
+                function_starts.as_deref(),
+                &import_thunks,
+                &[],
+                query.attribute_split_functions_to_parent,
+            );
             R::from_full_map(map)
         }
         SymbolicationResultKind::SymbolsForAddresses { addresses, .. } => {
@@ -103,6 +136,9 @@ where
                 &pe,
                 function_starts.as_deref(),
                 function_ends.as_deref(),
+                &import_thunks,
+                &[],
+                query.attribute_split_functions_to_parent,
             )
         }
     };
@@ -151,14 +187,57 @@ where
         return Err(GetSymbolsError::UnmatchedDebugId(file_debug_id, debug_id));
     }
 
-    let srcsrv_stream = if query.result_kind.wants_debug_info_for_addresses() {
-        match pdb.named_stream(b"srcsrv") {
+    if query.symbol_kind == QuerySymbolKind::Data {
+        let sections = pdb_section_infos(&mut pdb);
+        let symbols = pdb_data_symbols(&mut pdb)?;
+        return Ok(get_data_symbolication_result(
+            query.result_kind,
+            symbols,
+            &sections,
+        ));
+    }
+
+    let (srcsrv_stream, sourcelink_stream) = if query.result_kind.wants_debug_info_for_addresses() {
+        let srcsrv_stream = match pdb.named_stream(b"srcsrv") {
             Ok(stream) => Some(stream),
             Err(pdb::Error::StreamNameNotFound | pdb::Error::StreamNotFound(_)) => None,
             Err(e) => return Err(GetSymbolsError::PdbError("pdb.named_stream(srcsrv)", e)),
-        }
+        };
+        let sourcelink_stream = match pdb.named_stream(b"sourcelink") {
+            Ok(stream) => Some(stream),
+            Err(pdb::Error::StreamNameNotFound | pdb::Error::StreamNotFound(_)) => None,
+            Err(e) => return Err(GetSymbolsError::PdbError("pdb.named_stream(sourcelink)", e)),
+        };
+        (srcsrv_stream, sourcelink_stream)
     } else {
-        None
+        (None, None)
+    };
+
+    let sections = match query.result_kind {
+        SymbolicationResultKind::SymbolsForAddresses { .. } => pdb_section_infos(&mut pdb),
+        SymbolicationResultKind::AllSymbols => Vec::new(),
+    };
+
+    let (file_checksums, embedded_sources) = match query.result_kind {
+        SymbolicationResultKind::SymbolsForAddresses {
+            addresses,
+            with_debug_info: true,
+        } => {
+            // The checksums and the embedded sources are optional, so don't fail if
+            // they can't be read.
+            let source_files = pdb_source_files(&mut pdb, addresses).unwrap_or_default();
+            let embedded_sources = if R::wants_embedded_source() {
+                pdb_embedded_sources(&mut pdb, &source_files).unwrap_or_default()
+            } else {
+                HashMap::new()
+            };
+            let file_checksums: HashMap<_, _> = source_files
+                .into_iter()
+                .filter_map(|(name, checksum)| Some((name, checksum?)))
+                .collect();
+            (file_checksums, embedded_sources)
+        }
+        _ => (HashMap::new(), HashMap::new()),
     };
 
     let context_data = pdb_addr2line::ContextPdbData::try_from_pdb(pdb)
@@ -185,13 +264,21 @@ where
             addresses,
             with_debug_info,
         } => {
-            let path_mapper = match &srcsrv_stream {
+            let srcsrv_path_mapper = match &srcsrv_stream {
                 Some(srcsrv_stream) => Some(SrcSrvPathMapper::new(srcsrv::SrcSrvStream::parse(
                     srcsrv_stream.as_slice(),
                 )?)),
                 None => None,
             };
-            let mut path_mapper = PathMapper::new_with_maybe_extra_mapper(base_path, path_mapper);
+            // Ignore malformed SourceLink JSON; the paths are just not mapped then.
+            let sourcelink_path_mapper = sourcelink_stream
+                .as_ref()
+                .and_then(|stream| SourceLinkPathMapper::parse(stream.as_slice()).ok());
+            let mut path_mapper = PathMapper::new_with_maybe_extra_mapper(
+                base_path,
+                query.path_mapping_rules,
+                Some((srcsrv_path_mapper, sourcelink_path_mapper)),
+            );
             let mut map_path = |path: Cow<str>| path_mapper.map_path(&path);
 
             let mut symbolication_result = R::for_addresses(addresses);
@@ -208,20 +295,60 @@ where
                             .map(|end_rva| end_rva - function_frames.start_rva);
                         symbolication_result.add_address_symbol(
                             address,
-                            symbol_address,
-                            symbol_name,
-                            function_size,
+                            pdb_address_symbol_info(
+                                &sections,
+                                address,
+                                symbol_address,
+                                symbol_name,
+                                function_size,
+                            ),
                         );
                         if has_debug_info(&function_frames) {
-                            let frames: Vec<_> = function_frames
+                            // The line of the procedure's first instruction is the
+                            // line where the procedure starts. pdb-addr2line doesn't
+                            // tell us where inlined functions start, or where the
+                            // inlined code ranges start.
+                            let procedure_start = context
+                                .find_frames(function_frames.start_rva)
+                                .ok()
+                                .flatten()
+                                .and_then(|mut start_frames| start_frames.frames.pop());
+                            let mut frames: Vec<_> = function_frames
                                 .frames
                                 .into_iter()
-                                .map(|frame| InlineStackFrame {
-                                    function: frame.function,
-                                    file_path: frame.file.map(&mut map_path),
-                                    line_number: frame.line,
+                                .map(|frame| {
+                                    if let Some(file) = frame.file.as_deref() {
+                                        if let Some(source) = embedded_sources.get(file) {
+                                            symbolication_result.add_embedded_source(
+                                                map_path(Cow::from(file)),
+                                                source.clone(),
+                                            );
+                                        }
+                                    }
+                                    InlineStackFrame {
+                                        function: frame.function,
+                                        file_checksum: frame
+                                            .file
+                                            .as_deref()
+                                            .and_then(|file| file_checksums.get(file))
+                                            .cloned(),
+                                        file_path: frame.file.map(&mut map_path),
+                                        line_number: frame.line,
+                                        // pdb-addr2line doesn't return column information.
+                                        column_number: None,
+                                        function_decl_file_path: None,
+                                        function_decl_line_number: None,
+                                        inline_range_start: None,
+                                    }
                                 })
                                 .collect();
+                            if let (Some(outer_frame), Some(procedure_start)) =
+                                (frames.last_mut(), procedure_start)
+                            {
+                                outer_frame.function_decl_file_path =
+                                    procedure_start.file.map(&mut map_path);
+                                outer_frame.function_decl_line_number = procedure_start.line;
+                            }
                             if !frames.is_empty() {
                                 symbolication_result
                                     .add_address_debug_info(address, AddressDebugInfo { frames });
@@ -237,9 +364,13 @@ where
                     let function_size = func.end_rva.map(|end_rva| end_rva - func.start_rva);
                     symbolication_result.add_address_symbol(
                         address,
-                        symbol_address,
-                        symbol_name,
-                        function_size,
+                        pdb_address_symbol_info(
+                            &sections,
+                            address,
+                            symbol_address,
+                            symbol_name,
+                            function_size,
+                        ),
                     );
                 }
             }
@@ -258,6 +389,18 @@ where
 ///   - "hg:<repo>:<path>:<rev>"
 ///   - "git:<repo>:<path>:<rev>"
 ///   - "s3:<bucket>:<digest_and_path>:"
+///   - "p4:<server>:<depot_path>:<changelist>" (Perforce)
+///   - "tfs:<collection>:<server_path>:<changeset>" (Team Foundation Server / Azure DevOps)
+///   - "svn:<repo>:<path>:<rev>"
+///
+/// The Perforce server and the TFS collection can contain a port number, e.g.
+/// "p4:perforce.example.com:1666:depot/main/foo.cpp:42", so consumers should split
+/// off the revision and the path from the end.
+///
+/// Files which are downloaded from other HTTP servers, either directly or with a
+/// download tool like curl, are mapped to their URL, which starts with "http://" or
+/// "https://". `SpecialPath::parse` turns these into a `SpecialPath::Url`, so that
+/// the source API can ask the helper to download them.
 struct SrcSrvPathMapper<'a> {
     srcsrv_stream: srcsrv::SrcSrvStream<'a>,
     cache: HashMap<String, Option<String>>,
@@ -265,6 +408,10 @@ struct SrcSrvPathMapper<'a> {
     hg_regex: Regex,
     s3_regex: Regex,
     gitiles_regex: Regex,
+    p4_regex: Regex,
+    tfs_regex: Regex,
+    svn_regex: Regex,
+    http_download_regex: Regex,
     command_is_file_download_with_url_in_var4_and_uncompress_function_in_var5: bool,
 }
 
@@ -281,10 +428,11 @@ impl<'a> ExtraPathMapper for SrcSrvPathMapper<'a> {
             Ok(Some((srcsrv::SourceRetrievalMethod::Download { url }, _map))) => {
                 Some(self.url_to_special_path(&url))
             }
-            Ok(Some((srcsrv::SourceRetrievalMethod::ExecuteCommand { .. }, map))) => {
+            Ok(Some((srcsrv::SourceRetrievalMethod::ExecuteCommand { command, .. }, map))) => {
                 // We're not going to execute a command here.
-                // Instead, we have special handling for a few known cases (well, only one case for now).
+                // Instead, we have special handling for a few known cases.
                 self.gitiles_to_special_path(&map)
+                    .or_else(|| self.command_to_special_path(&command, &map))
             }
             _ => None,
         };
@@ -305,6 +453,10 @@ impl<'a> SrcSrvPathMapper<'a> {
             hg_regex: Regex::new(r"^https://(?P<repo>hg\..+)/raw-file/(?P<rev>[0-9a-f]+)/(?P<path>.*)$").unwrap(),
             s3_regex: Regex::new(r"^https://(?P<bucket>[^/]+).s3.amazonaws.com/(?P<digest_and_path>.*)$").unwrap(),
             gitiles_regex: Regex::new(r"^https://(?P<repo>.+)\.git/\+/(?P<rev>[^/]+)/(?P<path>.*)\?format=TEXT$").unwrap(),
+            p4_regex: Regex::new(r##"(?i)\bp4(?:\.exe)?\s.*-p\s*"?(?P<server>[^\s"]+)"?.*\sprint\s.*//(?P<path>[^"#]+)#(?P<rev>[0-9]+)"##).unwrap(),
+            tfs_regex: Regex::new(r#"(?i)\btf(?:\.exe)?\s+view\s.*/version:(?P<rev>[^\s"]+).*"\$/(?P<path>[^"]+)".*/server:"?(?:https?://)?(?P<collection>[^\s"]+)"#).unwrap(),
+            svn_regex: Regex::new(r#"(?i)\bsvn(?:\.exe)?\s+(?:cat|export)\s.*?"?(?P<url>(?:https?|svn|svn\+ssh)://[^\s"@]+)@(?P<rev>[0-9]+)"#).unwrap(),
+            http_download_regex: Regex::new(r#"(?i)\b(?:curl|wget|invoke-webrequest|iwr|certutil|bitsadmin)(?:\.exe)?\s.*?(?P<url>https?://[^\s"']+)"#).unwrap(),
             command_is_file_download_with_url_in_var4_and_uncompress_function_in_var5,
         }
     }
@@ -372,6 +524,42 @@ impl<'a> SrcSrvPathMapper<'a> {
         Some(format!("git:{}:{}:{}", repo, path, rev))
     }
 
+    /// Recognizes the commands which the srcsrv indexing scripts for Perforce, TFS and
+    /// Subversion generate, and commands which download the file from an HTTP URL.
+    /// `command` has already been evaluated, i.e. it doesn't contain variables anymore.
+    fn command_to_special_path(
+        &self,
+        command: &str,
+        map: &HashMap<String, String>,
+    ) -> Option<String> {
+        if let Some(captures) = self.p4_regex.captures(command) {
+            // p4.exe -p perforce.example.com:1666 print -o "C:\src\main\foo.cpp" -q "//depot/main/foo.cpp#42"
+            // -> "p4:perforce.example.com:1666:depot/main/foo.cpp:42"
+            let server = captures.name("server").unwrap().as_str();
+            let path = captures.name("path").unwrap().as_str();
+            let rev = captures.name("rev").unwrap().as_str();
+            Some(format!("p4:{}:{}:{}", server, path, rev))
+        } else if let Some(captures) = self.tfs_regex.captures(command) {
+            // tf.exe view /version:1234 /noprompt "$/Project/Main/foo.cpp" /server:http://tfs.example.com:8080/tfs/DefaultCollection /console >"C:\src\foo.cpp"
+            // -> "tfs:tfs.example.com:8080/tfs/DefaultCollection:Project/Main/foo.cpp:1234"
+            let collection = captures.name("collection").unwrap().as_str();
+            let path = captures.name("path").unwrap().as_str();
+            let rev = captures.name("rev").unwrap().as_str();
+            Some(format!("tfs:{}:{}:{}", collection, path, rev))
+        } else if let Some(captures) = self.svn_regex.captures(command) {
+            // svn.exe cat "https://svn.example.com/repos/project/trunk/foo.cpp@123" --non-interactive
+            // -> "svn:svn.example.com/repos/project:trunk/foo.cpp:123"
+            let url = captures.name("url").unwrap().as_str();
+            let rev = captures.name("rev").unwrap().as_str();
+            let (repo, path) = svn_repo_and_path(url, map);
+            let repo = repo.split_once("://").map_or(repo, |(_scheme, repo)| repo);
+            Some(format!("svn:{}:{}:{}", repo, path, rev))
+        } else {
+            let captures = self.http_download_regex.captures(command)?;
+            Some(self.url_to_special_path(captures.name("url").unwrap().as_str()))
+        }
+    }
+
     fn url_to_special_path(&self, url: &str) -> String {
         if let Some(captures) = self.github_regex.captures(url) {
             // https://raw.githubusercontent.com/baldurk/renderdoc/v1.15/renderdoc/data/glsl/gl_texsample.h
@@ -399,6 +587,29 @@ impl<'a> SrcSrvPathMapper<'a> {
     }
 }
 
+/// Splits an svn URL into the URL of the repository root and the path in the
+/// repository. The indexing script stores the repository root in one variable and
+/// the path in another, so if one of the variables is a prefix of the URL, it is
+/// the repository root. Otherwise, the host is treated as the repository.
+fn svn_repo_and_path<'u>(url: &'u str, map: &HashMap<String, String>) -> (&'u str, &'u str) {
+    let repo_len = map
+        .values()
+        .map(|value| value.trim_end_matches('/'))
+        .filter(|value| {
+            url.len() > value.len() && url.starts_with(value) && url[value.len()..].starts_with('/')
+        })
+        .map(str::len)
+        .max()
+        .or_else(|| {
+            let host_start = url.find("://")? + 3;
+            Some(host_start + url[host_start..].find('/')?)
+        });
+    match repo_len {
+        Some(repo_len) => (&url[..repo_len], &url[repo_len + 1..]),
+        None => (url, ""),
+    }
+}
+
 fn has_debug_info(func: &pdb_addr2line::FunctionFrames) -> bool {
     if func.frames.len() > 1 {
         true
@@ -444,6 +655,567 @@ impl<'s, F: FileContents> pdb::Source<'s> for &'s FileContentsWrapper<F> {
     }
 }
 
+/// Get the sections of the executable from the section headers in the PDB.
+fn pdb_section_infos<'s, S: pdb::Source<'s> + 's>(pdb: &mut PDB<'s, S>) -> Vec<SectionInfo> {
+    const IMAGE_SCN_CNT_CODE: u32 = 0x20;
+    const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+    let section_headers = match pdb.sections() {
+        Ok(Some(section_headers)) => section_headers,
+        _ => return Vec::new(),
+    };
+    section_headers
+        .iter()
+        .filter_map(|header| {
+            Some(SectionInfo {
+                start: header.virtual_address,
+                // The "physical address" field is the virtual size for executable images.
+                // Skip sections whose end doesn't fit in 32 bits; the headers are corrupt.
+                end: header
+                    .virtual_address
+                    .checked_add(header.physical_address)?,
+                name: header.name().to_string(),
+                is_executable: header.characteristics
+                    & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE)
+                    != 0,
+            })
+        })
+        .collect()
+}
+
+fn pdb_address_symbol_info<'a>(
+    sections: &'a [SectionInfo],
+    address: u32,
+    symbol_address: u32,
+    symbol_name: &'a str,
+    function_size: Option<u32>,
+) -> AddressSymbolInfo<'a> {
+    let section = SectionInfo::find(sections, address);
+    AddressSymbolInfo {
+        symbol_address,
+        symbol_name,
+        function_size,
+        is_split_function_part: false,
+        source: SymbolSource::Pdb,
+        section_name: section.map(|section| section.name.as_str()),
+        is_outside_text: !matches!(section, Some(section) if section.is_executable),
+    }
+}
+
+/// Returns the names of the source files which are used by the modules that contain
+/// the given addresses, as they are stored in the PDB, together with their checksums.
+/// Only those modules are parsed, so that we don't need to read the line info of the
+/// entire PDB.
+fn pdb_source_files<'s, S: pdb::Source<'s> + 's>(
+    pdb: &mut PDB<'s, S>,
+    addresses: &[u32],
+) -> Result<HashMap<String, Option<SourceFileChecksum>>> {
+    use pdb::FallibleIterator;
+    let address_map = pdb.address_map().context("address_map")?;
+    let string_table = pdb.string_table().context("string_table")?;
+    let dbi = pdb.debug_information().context("debug_information")?;
+    let modules: Vec<_> = dbi
+        .modules()
+        .context("modules")?
+        .collect()
+        .context("module iteration")?;
+    let mut contributions: Vec<_> = dbi
+        .section_contributions()
+        .context("section_contributions")?
+        .collect()
+        .context("section contribution iteration")?;
+    contributions
+        .sort_by_key(|contribution| (contribution.offset.section, contribution.offset.offset));
+
+    let mut module_indexes: Vec<usize> = addresses
+        .iter()
+        .filter_map(|&address| {
+            let offset = pdb::Rva(address).to_internal_offset(&address_map)?;
+            let key = (offset.section, offset.offset);
+            let index = contributions
+                .partition_point(|c| (c.offset.section, c.offset.offset) <= key)
+                .checked_sub(1)?;
+            let contribution = &contributions[index];
+            if contribution.offset.section == offset.section
+                && offset.offset - contribution.offset.offset < contribution.size
+            {
+                Some(usize::from(contribution.module))
+            } else {
+                None
+            }
+        })
+        .collect();
+    module_indexes.sort_unstable();
+    module_indexes.dedup();
+
+    let mut source_files = HashMap::new();
+    for module_index in module_indexes {
+        let module_info = match modules.get(module_index) {
+            Some(module) => pdb.module_info(module).context("module_info")?,
+            None => None,
+        };
+        let module_info = match module_info {
+            Some(module_info) => module_info,
+            None => continue,
+        };
+        let line_program = module_info.line_program().context("line_program")?;
+        let mut files = line_program.files();
+        while let Some(file) = files.next().context("file iteration")? {
+            let checksum = match file.checksum {
+                pdb::FileChecksum::Md5(bytes) => bytes.try_into().ok().map(SourceFileChecksum::Md5),
+                pdb::FileChecksum::Sha1(bytes) => {
+                    bytes.try_into().ok().map(SourceFileChecksum::Sha1)
+                }
+                pdb::FileChecksum::Sha256(bytes) => {
+                    bytes.try_into().ok().map(SourceFileChecksum::Sha256)
+                }
+                pdb::FileChecksum::None => None,
+            };
+            let name = file
+                .name
+                .to_string_lossy(&string_table)
+                .context("file name")?;
+            source_files.insert(name.into_owned(), checksum);
+        }
+    }
+    Ok(source_files)
+}
+
+/// Returns the contents of the source files in `source_files` which are injected
+/// into the PDB, keyed by the name in `source_files`.
+///
+/// The "/src/headerblock" stream has a hash table with an entry for each injected
+/// file, and the contents of each file are in a "/src/files/<lowercase virtual name>"
+/// stream. Compressed files are not supported.
+fn pdb_embedded_sources<'s, S: pdb::Source<'s> + 's>(
+    pdb: &mut PDB<'s, S>,
+    source_files: &HashMap<String, Option<SourceFileChecksum>>,
+) -> Result<HashMap<String, String>> {
+    let mut embedded_sources = HashMap::new();
+    let header_block = match pdb.named_stream(b"/src/headerblock") {
+        Ok(stream) => stream,
+        Err(pdb::Error::StreamNameNotFound | pdb::Error::StreamNotFound(_)) => {
+            return Ok(embedded_sources)
+        }
+        Err(e) => {
+            return Err(GetSymbolsError::PdbError(
+                "pdb.named_stream(headerblock)",
+                e,
+            ))
+        }
+    };
+    let injected_sources = parse_injected_sources(header_block.as_slice());
+    if injected_sources.is_empty() {
+        return Ok(embedded_sources);
+    }
+
+    // File names are compared case-insensitively, like Windows does.
+    let source_files: HashMap<String, &String> = source_files
+        .keys()
+        .map(|name| (name.to_lowercase(), name))
+        .collect();
+    let string_table = pdb.string_table().context("string_table")?;
+    for injected_source in injected_sources {
+        let file_name = string_table
+            .get(pdb::StringRef(injected_source.file_name))
+            .context("injected source file name")?;
+        let source_file = match source_files.get(&file_name.to_string().to_lowercase()) {
+            Some(source_file) => *source_file,
+            None => continue,
+        };
+        if injected_source.compression != 0 {
+            continue;
+        }
+        let virtual_file_name = string_table
+            .get(pdb::StringRef(injected_source.virtual_file_name))
+            .context("injected source virtual file name")?;
+        let stream_name = format!(
+            "/src/files/{}",
+            virtual_file_name.to_string().to_lowercase()
+        );
+        let stream = match pdb.named_stream(stream_name.as_bytes()) {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        embedded_sources.insert(
+            source_file.clone(),
+            String::from_utf8_lossy(stream.as_slice()).into_owned(),
+        );
+    }
+    Ok(embedded_sources)
+}
+
+/// An entry in the "/src/headerblock" stream of a PDB file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InjectedSource {
+    /// The string table offset of the original file name.
+    file_name: u32,
+    /// The string table offset of the name which the contents are stored under.
+    virtual_file_name: u32,
+    /// 0 if the contents are stored uncompressed.
+    compression: u8,
+}
+
+/// Parses the "/src/headerblock" stream. It consists of a 64 byte header followed
+/// by a serialized hash table: the size and capacity, a bit vector of the present
+/// buckets, a bit vector of the deleted buckets, and then a key and a 40 byte value
+/// for each present bucket. Returns an empty list if the stream is malformed.
+fn parse_injected_sources(data: &[u8]) -> Vec<InjectedSource> {
+    let read_u32 = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let mut injected_sources = Vec::new();
+    let mut offset = 64;
+    let capacity = match read_u32(offset + 4) {
+        Some(capacity) => capacity,
+        None => return injected_sources,
+    };
+    offset += 8;
+    let mut present_words = Vec::new();
+    for bit_vector_index in 0..2 {
+        let word_count = match read_u32(offset) {
+            Some(word_count) => word_count as usize,
+            None => return injected_sources,
+        };
+        offset += 4;
+        for word_index in 0..word_count {
+            let word = match read_u32(offset + word_index * 4) {
+                Some(word) => word,
+                None => return Vec::new(),
+            };
+            if bit_vector_index == 0 {
+                present_words.push(word);
+            }
+        }
+        offset += word_count * 4;
+    }
+    for bucket in 0..capacity as usize {
+        let is_present = matches!(
+            present_words.get(bucket / 32),
+            Some(word) if word & (1 << (bucket % 32)) != 0
+        );
+        if !is_present {
+            continue;
+        }
+        // Skip the key, which is the string table offset of the lowercased virtual
+        // file name.
+        let entry = offset + 4;
+        let (file_name, virtual_file_name, compression) = match (
+            read_u32(entry + 16),
+            read_u32(entry + 24),
+            data.get(entry + 28),
+        ) {
+            (Some(file_name), Some(virtual_file_name), Some(compression)) => {
+                (file_name, virtual_file_name, *compression)
+            }
+            _ => return Vec::new(),
+        };
+        injected_sources.push(InjectedSource {
+            file_name,
+            virtual_file_name,
+            compression,
+        });
+        offset += 4 + 40;
+    }
+    injected_sources
+}
+
+/// Collect the global and static variables, including thread-local variables, from
+/// the global symbols stream of the PDB. Their sizes are computed from their types.
+fn pdb_data_symbols<'s, S: pdb::Source<'s> + 's>(pdb: &mut PDB<'s, S>) -> Result<Vec<DataSymbol>> {
+    use pdb::FallibleIterator;
+    let address_map = pdb.address_map().context("address_map")?;
+    let type_information = pdb.type_information().context("type_information")?;
+    let mut type_finder = type_information.finder();
+
+    // Variables of class type often refer to a forward declaration of the class,
+    // which has no size. Remember the sizes of the full definitions, so that
+    // forward references can be resolved by name.
+    let mut type_sizes_by_name = HashMap::new();
+    let mut types = type_information.iter();
+    while let Some(item) = types.next().context("type iteration")? {
+        type_finder.update(&types);
+        let (properties, name, unique_name, size) = match item.parse() {
+            Ok(pdb::TypeData::Class(class)) => (
+                class.properties,
+                class.name,
+                class.unique_name,
+                u32::from(class.size),
+            ),
+            Ok(pdb::TypeData::Union(union)) => {
+                (union.properties, union.name, union.unique_name, union.size)
+            }
+            _ => continue,
+        };
+        if !properties.forward_reference() {
+            type_sizes_by_name.insert(unique_name.unwrap_or(name), size);
+        }
+    }
+
+    let mut data_symbols = Vec::new();
+    let global_symbols = pdb.global_symbols().context("global_symbols")?;
+    let mut symbols = global_symbols.iter();
+    while let Some(symbol) = symbols.next().context("global symbol iteration")? {
+        let (global, type_index, offset, name) = match symbol.parse() {
+            Ok(pdb::SymbolData::Data(data)) => {
+                (data.global, data.type_index, data.offset, data.name)
+            }
+            Ok(pdb::SymbolData::ThreadStorage(thread_storage)) => (
+                thread_storage.global,
+                thread_storage.type_index,
+                thread_storage.offset,
+                thread_storage.name,
+            ),
+            _ => continue,
+        };
+        let address = match offset.to_rva(&address_map) {
+            Some(rva) => rva.0,
+            None => continue,
+        };
+        let size = pdb_type_size(&type_finder, &type_sizes_by_name, type_index, 0);
+        data_symbols.push((
+            !global,
+            DataSymbol {
+                address,
+                size,
+                name: name.to_string().into_owned(),
+                source: SymbolSource::Pdb,
+            },
+        ));
+    }
+    // Prefer global variables over file-static variables at the same address.
+    data_symbols.sort_by_key(|(is_local, _)| *is_local);
+    Ok(data_symbols
+        .into_iter()
+        .map(|(_, data_symbol)| data_symbol)
+        .collect())
+}
+
+/// Returns the size in bytes of the type with the index `type_index`.
+fn pdb_type_size(
+    type_finder: &pdb::TypeFinder,
+    type_sizes_by_name: &HashMap<pdb::RawString, u32>,
+    type_index: pdb::TypeIndex,
+    depth: u32,
+) -> Option<u32> {
+    if depth > 16 {
+        return None;
+    }
+    let size = match type_finder.find(type_index).ok()?.parse().ok()? {
+        pdb::TypeData::Primitive(primitive) => match primitive.indirection {
+            Some(indirection) => pdb_pointer_size(indirection),
+            None => pdb_primitive_size(primitive.kind)?,
+        },
+        pdb::TypeData::Class(class) if class.properties.forward_reference() => {
+            *type_sizes_by_name.get(&class.unique_name.unwrap_or(class.name))?
+        }
+        pdb::TypeData::Class(class) => u32::from(class.size),
+        pdb::TypeData::Union(union) if union.properties.forward_reference() => {
+            *type_sizes_by_name.get(&union.unique_name.unwrap_or(union.name))?
+        }
+        pdb::TypeData::Union(union) => union.size,
+        pdb::TypeData::Enumeration(enumeration) => pdb_type_size(
+            type_finder,
+            type_sizes_by_name,
+            enumeration.underlying_type,
+            depth + 1,
+        )?,
+        pdb::TypeData::Modifier(modifier) => pdb_type_size(
+            type_finder,
+            type_sizes_by_name,
+            modifier.underlying_type,
+            depth + 1,
+        )?,
+        pdb::TypeData::Pointer(pointer) => u32::from(pointer.attributes.size()),
+        // The array dimensions are byte sizes, and the last one is the size of the
+        // entire array.
+        pdb::TypeData::Array(array) => *array.dimensions.last()?,
+        _ => return None,
+    };
+    if size == 0 {
+        None
+    } else {
+        Some(size)
+    }
+}
+
+fn pdb_pointer_size(indirection: pdb::Indirection) -> u32 {
+    match indirection {
+        pdb::Indirection::Near16 => 2,
+        pdb::Indirection::Far16 | pdb::Indirection::Huge16 | pdb::Indirection::Near32 => 4,
+        pdb::Indirection::Far32 => 6,
+        pdb::Indirection::Near64 => 8,
+        pdb::Indirection::Near128 => 16,
+    }
+}
+
+fn pdb_primitive_size(kind: pdb::PrimitiveKind) -> Option<u32> {
+    use pdb::PrimitiveKind::*;
+    let size = match kind {
+        Char | UChar | RChar | I8 | U8 | Bool8 => 1,
+        WChar | RChar16 | Short | UShort | I16 | U16 | F16 | Bool16 => 2,
+        RChar32 | Long | ULong | I32 | U32 | F32 | F32PP | Bool32 | HRESULT => 4,
+        F48 => 6,
+        Quad | UQuad | I64 | U64 | F64 | Bool64 | Complex32 => 8,
+        F80 => 10,
+        Octa | UOcta | I128 | U128 | F128 | Complex64 => 16,
+        Complex80 => 20,
+        Complex128 => 32,
+        _ => return None,
+    };
+    Some(size)
+}
+
+/// Synthesize symbols of the form "[thunk] CreateFileW" for import thunks.
+///
+/// When a function from a different DLL is called without `__declspec(dllimport)`,
+/// the linker generates a thunk for it, which consists of a single instruction:
+/// `jmp [iat_entry]`, with the address of the function's entry in the import address
+/// table. We find these thunks by looking for such instructions in the code sections
+/// and checking whether they jump via a known import address table entry.
+///
+/// This is only used for binaries without a PDB file; `get_symbolication_result` only
+/// has the PDB, which has its own public symbols for the thunks.
+fn import_thunk_symbols<'data, Pe, R>(pe: &PeFile<'data, Pe, R>) -> Vec<StubSymbol>
+where
+    Pe: ImageNtHeaders,
+    R: ReadRef<'data>,
+{
+    use object::{Object, ObjectSection, SectionKind};
+
+    let import_table = match pe.import_table() {
+        Ok(Some(import_table)) => import_table,
+        _ => return Vec::new(),
+    };
+    let mut descriptors = match import_table.descriptors() {
+        Ok(descriptors) => descriptors,
+        Err(_) => return Vec::new(),
+    };
+    let iat_entry_size = std::mem::size_of::<Pe::ImageThunkData>() as u32;
+    let mut import_names_by_iat_rva = HashMap::new();
+    while let Ok(Some(descriptor)) = descriptors.next() {
+        // The import lookup table has the names. The import address table (at
+        // first_thunk) has the same layout, but may already be bound to addresses.
+        let iat_rva = descriptor.first_thunk.get(LE);
+        let lookup_table_rva = match descriptor.original_first_thunk.get(LE) {
+            0 => iat_rva,
+            rva => rva,
+        };
+        let mut thunks = match import_table.thunks(lookup_table_rva) {
+            Ok(thunks) => thunks,
+            Err(_) => continue,
+        };
+        let mut entry_rva = iat_rva;
+        while let Ok(Some(thunk)) = thunks.next::<Pe>() {
+            if let Ok(Import::Name(_, name)) = import_table.import::<Pe>(thunk) {
+                if let Ok(name) = std::str::from_utf8(name) {
+                    import_names_by_iat_rva.insert(entry_rva, name);
+                }
+            }
+            entry_rva += iat_entry_size;
+        }
+    }
+    if import_names_by_iat_rva.is_empty() {
+        return Vec::new();
+    }
+
+    let image_base = pe.relative_address_base();
+    let is_64 = pe.is_64();
+    pe.sections()
+        .filter(|section| section.kind() == SectionKind::Text)
+        .flat_map(|section| {
+            let code_rva = (section.address() - image_base) as u32;
+            match section.data() {
+                Ok(code) => {
+                    find_import_thunks(code, code_rva, image_base, is_64, &import_names_by_iat_rva)
+                }
+                Err(_) => Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Find `jmp [iat_entry]` instructions (FF 25 followed by a 32-bit operand) whose
+/// jump target is read from an entry in `import_names_by_iat_rva`. On x86-64, the
+/// operand is relative to the end of the instruction; on x86, it is an absolute address.
+///
+/// The same instruction is also used for tail calls to imported functions, at the end
+/// of regular functions. To tell those apart from thunks, we only accept instructions
+/// which are right before or after another thunk, or after int3 padding. Runs of
+/// thunks are walked in thunk-sized steps, so the rest of the code only needs a quick
+/// search for the instruction's first two bytes.
+fn find_import_thunks(
+    code: &[u8],
+    code_rva: u32,
+    image_base: u64,
+    is_64: bool,
+    import_names_by_iat_rva: &HashMap<u32, &str>,
+) -> Vec<StubSymbol> {
+    const THUNK_SIZE: usize = 6;
+    let (min_iat_rva, max_iat_rva) = match (
+        import_names_by_iat_rva.keys().min(),
+        import_names_by_iat_rva.keys().max(),
+    ) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return Vec::new(),
+    };
+    let thunk_name_at = |offset: usize| -> Option<&str> {
+        let instruction = code.get(offset..offset + THUNK_SIZE)?;
+        if instruction[0] != 0xff || instruction[1] != 0x25 {
+            return None;
+        }
+        let operand = u32::from_le_bytes([
+            instruction[2],
+            instruction[3],
+            instruction[4],
+            instruction[5],
+        ]);
+        let iat_rva = if is_64 {
+            let next_instruction_rva =
+                code_rva.checked_add(u32::try_from(offset + THUNK_SIZE).ok()?)?;
+            next_instruction_rva.wrapping_add(operand)
+        } else {
+            u32::try_from(u64::from(operand).checked_sub(image_base)?).ok()?
+        };
+        if iat_rva < min_iat_rva || iat_rva > max_iat_rva {
+            return None;
+        }
+        import_names_by_iat_rva.get(&iat_rva).copied()
+    };
+
+    let mut thunks = Vec::new();
+    let mut search_start = 0;
+    while let Some(found) = memchr::memmem::find(&code[search_start..], &[0xff, 0x25]) {
+        let block_start = search_start + found;
+        let mut block_end = block_start;
+        while thunk_name_at(block_end).is_some() {
+            block_end += THUNK_SIZE;
+        }
+        let thunk_count = (block_end - block_start) / THUNK_SIZE;
+        let is_after_padding = block_start == 0 || code[block_start - 1] == 0xcc;
+        if thunk_count == 0 || (thunk_count == 1 && !is_after_padding) {
+            search_start = block_start + 1;
+            continue;
+        }
+        search_start = block_end;
+        for offset in (block_start..block_end).step_by(THUNK_SIZE) {
+            let address = match u32::try_from(offset)
+                .ok()
+                .and_then(|offset| code_rva.checked_add(offset))
+            {
+                Some(address) => address,
+                None => break,
+            };
+            thunks.push(StubSymbol {
+                address,
+                size: THUNK_SIZE as u32,
+                name: format!("[thunk] {}", thunk_name_at(offset).unwrap()),
+            });
+        }
+    }
+    thunks
+}
+
 /// Get the function start addresses (in rva form) from the .pdata section.
 /// This section has the addresses for functions with unwind info. That means
 /// it only covers a subset of functions; it does not include entries for
@@ -459,3 +1231,167 @@ fn function_start_and_end_addresses(pdata: &[u8]) -> (Vec<u32>, Vec<u32>) {
     }
     (start_addresses, end_addresses)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{find_import_thunks, parse_injected_sources, InjectedSource, SrcSrvPathMapper};
+    use crate::path_mapper::ExtraPathMapper;
+    use crate::shared::SpecialPath;
+    use std::collections::HashMap;
+
+    #[test]
+    fn import_thunks() {
+        let import_names_by_iat_rva: HashMap<u32, &str> =
+            [(0x3000, "CreateFileW"), (0x3008, "CloseHandle")]
+                .iter()
+                .cloned()
+                .collect();
+
+        // Two x86-64 thunks at 0x1002 and 0x1008, with rip-relative operands, a jump
+        // to something that's not in the import address table at 0x100e, and a tail
+        // call at the end of a function (after a `ret`) at 0x1015.
+        let code = [
+            0xcc, 0xcc, 0xff, 0x25, 0xf8, 0x1f, 0x00, 0x00, 0xff, 0x25, 0xfa, 0x1f, 0x00, 0x00,
+            0xff, 0x25, 0x00, 0x00, 0x00, 0x00, 0xc3, 0xff, 0x25, 0xe5, 0x1f, 0x00, 0x00,
+        ];
+        let thunks =
+            find_import_thunks(&code, 0x1000, 0x1_4000_0000, true, &import_names_by_iat_rva);
+        let thunks: Vec<_> = thunks
+            .iter()
+            .map(|thunk| (thunk.address, thunk.name.as_str()))
+            .collect();
+        assert_eq!(
+            thunks,
+            vec![
+                (0x1002, "[thunk] CreateFileW"),
+                (0x1008, "[thunk] CloseHandle")
+            ]
+        );
+
+        // An x86 thunk at 0x1000, with an absolute operand.
+        let code = [0xff, 0x25, 0x08, 0x30, 0x40, 0x00];
+        let thunks = find_import_thunks(&code, 0x1000, 0x40_0000, false, &import_names_by_iat_rva);
+        assert_eq!(thunks.len(), 1);
+        assert_eq!(thunks[0].address, 0x1000);
+        assert_eq!(thunks[0].name, "[thunk] CloseHandle");
+    }
+
+    #[test]
+    fn injected_sources() {
+        let mut data = vec![0u8; 64];
+        let mut push_u32 = |value: u32| data.extend_from_slice(&value.to_le_bytes());
+        // Size and capacity of the hash table, then one present bucket (the second
+        // one) and no deleted buckets.
+        push_u32(1);
+        push_u32(3);
+        push_u32(1);
+        push_u32(0b10);
+        push_u32(0);
+        // The key, and the entry: size, version, CRC, file size, file name, object
+        // name, virtual file name, compression and padding.
+        push_u32(0x30);
+        push_u32(40);
+        push_u32(0x1313_1313);
+        push_u32(0xdead_beef);
+        push_u32(120);
+        push_u32(0x10);
+        push_u32(0x20);
+        push_u32(0x30);
+        push_u32(0);
+        push_u32(0);
+        push_u32(0);
+        assert_eq!(
+            parse_injected_sources(&data),
+            vec![InjectedSource {
+                file_name: 0x10,
+                virtual_file_name: 0x30,
+                compression: 0,
+            }]
+        );
+        assert_eq!(parse_injected_sources(&data[..data.len() - 20]), vec![]);
+        assert_eq!(parse_injected_sources(&[]), vec![]);
+    }
+
+    #[test]
+    fn srcsrv_commands() {
+        let perforce = br#"SRCSRV: ini ------------------------------------------------
+VERSION=1
+VERCTRL=Perforce
+SRCSRV: variables ------------------------------------------
+MYSERVER=perforce.example.com:1666
+P4_EXTRACT_TARGET=%targ%\%fnbksl%(%var3%)\%var4%\%fnfile%(%var1%)
+P4_EXTRACT_CMD=p4.exe -p %fnvar%(%var2%) print -o %srcsrvtrg% -q "//%var3%#%var4%"
+SRCSRVTRG=%p4_extract_target%
+SRCSRVCMD=%p4_extract_cmd%
+SRCSRV: source files ---------------------------------------
+c:\src\main\foo.cpp*MYSERVER*depot/main/foo.cpp*42
+SRCSRV: end ------------------------------------------------
+"#;
+        let mut mapper = SrcSrvPathMapper::new(srcsrv::SrcSrvStream::parse(perforce).unwrap());
+        assert_eq!(
+            mapper.map_path(r"c:\src\main\foo.cpp"),
+            Some("p4:perforce.example.com:1666:depot/main/foo.cpp:42".to_string())
+        );
+        assert_eq!(mapper.map_path(r"c:\src\main\bar.cpp"), None);
+
+        let tfs = br#"SRCSRV: ini ------------------------------------------------
+VERSION=3
+VERCTRL=Team Foundation Server
+SRCSRV: variables ------------------------------------------
+TFS_EXTRACT_TARGET=%targ%\%var2%\%fnfile%(%var1%)
+TFS_EXTRACT_CMD=tf.exe view /version:%var4% /noprompt "$%var3%" /server:%fnvar%(%var2%) /console >%srcsrvtrg%
+SRCSRVTRG=%tfs_extract_target%
+SRCSRVCMD=%tfs_extract_cmd%
+VSTFSSERVER=http://tfs.example.com:8080/tfs/DefaultCollection
+SRCSRV: source files ---------------------------------------
+c:\build\src\foo.cs*VSTFSSERVER*/Project/Main/src/foo.cs*1234
+SRCSRV: end ------------------------------------------------
+"#;
+        let mut mapper = SrcSrvPathMapper::new(srcsrv::SrcSrvStream::parse(tfs).unwrap());
+        assert_eq!(
+            mapper.map_path(r"c:\build\src\foo.cs"),
+            Some(
+                "tfs:tfs.example.com:8080/tfs/DefaultCollection:Project/Main/src/foo.cs:1234"
+                    .to_string()
+            )
+        );
+
+        let svn = br#"SRCSRV: ini ------------------------------------------------
+VERSION=1
+VERCTRL=Subversion
+SRCSRV: variables ------------------------------------------
+SVN_EXTRACT_TARGET=%targ%\%fnbksl%(%var3%)\%var4%\%fnfile%(%var1%)
+SVN_EXTRACT_CMD=cmd /c svn.exe cat "%var2%%var3%@%var4%" --non-interactive > "%svn_extract_target%"
+SRCSRVTRG=%svn_extract_target%
+SRCSRVCMD=%svn_extract_cmd%
+SRCSRV: source files ---------------------------------------
+c:\src\trunk\foo.c*https://svn.example.com/repos/project/*trunk/foo.c*123
+SRCSRV: end ------------------------------------------------
+"#;
+        let mut mapper = SrcSrvPathMapper::new(srcsrv::SrcSrvStream::parse(svn).unwrap());
+        assert_eq!(
+            mapper.map_path(r"c:\src\trunk\foo.c"),
+            Some("svn:svn.example.com/repos/project:trunk/foo.c:123".to_string())
+        );
+
+        let curl = br#"SRCSRV: ini ------------------------------------------------
+VERSION=2
+VERCTRL=http
+SRCSRV: variables ------------------------------------------
+HTTP_EXTRACT_TARGET=%targ%\%var2%\%fnfile%(%var1%)
+HTTP_EXTRACT_CMD=curl.exe -sSfL -o "%http_extract_target%" "https://sources.example.com/%var2%/%var3%"
+SRCSRVTRG=%http_extract_target%
+SRCSRVCMD=%http_extract_cmd%
+SRCSRV: source files ---------------------------------------
+c:\src\foo.c*0123abcd*src/foo.c
+SRCSRV: end ------------------------------------------------
+"#;
+        let mut mapper = SrcSrvPathMapper::new(srcsrv::SrcSrvStream::parse(curl).unwrap());
+        let url = "https://sources.example.com/0123abcd/src/foo.c";
+        assert_eq!(mapper.map_path(r"c:\src\foo.c"), Some(url.to_string()));
+        assert_eq!(
+            SpecialPath::parse(url),
+            Some(SpecialPath::Url(url.to_string()))
+        );
+    }
+}
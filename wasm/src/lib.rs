@@ -19,7 +19,10 @@ extern "C" {
     /// The strings in the array can be either
     ///   - The path to a binary, or
     ///   - a special string with the syntax "dyldcache:<dyld_cache_path>:<dylib_path>"
-    ///     for libraries that are in the dyld shared cache.
+    ///     for libraries that are in the dyld shared cache, or
+    ///   - a special string with the syntax "dyldcache:<dyld_cache_path>" for libraries
+    ///     that are in the dyld shared cache at an unknown path. The library is found
+    ///     by its debug ID.
     #[wasm_bindgen(catch, method)]
     fn getCandidatePathsForBinaryOrPdb(
         this: &FileAndPathHelper,
@@ -282,6 +285,7 @@ fn get_candidate_paths_for_binary_or_pdb_impl(
         .filter_map(|val| val.as_string())
         .map(|s| {
             // Support special syntax "dyldcache:<dyld_cache_path>:<dylib_path>"
+            // and "dyldcache:<dyld_cache_path>"
             if let Some(remainder) = s.strip_prefix("dyldcache:") {
                 if let Some(offset) = remainder.find(':') {
                     let dyld_cache_path = &remainder[0..offset];
@@ -291,6 +295,9 @@ fn get_candidate_paths_for_binary_or_pdb_impl(
                         dylib_path: dylib_path.into(),
                     };
                 }
+                return profiler_get_symbols::CandidatePathInfo::InDyldCacheByDebugId {
+                    dyld_cache_path: remainder.into(),
+                };
             }
             profiler_get_symbols::CandidatePathInfo::SingleFile(FileLocation::Path(s.into()))
         })